@@ -24,19 +24,19 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn writer(map: SubscriptionMap<Id, State>, observed_id: Id) -> anyhow::Result<()> {
-    let mut entry = map.get_or_insert(observed_id, 0).await;
+    let mut entry = map.get_or_insert(observed_id, 0).await?;
     let mut state = 0;
 
     loop {
         log::info!("writer: state change: {}", state);
-        entry.publish_if_changed(state);
+        entry.publish_if_changed(state)?;
         state = state.wrapping_add(1);
         task::sleep(Duration::from_millis(10)).await;
     }
 }
 
 async fn reader(map: SubscriptionMap<Id, State>, observed_id: Id) -> anyhow::Result<()> {
-    let mut entry = map.get_or_insert(observed_id, 0).await;
+    let mut entry = map.get_or_insert(observed_id, 0).await?;
 
     loop {
         let update = entry.next().await;
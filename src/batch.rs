@@ -0,0 +1,95 @@
+use crate::{Error, SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Same as [`get_or_insert`](Self::get_or_insert) for many keys at once.
+    ///
+    /// All refs are created under a single lock acquisition, which avoids a lock round trip per
+    /// key when subscribing to a large set of keys, e.g. on session startup. The refs are
+    /// returned in the order of the pairs. If any of them can't be created, all refs created so
+    /// far are dropped again.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let subscriptions = map
+    ///     .get_or_insert_many((0..500).map(|key| (key, 0)))
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// assert_eq!(subscriptions.len(), 500);
+    /// # };
+    /// ```
+    pub async fn get_or_insert_many<I>(
+        &self,
+        pairs: I,
+    ) -> anyhow::Result<Vec<SubscriptionRef<K, V>>>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let pairs = pairs.into_iter();
+        let entries = self.0.entries();
+        let mut map = self.lock_entries(&entries).await;
+
+        if self.0.draining.load(Ordering::SeqCst) {
+            return Err(Error::Draining).context("unable to subscribe to many keys");
+        }
+
+        let mut refs = Vec::with_capacity(pairs.size_hint().0);
+
+        for (key, value) in pairs {
+            match self.attach(&entries, &mut map, key, value) {
+                Ok(subscription) => refs.push(subscription),
+                Err(e) => {
+                    // dropping the refs created so far requires the lock
+                    drop(map);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(refs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+
+    #[async_std::test]
+    async fn should_subscribe_to_many_keys() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let _existing = map.get_or_insert(1, 10).await.unwrap();
+
+        let refs = map
+            .get_or_insert_many([(1, 0), (2, 2), (2, 0)])
+            .await
+            .unwrap();
+
+        let values: Vec<_> = refs.iter().map(|r| (*r.key(), r.latest())).collect();
+        assert_eq!(values, vec![(1, 10), (2, 2), (2, 2)]);
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 2);
+        assert_eq!(map.entries_snapshot().await.get(&2).unwrap().rc(), 2);
+
+        drop(refs);
+        assert_eq!(map.entries_snapshot().await.len(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_reject_many_while_draining() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        map.drain();
+
+        let err = map.get_or_insert_many([(1, 0)]).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{diag, EntryMeta, Error, SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use async_observable::Observable;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::sync::MutexGuard;
+use std::time::{Duration, Instant};
+
+/// The health of the producers of a key, see
+/// [`circuit_breaker`](crate::SubscriptionMapBuilder::circuit_breaker).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FeedStatus {
+    /// Publishes are accepted
+    #[default]
+    Healthy,
+    /// The producers failed repeatedly, publishes are rejected until the cooldown passed and
+    /// then accepted again on probation
+    Degraded,
+}
+
+/// When the circuit breaker of a key trips and how long it stays open
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BreakerConfig {
+    pub failures: u32,
+    pub cooldown: Duration,
+}
+
+/// The failure bookkeeping of a single entry
+#[derive(Debug)]
+pub(crate) struct Breaker {
+    /// Consecutive failures since the last successful publish
+    failures: u32,
+    open_until: Option<Instant>,
+    status: Observable<FeedStatus>,
+}
+
+impl Default for Breaker {
+    fn default() -> Self {
+        Self {
+            failures: 0,
+            open_until: None,
+            status: Observable::new(FeedStatus::Healthy),
+        }
+    }
+}
+
+fn lock<V>(meta: &EntryMeta<V>) -> MutexGuard<'_, Option<Breaker>> {
+    meta.breaker.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn status_observable<V>(meta: &EntryMeta<V>) -> Observable<FeedStatus> {
+    lock(meta)
+        .get_or_insert_with(Breaker::default)
+        .status
+        .clone()
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Fail with [`Error::CircuitOpen`] while the circuit breaker of the entry is open
+    pub(crate) fn check_breaker(&self, key: &K, meta: &EntryMeta<V>) -> anyhow::Result<()> {
+        if self.0.config.circuit_breaker.is_none() {
+            return Ok(());
+        }
+
+        let now = self.0.config.clock.now();
+        let open_until = lock(meta).as_ref().and_then(|breaker| breaker.open_until);
+
+        match open_until {
+            Some(until) if until > now => Err(Error::CircuitOpen).with_context(|| {
+                format!(
+                    "unable to publish to {:?} for another {:?}",
+                    self.redacted_key(key),
+                    until - now
+                )
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Account for the outcome of a publish, rejected values count as failures of the producer
+    pub(crate) fn record_outcome<T>(
+        &self,
+        key: &K,
+        meta: &EntryMeta<V>,
+        outcome: &anyhow::Result<T>,
+    ) {
+        if self.0.config.circuit_breaker.is_none() {
+            return;
+        }
+
+        match outcome {
+            Ok(_) => self.record_success(meta),
+            Err(e) if e.downcast_ref::<Error>() == Some(&Error::ValueTooLarge) => {
+                self.record_failure(key, meta);
+            }
+            Err(_) => {}
+        }
+    }
+
+    fn record_success(&self, meta: &EntryMeta<V>) {
+        if let Some(breaker) = lock(meta).as_mut() {
+            breaker.failures = 0;
+            breaker.open_until = None;
+            breaker.status.publish_if_changed(FeedStatus::Healthy);
+        }
+    }
+
+    /// Count a failure of the producers of the entry, returns if the circuit breaker is open
+    pub(crate) fn record_failure(&self, key: &K, meta: &EntryMeta<V>) -> bool {
+        let config = match self.0.config.circuit_breaker {
+            Some(config) => config,
+            None => return false,
+        };
+
+        let now = self.0.config.clock.now();
+        let mut breaker = lock(meta);
+        let breaker = breaker.get_or_insert_with(Breaker::default);
+        breaker.failures = breaker.failures.saturating_add(1);
+
+        if breaker.failures < config.failures {
+            return false;
+        }
+
+        // failures after the cooldown trip the breaker again right away
+        if breaker.open_until.is_none_or(|until| until <= now) {
+            diag::warning!(
+                "circuit breaker of {:?} opened after {} consecutive failures",
+                self.redacted_key(key),
+                breaker.failures
+            );
+
+            breaker.open_until = Some(now + config.cooldown);
+            breaker.status.publish_if_changed(FeedStatus::Degraded);
+        }
+
+        true
+    }
+}
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Report that producing a value for the entry failed.
+    ///
+    /// Consecutive failures, including values rejected by the map, trip the
+    /// [circuit breaker](crate::SubscriptionMapBuilder::circuit_breaker) of the key. Returns if it
+    /// is open, always `false` without a configured breaker.
+    ///
+    /// ```
+    /// # use async_subscription_map::{Error, FeedStatus, SubscriptionMap};
+    /// # use std::time::Duration;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, f64>::builder()
+    ///     .circuit_breaker(3, Duration::from_secs(30))
+    ///     .build();
+    /// let mut feed = map.get_or_insert("eur/usd", 1.0).await.unwrap();
+    ///
+    /// for _ in 0..3 {
+    ///     feed.publish_err("upstream timed out");
+    /// }
+    ///
+    /// assert_eq!(feed.status(), FeedStatus::Degraded);
+    /// let err = feed.publish(1.1).unwrap_err();
+    /// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CircuitOpen));
+    /// # };
+    /// ```
+    pub fn publish_err<E>(&mut self, error: E) -> bool
+    where
+        E: Display,
+    {
+        diag::debug!(
+            "producer of {:?} failed: {}",
+            self.owner.redacted_key(&self.key),
+            error
+        );
+
+        self.owner.record_failure(&self.key, &self.meta)
+    }
+
+    /// The health of the producers of the entry
+    pub fn status(&self) -> FeedStatus {
+        match self.owner.0.config.circuit_breaker {
+            Some(_) => status_observable(&self.meta).latest(),
+            None => FeedStatus::Healthy,
+        }
+    }
+
+    /// Wait until the health of the producers of the entry changes and return it.
+    ///
+    /// Waits indefinitely without a configured circuit breaker.
+    pub async fn status_changed(&self) -> FeedStatus {
+        match self.owner.0.config.circuit_breaker {
+            Some(_) => status_observable(&self.meta).next().await,
+            None => futures::future::pending().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FeedStatus;
+    use crate::{Error, ManualClock, SubscriptionMap};
+    use futures::FutureExt;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_trip_and_recover_after_cooldown() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .circuit_breaker(2, Duration::from_secs(10))
+            .build();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        let subscriber = map.get_or_insert(1, 0).await.unwrap();
+
+        // a successful publish resets the consecutive failures
+        assert!(!producer.publish_err("timeout"));
+        producer.publish(1).unwrap();
+        assert!(!producer.publish_err("timeout"));
+
+        {
+            let degraded = subscriber.status_changed();
+            futures::pin_mut!(degraded);
+            assert!(degraded.as_mut().now_or_never().is_none());
+
+            assert!(producer.publish_err("timeout"));
+            assert_eq!(degraded.await, FeedStatus::Degraded);
+        }
+
+        let err = map.publish_if_changed(&1, 2).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CircuitOpen));
+
+        clock.advance(Duration::from_secs(10));
+        producer.publish(2).unwrap();
+        assert_eq!(subscriber.status(), FeedStatus::Healthy);
+        assert_eq!(subscriber.latest(), 2);
+    }
+
+    #[async_std::test]
+    async fn should_count_rejected_values_as_failures() {
+        let map = SubscriptionMap::<usize, Vec<u8>>::builder()
+            .max_value_size(2, |value: &Vec<u8>| value.len())
+            .circuit_breaker(1, Duration::from_secs(10))
+            .build();
+        let mut producer = map.get_or_insert(1, vec![]).await.unwrap();
+
+        let err = producer.publish(vec![0; 3]).unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ValueTooLarge));
+        assert_eq!(producer.status(), FeedStatus::Degraded);
+
+        let err = producer.publish(vec![0]).unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CircuitOpen));
+    }
+
+    #[async_std::test]
+    async fn should_keep_breakers_of_swapped_entries() {
+        let breaking = || {
+            SubscriptionMap::<usize, usize>::builder()
+                .circuit_breaker(1, Duration::from_secs(10))
+                .build()
+        };
+        let (map, other) = (breaking(), breaking());
+        let swapped_out = map.get_or_insert(1, 0).await.unwrap();
+        let mut swapped_in = other.get_or_insert(1, 0).await.unwrap();
+        assert!(swapped_in.publish_err("timeout"));
+
+        map.swap_contents(&other);
+        // removing the entry which moved to the other map leaves the one of this map alone
+        drop(swapped_out);
+
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(subscription.status(), FeedStatus::Degraded);
+
+        let err = subscription.publish(1).unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CircuitOpen));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::breaker::BreakerConfig;
+#[cfg(feature = "fault-injection")]
+use crate::fault::FaultInjector;
+#[cfg(any(feature = "stats", feature = "latency"))]
+use crate::KeyPrefix;
+use crate::{bulk, Clock, Redactor, SubscriptionMap, SystemClock};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+
+type EvictionWeight<K, V> = Arc<dyn Fn(&K, &V) -> u64 + Send + Sync>;
+type ValueSize<V> = Arc<dyn Fn(&V) -> usize + Send + Sync>;
+
+/// Static configuration of a subscription map, shared by all of its clones
+pub(crate) struct Config<K, V> {
+    pub redactor: Option<Arc<dyn Redactor<V>>>,
+    pub key_redactor: Option<Arc<dyn Redactor<K>>>,
+    pub clock: Arc<dyn Clock>,
+    #[cfg(feature = "fault-injection")]
+    pub fault_injector: Option<Arc<dyn FaultInjector<K>>>,
+    #[cfg(any(feature = "stats", feature = "latency"))]
+    pub stats_prefixes: Arc<[K]>,
+    #[cfg(any(feature = "stats", feature = "latency"))]
+    pub prefix_matcher: Option<fn(&K, &K) -> bool>,
+    pub capacity: Option<usize>,
+    pub eviction_weight: Option<EvictionWeight<K, V>>,
+    pub heartbeat: Option<Duration>,
+    pub keep_alive: Option<Duration>,
+    #[cfg(feature = "latency")]
+    pub latency_budget: Option<Duration>,
+    pub max_value_size: Option<(usize, ValueSize<V>)>,
+    pub bulk_chunk_size: usize,
+    pub circuit_breaker: Option<BreakerConfig>,
+}
+
+#[cfg(any(feature = "stats", feature = "latency"))]
+impl<K, V> Config<K, V> {
+    /// The index of the first configured statistics prefix matching the key
+    pub fn prefix_of(&self, key: &K) -> Option<usize> {
+        let matches = self.prefix_matcher?;
+        self.stats_prefixes
+            .iter()
+            .position(|prefix| matches(key, prefix))
+    }
+}
+
+impl<K, V> Default for Config<K, V> {
+    fn default() -> Self {
+        Self {
+            redactor: None,
+            key_redactor: None,
+            clock: Arc::new(SystemClock),
+            #[cfg(feature = "fault-injection")]
+            fault_injector: None,
+            #[cfg(any(feature = "stats", feature = "latency"))]
+            stats_prefixes: Arc::new([]),
+            #[cfg(any(feature = "stats", feature = "latency"))]
+            prefix_matcher: None,
+            capacity: None,
+            eviction_weight: None,
+            heartbeat: None,
+            keep_alive: None,
+            #[cfg(feature = "latency")]
+            latency_budget: None,
+            max_value_size: None,
+            bulk_chunk_size: bulk::DEFAULT_CHUNK_SIZE,
+            circuit_breaker: None,
+        }
+    }
+}
+
+impl<K, V> Clone for Config<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            redactor: self.redactor.clone(),
+            key_redactor: self.key_redactor.clone(),
+            clock: self.clock.clone(),
+            #[cfg(feature = "fault-injection")]
+            fault_injector: self.fault_injector.clone(),
+            #[cfg(any(feature = "stats", feature = "latency"))]
+            stats_prefixes: self.stats_prefixes.clone(),
+            #[cfg(any(feature = "stats", feature = "latency"))]
+            prefix_matcher: self.prefix_matcher,
+            capacity: self.capacity,
+            eviction_weight: self.eviction_weight.clone(),
+            heartbeat: self.heartbeat,
+            keep_alive: self.keep_alive,
+            #[cfg(feature = "latency")]
+            latency_budget: self.latency_budget,
+            max_value_size: self.max_value_size.clone(),
+            bulk_chunk_size: self.bulk_chunk_size,
+            circuit_breaker: self.circuit_breaker,
+        }
+    }
+}
+
+/// Configures and creates a [`SubscriptionMap`].
+///
+/// ```
+/// # use async_subscription_map::{FullRedaction, SubscriptionMap};
+/// let map = SubscriptionMap::<usize, usize>::builder()
+///     .redactor(FullRedaction)
+///     .build();
+/// ```
+pub struct SubscriptionMapBuilder<K, V> {
+    config: Config<K, V>,
+}
+
+impl<K, V> SubscriptionMapBuilder<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            config: Config::default(),
+        }
+    }
+
+    /// Format values through the provided redactor in all diagnostics output.
+    pub fn redactor<R>(mut self, redactor: R) -> Self
+    where
+        R: Redactor<V> + 'static,
+    {
+        self.config.redactor = Some(Arc::new(redactor));
+        self
+    }
+
+    /// Format keys through the provided redactor in all diagnostics output.
+    ///
+    /// This covers log messages, error messages and the `Debug` output of the map and its refs,
+    /// the API itself always hands out the full keys. Keys which embed user identifiers can be
+    /// hashed with [`HashedKeys`](crate::HashedKeys) or cut off with
+    /// [`TruncatedKeys`](crate::TruncatedKeys).
+    ///
+    /// ```
+    /// # use async_subscription_map::{HashedKeys, SubscriptionMap};
+    /// let map = SubscriptionMap::<String, usize>::builder()
+    ///     .key_redactor(HashedKeys::new())
+    ///     .build();
+    /// ```
+    pub fn key_redactor<R>(mut self, redactor: R) -> Self
+    where
+        R: Redactor<K> + 'static,
+    {
+        self.config.key_redactor = Some(Arc::new(redactor));
+        self
+    }
+
+    /// Use the provided time source for all time dependent behavior of the map, the default is the
+    /// [`SystemClock`].
+    pub fn clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + 'static,
+    {
+        self.config.clock = Arc::new(clock);
+        self
+    }
+
+    /// Inject delays and failures into the critical sections of the map.
+    #[cfg(feature = "fault-injection")]
+    pub fn fault_injector<F>(mut self, injector: F) -> Self
+    where
+        F: FaultInjector<K> + 'static,
+    {
+        self.config.fault_injector = Some(Arc::new(injector));
+        self
+    }
+
+    /// Limit the amount of entries, once reached new entries evict an unreferenced one.
+    ///
+    /// Eviction also considers pinned and retained entries as long as no one subscribes to them,
+    /// inserting fails with [`Error::CapacityExceeded`](crate::Error::CapacityExceeded) if every
+    /// entry is referenced. Without an [`eviction_weight`](Self::eviction_weight) the least
+    /// recently used entry is evicted.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.config.capacity = Some(capacity);
+        self
+    }
+
+    /// Evict the unreferenced entry with the highest weight first, ties are broken by recency.
+    ///
+    /// This keeps cheap entries around while large stale ones are evicted. The weight is computed
+    /// whenever a value is inserted or published and cached with its entry.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// let map = SubscriptionMap::<usize, Vec<u8>>::builder()
+    ///     .capacity(1024)
+    ///     .eviction_weight(|_, blob: &Vec<u8>| blob.len() as u64)
+    ///     .build();
+    /// ```
+    pub fn eviction_weight<W>(mut self, weight: W) -> Self
+    where
+        W: Fn(&K, &V) -> u64 + Send + Sync + 'static,
+    {
+        self.config.eviction_weight = Some(Arc::new(weight));
+        self
+    }
+
+    /// Consider producers stale if they don't publish within the interval, see
+    /// [`SubscriptionRef::next_or_stale`](crate::SubscriptionRef::next_or_stale).
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.config.heartbeat = Some(interval);
+        self
+    }
+
+    /// Keep entries for the grace period after their last ref is dropped.
+    ///
+    /// Entries which bounce between zero and one subscribers, e.g. per request in an HTTP
+    /// handler, are then reused instead of being recreated every time. Unreferenced entries are
+    /// removed by [`sweep`](SubscriptionMap::sweep) or [`maintain`](SubscriptionMap::maintain)
+    /// once the grace period passed without a new subscriber.
+    pub fn keep_alive(mut self, grace_period: Duration) -> Self {
+        self.config.keep_alive = Some(grace_period);
+        self
+    }
+
+    /// Measure the latency between publishes and the wakeups of their subscribers and check it
+    /// against the budget through
+    /// [`check_publish_latency`](SubscriptionMap::check_publish_latency).
+    ///
+    /// This is meant for tests and staging environments, measuring adds bookkeeping to every
+    /// publish and wakeup. Latencies are measured in real time, independent of the configured
+    /// [`Clock`]. Requires the `latency` feature.
+    #[cfg(feature = "latency")]
+    pub fn publish_latency_budget(mut self, budget: Duration) -> Self {
+        self.config.latency_budget = Some(budget);
+        self
+    }
+
+    /// Reject values whose estimated size exceeds the limit with
+    /// [`Error::ValueTooLarge`](crate::Error::ValueTooLarge).
+    ///
+    /// Every subscriber clones the values it receives, this protects shared maps from a single
+    /// producer which accidentally publishes a huge value. The size is estimated on every publish
+    /// and insertion, which is why publishes clone the current value while a limit is set.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// let map = SubscriptionMap::<usize, Vec<u8>>::builder()
+    ///     .max_value_size(1 << 20, |blob: &Vec<u8>| blob.len())
+    ///     .build();
+    /// ```
+    pub fn max_value_size<S>(mut self, limit: usize, size: S) -> Self
+    where
+        S: Fn(&V) -> usize + Send + Sync + 'static,
+    {
+        self.config.max_value_size = Some((limit, Arc::new(size)));
+        self
+    }
+
+    /// The amount of keys bulk operations such as
+    /// [`publish_many`](SubscriptionMap::publish_many) and [`clear`](SubscriptionMap::clear)
+    /// process per lock acquisition, 256 by default.
+    ///
+    /// Bulk operations yield to other tasks between chunks, smaller chunks bound the time other
+    /// operations wait for the lock at the cost of throughput of the bulk operation.
+    pub fn bulk_chunk_size(mut self, size: usize) -> Self {
+        self.config.bulk_chunk_size = size.max(1);
+        self
+    }
+
+    /// Reject publishes to a key for the cooldown once its producers failed the given amount of
+    /// times in a row, see [`publish_err`](crate::SubscriptionRef::publish_err).
+    ///
+    /// Subscribers observe the [`FeedStatus`](crate::FeedStatus) of the key instead of waiting on
+    /// a broken feed. After the cooldown publishes are accepted again, the first successful one
+    /// closes the breaker while another failure trips it right away.
+    pub fn circuit_breaker(mut self, failures: u32, cooldown: Duration) -> Self {
+        self.config.circuit_breaker = Some(BreakerConfig {
+            failures: failures.max(1),
+            cooldown,
+        });
+        self
+    }
+
+    /// Create the configured map
+    pub fn build(self) -> SubscriptionMap<K, V> {
+        SubscriptionMap::with_config(self.config)
+    }
+}
+
+#[cfg(any(feature = "stats", feature = "latency"))]
+impl<K, V> SubscriptionMapBuilder<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord + KeyPrefix,
+    V: Clone + Debug,
+{
+    /// Additionally break down the [`stats`](SubscriptionMap::stats) by these key prefixes, every
+    /// key is accounted to the first prefix it matches, the [publish
+    /// latency](SubscriptionMap::publish_latency) is broken down the same way. Requires the
+    /// `stats` or the `latency` feature.
+    pub fn stats_prefixes<I>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+    {
+        self.config.stats_prefixes = prefixes.into_iter().collect();
+        self.config.prefix_matcher = Some(K::starts_with);
+        self
+    }
+}
@@ -0,0 +1,177 @@
+use crate::SubscriptionMap;
+use anyhow::Context as _;
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::Hash;
+use std::ops::{Bound, RangeBounds};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The amount of keys bulk operations process per lock acquisition by default
+pub(crate) const DEFAULT_CHUNK_SIZE: usize = 256;
+
+/// Let other tasks of the executor run before the bulk operation continues
+pub(crate) async fn yield_now() {
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if std::mem::replace(&mut self.0, true) {
+                return Poll::Ready(());
+            }
+
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    YieldNow(false).await
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Publish the values of many keys, returns the amount of applied publishes.
+    ///
+    /// The pairs are published in chunks of the
+    /// [`bulk_chunk_size`](crate::SubscriptionMapBuilder::bulk_chunk_size) and the task yields
+    /// between them, so large bulk publishes don't stall insertions and cleanup of unrelated
+    /// keys. Fails on the first key which isn't present, the publishes before it stay applied.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let subscriptions = map.get_or_insert_many((0..1000).map(|key| (key, 0))).await.unwrap();
+    ///
+    /// let published = map.publish_many((0..1000).map(|key| (key, 1))).await.unwrap();
+    /// assert_eq!(published, 1000);
+    /// assert_eq!(subscriptions[999].latest(), 1);
+    /// # };
+    /// ```
+    pub async fn publish_many<I>(&self, pairs: I) -> anyhow::Result<usize>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let chunk = self.0.config.bulk_chunk_size;
+        let mut pairs = pairs.into_iter().peekable();
+        let mut published = 0;
+
+        loop {
+            {
+                let map = self.read_entries().await;
+
+                for (key, value) in pairs.by_ref().take(chunk) {
+                    let entry = map.get(&key).with_context(|| {
+                        format!(
+                            "unable publish new version of not present key {:?}",
+                            self.redacted_key(&key)
+                        )
+                    })?;
+
+                    if self.publish_value_gate(&key, entry.target(), |_, _| true, value)? {
+                        published += 1;
+                    }
+                }
+            }
+
+            if pairs.peek().is_none() {
+                return Ok(published);
+            }
+
+            yield_now().await;
+        }
+    }
+
+    /// Modify and publish the values of all keys within the range, returns the amount of
+    /// applied publishes.
+    ///
+    /// Keys are processed in order and in chunks like [`publish_many`](Self::publish_many), keys
+    /// which are created in the range behind the current chunk in the meantime are modified too.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// map.seed((0..10).map(|key| (key, key))).await.unwrap();
+    ///
+    /// let modified = map.modify_range(5.., |_, value| *value *= 10).await.unwrap();
+    /// assert_eq!(modified, 5);
+    /// assert_eq!(map.peek(&9).await, Some(90));
+    /// # };
+    /// ```
+    pub async fn modify_range<R, F>(&self, range: R, mut modify: F) -> anyhow::Result<usize>
+    where
+        R: RangeBounds<K>,
+        F: FnMut(&K, &mut V),
+    {
+        let chunk = self.0.config.bulk_chunk_size;
+        let mut start = range.start_bound().cloned();
+        let end = range.end_bound().cloned();
+        let mut published = 0;
+
+        loop {
+            let last = {
+                let map = self.read_entries().await;
+                let mut processed = 0;
+                let mut last = None;
+
+                for (key, entry) in map.range((start.clone(), end.clone())).take(chunk) {
+                    if self.publish_gate(key, entry.target(), |_| true, |v| modify(key, v))? {
+                        published += 1;
+                    }
+
+                    processed += 1;
+                    last = Some(key.clone());
+                }
+
+                last.filter(|_| processed == chunk)
+            };
+
+            match last {
+                Some(last) => start = Bound::Excluded(last),
+                None => return Ok(published),
+            }
+
+            yield_now().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_yield_between_chunks() {
+        let map: SubscriptionMap<usize, usize> =
+            SubscriptionMap::builder().bulk_chunk_size(10).build();
+        map.seed((0..100).map(|key| (key, 0))).await.unwrap();
+
+        // the insertion gets the lock while the bulk publish is in progress
+        let (published, progress) =
+            futures::join!(map.publish_many((0..100).map(|key| (key, 1))), async {
+                map.pin(100, 0).await.unwrap();
+                (map.peek(&0).await, map.peek(&99).await)
+            });
+
+        assert_eq!(published.unwrap(), 100);
+        assert_eq!(progress, (Some(1), Some(0)));
+        assert_eq!(map.peek(&99).await, Some(1));
+
+        let modified = map.modify_range(50..=100, |key, v| *v = *key).await;
+        assert_eq!(modified.unwrap(), 51);
+        assert_eq!(map.peek(&100).await, Some(100));
+        assert_eq!(map.peek(&49).await, Some(1));
+
+        let err = map.publish_many([(5, 2), (200, 2)]).await.unwrap_err();
+        assert!(format!("{:#}", err).contains("not present"));
+        assert_eq!(map.peek(&5).await, Some(2));
+
+        assert_eq!(map.clear().await, 101);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::{diag, EntryMeta, Error, SubscriptionEntry, SubscriptionMap};
+use anyhow::Context;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Evict unreferenced entries until a new entry fits into the configured capacity
+    pub(crate) fn make_room(
+        &self,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+        key: &K,
+    ) -> anyhow::Result<()> {
+        let capacity = match self.tunables().capacity {
+            Some(capacity) => capacity,
+            None => return Ok(()),
+        };
+
+        while map.len() >= capacity {
+            let victim = self
+                .eviction_candidate(map, &BTreeSet::new())
+                .ok_or(Error::CapacityExceeded)
+                .with_context(|| format!("unable to insert {:?}", self.redacted_key(key)))?;
+
+            if let Some(entry) = map.remove(&victim) {
+                diag::debug!(
+                    "evicting unreferenced entry {:?}",
+                    self.redacted_key(&victim)
+                );
+                self.record_removal(map, &victim, &entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evict unreferenced entries until all keys fit into the configured capacity, the entries of
+    /// the keys are never evicted. Fails without evicting anything if there isn't enough room.
+    pub(crate) fn make_room_for(
+        &self,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+        keys: &BTreeSet<K>,
+    ) -> anyhow::Result<()> {
+        let capacity = match self.tunables().capacity {
+            Some(capacity) => capacity,
+            None => return Ok(()),
+        };
+
+        let missing = keys.iter().filter(|key| !map.contains_key(key)).count();
+        let excess = (map.len() + missing).saturating_sub(capacity);
+        let evictable = map
+            .iter()
+            .filter(|(key, entry)| {
+                entry.rc() == 0 && !keys.contains(*key) && !self.is_excluded_from_cleanup(key)
+            })
+            .count();
+
+        if evictable < excess {
+            return Err(Error::CapacityExceeded).with_context(|| {
+                format!(
+                    "unable to insert {} entries, only {} can be evicted",
+                    missing, evictable
+                )
+            });
+        }
+
+        for _ in 0..excess {
+            let victim = match self.eviction_candidate(map, keys) {
+                Some(victim) => victim,
+                None => break,
+            };
+
+            if let Some(entry) = map.remove(&victim) {
+                diag::debug!(
+                    "evicting unreferenced entry {:?}",
+                    self.redacted_key(&victim)
+                );
+                self.record_removal(map, &victim, &entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Evict unreferenced entries until the map no longer exceeds its capacity, returns the
+    /// amount of evicted entries
+    pub(crate) fn shrink_to_capacity(&self, map: &mut BTreeMap<K, SubscriptionEntry<V>>) -> usize {
+        let capacity = match self.tunables().capacity {
+            Some(capacity) => capacity,
+            None => return 0,
+        };
+
+        let mut evicted = 0;
+
+        while map.len() > capacity {
+            let victim = match self.eviction_candidate(map, &BTreeSet::new()) {
+                Some(victim) => victim,
+                None => break,
+            };
+
+            if let Some(entry) = map.remove(&victim) {
+                diag::debug!(
+                    "evicting unreferenced entry {:?}",
+                    self.redacted_key(&victim)
+                );
+                self.record_removal(map, &victim, &entry);
+                evicted += 1;
+            }
+        }
+
+        evicted
+    }
+
+    /// The eviction weight of a value, it is only computed when the value is published so
+    /// evictions don't have to look at the values
+    pub(crate) fn weigh(&self, key: &K, value: &V) -> u64 {
+        self.0
+            .config
+            .eviction_weight
+            .as_ref()
+            .map_or(0, |weight| weight(key, value))
+    }
+
+    /// Update the eviction weight of the entry to the published value
+    pub(crate) fn record_weight(&self, key: &K, meta: &EntryMeta<V>, value: &V) {
+        if self.0.config.eviction_weight.is_some() {
+            meta.weight.store(self.weigh(key, value), Ordering::Relaxed);
+        }
+    }
+
+    /// The unreferenced entry which should be evicted next, apart from the protected ones
+    fn eviction_candidate(
+        &self,
+        map: &BTreeMap<K, SubscriptionEntry<V>>,
+        protected: &BTreeSet<K>,
+    ) -> Option<K> {
+        let candidates = map.iter().filter(|(key, entry)| {
+            entry.rc() == 0 && !protected.contains(*key) && !self.is_excluded_from_cleanup(key)
+        });
+
+        let victim = match self.0.config.eviction_weight {
+            Some(_) => candidates.max_by_key(|(_, entry)| {
+                let weight = entry.meta.weight.load(Ordering::Relaxed);
+                (weight, Reverse(entry.last_used))
+            }),
+            None => candidates.min_by_key(|(_, entry)| entry.last_used),
+        };
+
+        victim.map(|(key, _)| key.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_evict_least_recently_used() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .capacity(2)
+            .build();
+
+        map.pin(1, 1).await.unwrap();
+        clock.advance(Duration::from_secs(1));
+        map.pin(2, 2).await.unwrap();
+        clock.advance(Duration::from_secs(1));
+
+        drop(map.get_or_insert(1, 0).await.unwrap());
+        let _three = map.get_or_insert(3, 3).await.unwrap();
+
+        let snapshot = map.entries_snapshot().await;
+        assert!(snapshot.contains_key(&1));
+        assert!(!snapshot.contains_key(&2));
+        #[cfg(feature = "stats")]
+        assert_eq!(map.stats().total.removed, 1);
+    }
+
+    #[async_std::test]
+    async fn should_evict_heaviest_entry_first() {
+        let map = SubscriptionMap::<usize, Vec<u8>>::builder()
+            .capacity(3)
+            .eviction_weight(|_, blob: &Vec<u8>| blob.len() as u64)
+            .build();
+
+        map.pin(1, vec![0; 1024]).await.unwrap();
+        map.pin(2, vec![0; 1]).await.unwrap();
+        map.pin(3, vec![0; 2]).await.unwrap();
+
+        map.pin(4, vec![]).await.unwrap();
+        assert!(!map.entries_snapshot().await.contains_key(&1));
+
+        map.pin(5, vec![]).await.unwrap();
+        assert!(!map.entries_snapshot().await.contains_key(&3));
+    }
+
+    #[async_std::test]
+    async fn should_weigh_published_values() {
+        let map = SubscriptionMap::<usize, Vec<u8>>::builder()
+            .capacity(2)
+            .eviction_weight(|_, blob: &Vec<u8>| blob.len() as u64)
+            .build();
+
+        map.pin(1, vec![0; 1024]).await.unwrap();
+        map.pin(2, vec![0; 1]).await.unwrap();
+
+        map.publish(&1, vec![]).await.unwrap();
+        map.publish(&2, vec![0; 1024]).await.unwrap();
+
+        map.pin(3, vec![]).await.unwrap();
+
+        let entries = map.entries_snapshot().await;
+        assert!(entries.contains_key(&1));
+        assert!(!entries.contains_key(&2));
+    }
+
+    #[async_std::test]
+    async fn should_not_evict_for_rejected_values() {
+        let map = SubscriptionMap::<usize, Vec<u8>>::builder()
+            .capacity(1)
+            .max_value_size(2, |blob: &Vec<u8>| blob.len())
+            .build();
+
+        map.pin(1, vec![0]).await.unwrap();
+
+        let err = map.get_or_insert(2, vec![0; 3]).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ValueTooLarge));
+
+        let err = map.pin(2, vec![0; 3]).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ValueTooLarge));
+
+        assert!(map.entries_snapshot().await.contains_key(&1));
+    }
+
+    #[async_std::test]
+    async fn should_fail_if_all_entries_are_referenced() {
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .capacity(2)
+            .build();
+
+        let _one = map.get_or_insert(1, 0).await.unwrap();
+        let _two = map.get_or_insert(2, 0).await.unwrap();
+        let _also_one = map.get_or_insert(1, 0).await.unwrap();
+
+        let err = map.get_or_insert(3, 0).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CapacityExceeded));
+
+        assert!(map.get_or_insert_many([(1, 0), (3, 0)]).await.is_err());
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 2);
+    }
+}
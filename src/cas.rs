@@ -0,0 +1,103 @@
+#[cfg(feature = "fault-injection")]
+use crate::fault;
+use crate::{SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Eq,
+{
+    /// Publish the new value only if the current one equals the expected value, returns if a
+    /// publish was made.
+    ///
+    /// The comparison and the publish happen atomically under the lock of the entry's value, so
+    /// concurrent writers can update the key optimistically: read the latest value, compute the
+    /// new one and retry if another writer published in between.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let _counter = map.get_or_insert("visits", 0).await.unwrap();
+    ///
+    /// loop {
+    ///     let current = map.peek(&"visits").await.unwrap();
+    ///
+    ///     if map.compare_and_publish(&"visits", current, current + 1).await.unwrap() {
+    ///         break;
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(map.peek(&"visits").await, Some(1));
+    /// # };
+    /// ```
+    pub async fn compare_and_publish(&self, key: &K, expected: V, new: V) -> anyhow::Result<bool> {
+        #[cfg(feature = "fault-injection")]
+        self.inject_fault(fault::FaultPoint::BeforePublish, key)
+            .await?;
+
+        let map = self.read_entries().await;
+        let entry = map.get(key).with_context(|| {
+            format!(
+                "unable to compare and publish not present key {:?}",
+                self.redacted_key(key)
+            )
+        })?;
+
+        self.publish_value_gate(key, entry.target(), |current, _| *current == expected, new)
+    }
+}
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Eq,
+{
+    /// Same as [`SubscriptionMap::compare_and_publish`] through the ref.
+    pub fn compare_and_publish(&mut self, expected: V, new: V) -> anyhow::Result<bool> {
+        self.owner.publish_value_gate_as(
+            &self.key,
+            self.tag,
+            self.target(),
+            |current, _| *current == expected,
+            new,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_publish_only_expected_values() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut first = map.get_or_insert(1, 0).await.unwrap();
+        let mut second = map.get_or_insert(1, 0).await.unwrap();
+
+        assert!(first.compare_and_publish(0, 1).unwrap());
+        assert!(!second.compare_and_publish(0, 2).unwrap());
+        assert_eq!(second.next().await, 1);
+
+        assert!(!map.compare_and_publish(&1, 0, 3).await.unwrap());
+        assert!(map.compare_and_publish(&1, 1, 3).await.unwrap());
+        assert_eq!(first.latest(), 3);
+
+        assert!(map.compare_and_publish(&2, 0, 1).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn should_compare_against_buffered_values_while_paused() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.pause();
+        assert!(subscription.compare_and_publish(0, 1).unwrap());
+        assert!(subscription.compare_and_publish(1, 2).unwrap());
+        map.resume().await;
+        assert_eq!(subscription.next().await, 2);
+    }
+}
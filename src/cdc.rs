@@ -0,0 +1,245 @@
+use crate::update::UpdateMeta;
+use crate::{KeyMatcher, SubscriptionMap, Update};
+use futures::Stream;
+use std::collections::VecDeque;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Waker};
+
+type Matcher<K> = Box<dyn KeyMatcher<K> + Send + Sync>;
+
+/// A single change of a [`CdcStream`]: the key and the update
+pub type CdcChange<K, V> = (K, Update<V>);
+
+/// The changes captured for a single stream
+pub(crate) struct ChangeQueue<K, V> {
+    state: Mutex<QueueState<K, V>>,
+    matcher: Matcher<K>,
+}
+
+struct QueueState<K, V> {
+    changes: VecDeque<CdcChange<K, V>>,
+    waker: Option<Waker>,
+}
+
+impl<K, V> ChangeQueue<K, V> {
+    fn state(&self) -> MutexGuard<'_, QueueState<K, V>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+pub(crate) type ChangeQueues<K, V> = Vec<Weak<ChangeQueue<K, V>>>;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Stream every publish to keys matching the filter across the whole map, in the order the
+    /// publishes happened.
+    ///
+    /// Changes are received in the order of their [versions](Update::version), which increase with
+    /// every change of the map, see [`SubscriptionRef::latest_versioned`]. Unlike
+    /// [`subscribe_matching`](Self::subscribe_matching) no publish is conflated, which makes the
+    /// stream suitable to feed an external event log or search index. Insertions and removals
+    /// are reported by [`events`](Self::events). Changes are queued until they are received, so
+    /// the stream should be polled continuously, and capturing them serializes the publishes of
+    /// the map while any stream is alive.
+    ///
+    /// [`SubscriptionRef::latest_versioned`]: crate::SubscriptionRef::latest_versioned
+    ///
+    /// ```
+    /// # use async_subscription_map::{Prefix, SubscriptionMap};
+    /// # use futures::StreamExt;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let mut changes = map.cdc_stream(Prefix("orders/"));
+    ///
+    /// let mut order = map.get_or_insert("orders/1", 0).await.unwrap();
+    /// let mut user = map.get_or_insert("users/1", 0).await.unwrap();
+    /// order.publish(1).unwrap();
+    /// user.publish(1).unwrap();
+    /// order.publish(2).unwrap();
+    ///
+    /// let (key, first) = changes.next().await.unwrap();
+    /// assert_eq!((key, first.value), ("orders/1", 1));
+    ///
+    /// let (_, second) = changes.next().await.unwrap();
+    /// assert_eq!(second.value, 2);
+    /// assert!(second.version > first.version);
+    /// # };
+    /// ```
+    pub fn cdc_stream<M>(&self, filter: M) -> CdcStream<K, V>
+    where
+        M: KeyMatcher<K> + Send + Sync + 'static,
+    {
+        let queue = Arc::new(ChangeQueue {
+            state: Mutex::new(QueueState {
+                changes: VecDeque::new(),
+                waker: None,
+            }),
+            matcher: Box::new(filter),
+        });
+
+        let mut queues = self.change_queues();
+        queues.retain(|queue| queue.strong_count() > 0);
+        queues.push(Arc::downgrade(&queue));
+        self.0.capturing.fetch_add(1, Ordering::SeqCst);
+
+        CdcStream {
+            owner: self.internal(),
+            queue,
+        }
+    }
+
+    fn change_queues(&self) -> MutexGuard<'_, ChangeQueues<K, V>> {
+        self.0.changes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Lock the change queues if any stream captures changes, publishes are totally ordered as
+    /// long as the guard is held while their version is assigned
+    pub(crate) fn capturing_changes(&self) -> Option<MutexGuard<'_, ChangeQueues<K, V>>> {
+        match self.0.capturing.load(Ordering::SeqCst) {
+            0 => None,
+            _ => Some(self.change_queues()),
+        }
+    }
+
+    /// Queue a published value for all streams whose filter matches the key
+    pub(crate) fn capture_change(
+        &self,
+        queues: &mut ChangeQueues<K, V>,
+        key: &K,
+        meta: UpdateMeta,
+        value: &V,
+    ) {
+        for queue in queues.iter().filter_map(Weak::upgrade) {
+            if !queue.matcher.matches(key) {
+                continue;
+            }
+
+            let update = Update {
+                value: value.clone(),
+                version: meta.version,
+                timestamp: meta.timestamp,
+                producer: meta.producer,
+            };
+
+            let mut state = queue.state();
+            state.changes.push_back((key.clone(), update));
+
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// A totally ordered stream of the publishes of a map, see [`SubscriptionMap::cdc_stream`].
+pub struct CdcStream<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    owner: SubscriptionMap<K, V>,
+    queue: Arc<ChangeQueue<K, V>>,
+}
+
+impl<K, V> CdcStream<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The amount of changes which were captured but not received yet
+    pub fn backlog(&self) -> usize {
+        self.queue.state().changes.len()
+    }
+}
+
+impl<K, V> Stream for CdcStream<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    type Item = CdcChange<K, V>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.queue.state();
+
+        match state.changes.pop_front() {
+            Some(change) => Poll::Ready(Some(change)),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<K, V> Drop for CdcStream<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn drop(&mut self) {
+        self.owner.0.capturing.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<K, V> Debug for CdcStream<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CdcStream")
+            .field("backlog", &self.backlog())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AllKeys, Exact, SubscriptionMap};
+    use futures::{FutureExt, StreamExt};
+
+    #[async_std::test]
+    async fn should_capture_every_publish_in_order() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut changes = map.cdc_stream(AllKeys);
+        let mut one = map.get_or_insert(1, 0).await.unwrap();
+        let mut two = map.get_or_insert_tagged(2, 0, "importer").await.unwrap();
+
+        one.publish(1).unwrap();
+        two.publish(1).unwrap();
+        one.publish(2).unwrap();
+        assert!(!one.publish_if_changed(2).unwrap());
+        assert_eq!(changes.backlog(), 3);
+
+        let captured: Vec<_> = (&mut changes).take(3).collect().await;
+        let keys: Vec<_> = captured.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec![1, 2, 1]);
+        assert!(captured
+            .windows(2)
+            .all(|pair| pair[0].1.version < pair[1].1.version));
+        assert_eq!(captured[1].1.producer, Some("importer"));
+        assert_eq!(captured[2].1.version, one.latest_versioned().version);
+    }
+
+    #[async_std::test]
+    async fn should_only_capture_matching_keys() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut changes = map.cdc_stream(Exact(2));
+        let mut one = map.get_or_insert(1, 0).await.unwrap();
+
+        one.publish(1).unwrap();
+        assert!(changes.next().now_or_never().is_none());
+
+        drop(changes);
+        assert_eq!(map.0.capturing.load(std::sync::atomic::Ordering::SeqCst), 0);
+        one.publish(2).unwrap();
+    }
+}
@@ -0,0 +1,67 @@
+use crate::SubscriptionRef;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Take ownership of the entry if this is the last ref to it.
+    ///
+    /// The entry is removed, even if it is pinned or retained, and its key and final value are
+    /// returned. If other refs are still alive this ref is just dropped and `None` is returned.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, Vec<u8>>::default();
+    /// let mut upload = map.get_or_insert("upload", vec![]).await.unwrap();
+    /// upload.modify(|chunks| chunks.push(1)).unwrap();
+    ///
+    /// assert_eq!(upload.into_entry().await, Some(("upload", vec![1])));
+    /// # };
+    /// ```
+    pub async fn into_entry(self) -> Option<(K, V)> {
+        let entries = self.entries.clone();
+        let mut map = self.owner.lock_entries(&entries).await;
+
+        match map.get(&self.key) {
+            Some(entry) if Arc::ptr_eq(&entry.rc, &self.rc) && entry.rc() == 1 => {}
+            _ => return None,
+        }
+
+        let entry = map.remove(&self.key)?;
+        self.owner.record_removal(&mut map, &self.key, &entry);
+
+        // dropping this ref afterwards finds the entry already removed
+        Some((self.key.clone(), entry.observable.latest()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{MapEvent, SubscriptionMap};
+    use futures::StreamExt;
+
+    #[async_std::test]
+    async fn should_claim_entry_with_last_ref() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut first = map.get_or_insert(1, 0).await.unwrap();
+        let second = map.get_or_insert(1, 0).await.unwrap();
+        first.publish(5).unwrap();
+
+        assert_eq!(first.into_entry().await, None);
+        assert_eq!(map.entries_snapshot().await.len(), 1);
+
+        let mut events = map.lifecycle_events();
+        assert_eq!(second.into_entry().await, Some((1, 5)));
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+        assert_eq!(events.next().await.unwrap(), vec![MapEvent::Removed(1)]);
+
+        // the claimed key starts over
+        let third = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(third.latest(), 0);
+    }
+}
@@ -0,0 +1,217 @@
+use futures::future::BoxFuture;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// The time source of a subscription map.
+///
+/// Every time dependent feature of the map asks its clock for the current time and for timers,
+/// which allows swapping in a [`ManualClock`] to test such behavior instantly and deterministically.
+pub trait Clock: Debug + Send + Sync {
+    /// The current point in time
+    fn now(&self) -> Instant;
+
+    /// A future resolving as soon as the deadline has passed
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()>;
+}
+
+/// The default clock backed by the system time and async-io timers, which work on any executor
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        let timer = async_io::Timer::at(deadline);
+
+        Box::pin(async move {
+            timer.await;
+        })
+    }
+}
+
+/// A virtual clock which only advances when told to.
+///
+/// All clones share the same time, sleeping futures resolve as soon as the clock is advanced past
+/// their deadline.
+///
+/// ```
+/// # use async_subscription_map::{Clock, ManualClock};
+/// # use std::time::Duration;
+/// # async {
+/// let clock = ManualClock::new();
+/// let sleep = clock.sleep_until(clock.now() + Duration::from_secs(60));
+///
+/// clock.advance(Duration::from_secs(60));
+/// sleep.await; // resolves immediately
+/// # };
+/// ```
+#[derive(Clone, Debug)]
+pub struct ManualClock(Arc<Mutex<ManualTime>>);
+
+#[derive(Debug)]
+struct ManualTime {
+    now: Instant,
+    /// The deadline and latest waker of every pending sleep by its id
+    sleepers: BTreeMap<u64, (Instant, Waker)>,
+    next_sleeper: u64,
+}
+
+impl ManualClock {
+    /// Create a clock frozen at the current system time
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(ManualTime {
+            now: Instant::now(),
+            sleepers: BTreeMap::new(),
+            next_sleeper: 0,
+        })))
+    }
+
+    /// Move the clock forward and wake every sleeper whose deadline has passed
+    pub fn advance(&self, duration: Duration) {
+        let woken = {
+            let mut time = self.lock();
+            time.now += duration;
+
+            let now = time.now;
+            let (woken, sleeping) = std::mem::take(&mut time.sleepers)
+                .into_iter()
+                .partition::<BTreeMap<_, _>, _>(|(_, (deadline, _))| *deadline <= now);
+
+            time.sleepers = sleeping;
+            woken
+        };
+
+        for (_, waker) in woken.into_values() {
+            waker.wake();
+        }
+    }
+
+    /// The earliest deadline any sleeper is waiting for
+    #[cfg_attr(not(feature = "sim"), allow(dead_code))]
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        self.lock()
+            .sleepers
+            .values()
+            .map(|(deadline, _)| *deadline)
+            .min()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ManualTime> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(e) => e.into_inner(),
+        }
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.lock().now
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        let id = {
+            let mut time = self.lock();
+            time.next_sleeper += 1;
+            time.next_sleeper
+        };
+
+        Box::pin(ManualSleep {
+            clock: self.clone(),
+            id,
+            deadline,
+        })
+    }
+}
+
+struct ManualSleep {
+    clock: ManualClock,
+    /// The slot of the sleep in [`ManualTime::sleepers`], polls replace its waker
+    id: u64,
+    deadline: Instant,
+}
+
+impl Future for ManualSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut time = self.clock.lock();
+
+        if time.now >= self.deadline {
+            time.sleepers.remove(&self.id);
+            return Poll::Ready(());
+        }
+
+        time.sleepers
+            .insert(self.id, (self.deadline, cx.waker().clone()));
+        Poll::Pending
+    }
+}
+
+impl Drop for ManualSleep {
+    fn drop(&mut self) {
+        self.clock.lock().sleepers.remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Clock, ManualClock};
+    use futures::FutureExt;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_only_advance_manually() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+
+        assert_eq!(clock.now(), start);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(clock.now(), start + Duration::from_secs(1));
+    }
+
+    #[async_std::test]
+    async fn should_wake_sleepers_past_their_deadline() {
+        let clock = ManualClock::new();
+        let mut short = clock.sleep_until(clock.now() + Duration::from_secs(1));
+        let mut long = clock.sleep_until(clock.now() + Duration::from_secs(10));
+
+        assert!((&mut short).now_or_never().is_none());
+        assert!((&mut long).now_or_never().is_none());
+
+        clock.advance(Duration::from_secs(5));
+        assert!(short.now_or_never().is_some());
+        assert!((&mut long).now_or_never().is_none());
+
+        clock.advance(Duration::from_secs(5));
+        long.await;
+    }
+
+    #[async_std::test]
+    async fn should_keep_one_waker_per_sleep() {
+        let clock = ManualClock::new();
+        let mut sleep = clock.sleep_until(clock.now() + Duration::from_secs(1));
+
+        for _ in 0..100 {
+            assert!((&mut sleep).now_or_never().is_none());
+        }
+
+        assert_eq!(clock.lock().sleepers.len(), 1);
+        drop(sleep);
+        assert_eq!(clock.next_deadline(), None);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{diag, MapEvent, SubscriptionMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+
+/// If a clone of the map keeps it open.
+///
+/// Clones held by refs and other internal bookkeeping are detached, so the map is closed once
+/// the last clone of the user is dropped even if refs are still alive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Handle {
+    Open,
+    Detached,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// A clone which doesn't keep the map open
+    pub(crate) fn internal(&self) -> Self {
+        Self(self.0.clone(), Handle::Detached)
+    }
+
+    /// Check if the last clone of the map was dropped, only observable through refs.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.0.closed.load(Ordering::SeqCst)
+    }
+
+    fn close(&self) {
+        self.0.closed.store(true, Ordering::SeqCst);
+        diag::debug!("subscription map closed, remaining refs are detached");
+
+        self.emit(MapEvent::Closed);
+        // wakes the maintenance future, which finishes once the map is closed
+        self.0.deadlines_changed.clone().publish(());
+    }
+}
+
+impl<K, V> Clone for SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn clone(&self) -> Self {
+        self.0.handles.fetch_add(1, Ordering::SeqCst);
+        Self(self.0.clone(), Handle::Open)
+    }
+}
+
+impl<K, V> Drop for SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn drop(&mut self) {
+        if self.1 == Handle::Open && self.0.handles.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{MapEvent, SubscriptionMap};
+    use futures::StreamExt;
+
+    #[async_std::test]
+    async fn should_detach_refs_once_map_is_dropped() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut events = map.lifecycle_events();
+
+        let mut publisher = map.get_or_insert(1, 0).await.unwrap();
+        let mut subscriber = publisher.fork().await;
+        let clone = map.clone();
+        drop(map);
+        assert!(!publisher.owner.is_closed());
+
+        drop(clone);
+        assert!(publisher.owner.is_closed());
+        assert_eq!(
+            events.next().await.unwrap(),
+            vec![MapEvent::Inserted(1), MapEvent::Closed]
+        );
+
+        publisher.publish(1).unwrap();
+        assert_eq!(subscriber.next().await, 1);
+        drop((publisher, subscriber));
+    }
+}
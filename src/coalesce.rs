@@ -0,0 +1,283 @@
+use crate::gate::PublishTarget;
+use crate::{diag, KeyPrefix, SubscriptionMap};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// The coalescing windows configured at runtime and the publishes buffered in them
+pub(crate) struct Coalescing<K, V> {
+    prefixes: Vec<(K, Duration)>,
+    matcher: Option<fn(&K, &K) -> bool>,
+    windows: BTreeMap<K, Window<V>>,
+}
+
+/// A key which published within the last window, later publishes are buffered until it ends
+struct Window<V> {
+    until: Instant,
+    pending: Option<V>,
+}
+
+impl<K, V> Default for Coalescing<K, V> {
+    fn default() -> Self {
+        Self {
+            prefixes: Vec::new(),
+            matcher: None,
+            windows: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K, V> Coalescing<K, V>
+where
+    K: Ord,
+{
+    /// Check if publishes can bypass coalescing entirely
+    pub fn is_idle(&self) -> bool {
+        self.prefixes.is_empty() && self.windows.is_empty()
+    }
+
+    /// The configured prefixes and their windows in matching order
+    pub fn prefixes(&self) -> &[(K, Duration)] {
+        &self.prefixes
+    }
+
+    /// The window of the first configured prefix matching the key
+    fn window_of(&self, key: &K) -> Option<Duration> {
+        let matches = self.matcher?;
+        self.prefixes
+            .iter()
+            .find(|(prefix, _)| matches(key, prefix))
+            .map(|(_, window)| *window)
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord + KeyPrefix,
+    V: Clone + Debug,
+{
+    /// Coalesce publishes to keys with the prefix which happen within the window.
+    ///
+    /// The first publish is applied immediately and opens a window, subscribers observe the
+    /// latest value published in the meantime once it ends. This trades latency for throughput
+    /// on noisy keys and can be changed at any time. Windows are closed by
+    /// [`sweep`](Self::sweep) and [`maintain`](Self::maintain), or by the first publish after
+    /// they ended. The first matching prefix in configuration order applies.
+    ///
+    /// ```
+    /// # use async_subscription_map::{ManualClock, SubscriptionMap};
+    /// # use std::time::Duration;
+    /// # async {
+    /// let clock = ManualClock::new();
+    /// let map = SubscriptionMap::<&'static str, usize>::builder()
+    ///     .clock(clock.clone())
+    ///     .build();
+    /// map.coalesce("ticks/", Duration::from_millis(100));
+    ///
+    /// let mut subscription = map.get_or_insert("ticks/btc", 0).await.unwrap();
+    /// map.publish_if_changed(&"ticks/btc", 1).await.unwrap();
+    /// map.publish_if_changed(&"ticks/btc", 2).await.unwrap();
+    /// map.publish_if_changed(&"ticks/btc", 3).await.unwrap();
+    /// assert_eq!(subscription.next().await, 1);
+    ///
+    /// clock.advance(Duration::from_millis(100));
+    /// map.sweep().await;
+    /// assert_eq!(subscription.next().await, 3);
+    /// # };
+    /// ```
+    pub fn coalesce(&self, prefix: K, window: Duration) {
+        let mut state = self.publish_state_mut();
+        let coalescing = &mut state.coalescing;
+
+        coalescing.matcher = Some(<K as KeyPrefix>::starts_with);
+
+        match coalescing.prefixes.iter_mut().find(|(p, _)| *p == prefix) {
+            Some((_, current)) => *current = window,
+            None => coalescing.prefixes.push((prefix, window)),
+        }
+    }
+
+    /// Stop coalescing publishes to keys with the prefix, returns if it was configured.
+    ///
+    /// Windows which are already open still end regularly.
+    pub fn stop_coalescing(&self, prefix: &K) -> bool {
+        let mut state = self.publish_state_mut();
+        let prefixes = &mut state.coalescing.prefixes;
+        let len = prefixes.len();
+
+        prefixes.retain(|(p, _)| p != prefix);
+        prefixes.len() != len
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Apply or buffer a publish depending on the coalescing window of the key
+    pub(crate) fn coalesce_publish<C, M>(
+        &self,
+        coalescing: &mut Coalescing<K, V>,
+        key: &K,
+        producer: Option<&'static str>,
+        target: &mut PublishTarget<'_, V>,
+        condition: C,
+        modify: M,
+    ) -> bool
+    where
+        C: FnOnce(&V) -> bool,
+        M: FnOnce(&mut V),
+    {
+        let now = self.0.config.clock.now();
+
+        if let Some(window) = coalescing.windows.get_mut(key) {
+            if window.until > now {
+                let buffered = window.pending.is_some();
+                let mut pending = window
+                    .pending
+                    .take()
+                    .unwrap_or_else(|| target.observable.latest());
+
+                let changed = condition(&pending);
+
+                if changed {
+                    modify(&mut pending);
+                }
+
+                if changed || buffered {
+                    window.pending = Some(pending);
+                }
+
+                if changed && !buffered {
+                    self.0.deadlines_changed.clone().publish(());
+                }
+
+                return changed;
+            }
+        }
+
+        // the window ended, a value buffered in it is published together with this one
+        let changed = match coalescing.windows.remove(key).and_then(|w| w.pending) {
+            Some(mut pending) => {
+                let changed = condition(&pending);
+
+                if changed {
+                    modify(&mut pending);
+                }
+
+                self.publish_buffered(key, producer, target, pending);
+                changed
+            }
+            None => self.publish_now(key, producer, target, condition, modify),
+        };
+
+        if let (true, Some(window)) = (changed, coalescing.window_of(key)) {
+            coalescing.windows.insert(
+                key.clone(),
+                Window {
+                    until: now + window,
+                    pending: None,
+                },
+            );
+        }
+
+        changed
+    }
+
+    /// Publish the values of all ended windows and return the end of the next pending one
+    pub(crate) async fn flush_coalesced(&self) -> Option<Instant> {
+        let mut entries = self.lock_entries(&self.0.entries()).await;
+        let mut state = self.publish_state_mut();
+        let now = self.0.config.clock.now();
+
+        state.coalescing.windows.retain(|key, window| {
+            if window.until > now {
+                return true;
+            }
+
+            if let Some(value) = window.pending.take() {
+                if let Some(entry) = entries.get_mut(key) {
+                    self.publish_buffered(key, None, &mut entry.target(), value);
+                } else {
+                    diag::debug!(
+                        "discarding coalesced publish of removed key {:?}",
+                        self.redacted_key(key)
+                    );
+                }
+            }
+
+            false
+        });
+
+        state
+            .coalescing
+            .windows
+            .values()
+            .filter(|window| window.pending.is_some())
+            .map(|window| window.until)
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    fn map() -> (ManualClock, SubscriptionMap<&'static str, usize>) {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::builder().clock(clock.clone()).build();
+        map.coalesce("a/", Duration::from_secs(10));
+        (clock, map)
+    }
+
+    #[async_std::test]
+    async fn should_coalesce_publishes_within_window() {
+        let (clock, map) = map();
+        let mut coalesced = map.get_or_insert("a/1", 0).await.unwrap();
+        let mut direct = map.get_or_insert("b/1", 0).await.unwrap();
+
+        coalesced.publish(1).unwrap();
+        coalesced.publish(2).unwrap();
+        direct.publish(1).unwrap();
+        direct.publish(2).unwrap();
+
+        assert_eq!(coalesced.latest(), 1);
+        assert_eq!(direct.latest(), 2);
+
+        // publishes after the window ended flush the buffered value
+        clock.advance(Duration::from_secs(10));
+        coalesced.modify(|v| *v += 1).unwrap();
+        assert_eq!(coalesced.latest(), 3);
+
+        coalesced.publish(4).unwrap();
+        clock.advance(Duration::from_secs(10));
+        map.sweep().await;
+        assert_eq!(coalesced.latest(), 4);
+    }
+
+    #[async_std::test]
+    async fn should_stop_coalescing_at_runtime() {
+        let (clock, map) = map();
+        let mut subscription = map.get_or_insert("a/1", 0).await.unwrap();
+
+        assert!(map.stop_coalescing(&"a/"));
+        assert!(!map.stop_coalescing(&"a/"));
+
+        subscription.publish(1).unwrap();
+        subscription.publish(2).unwrap();
+        assert_eq!(subscription.latest(), 2);
+
+        map.coalesce("a/", Duration::from_secs(1));
+        subscription.publish(3).unwrap();
+        subscription.publish(4).unwrap();
+        assert_eq!(subscription.latest(), 3);
+
+        clock.advance(Duration::from_secs(1));
+        map.sweep().await;
+        assert_eq!(subscription.latest(), 4);
+        assert!(map.publish_state().coalescing.windows.is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+use crate::SubscriptionMap;
+use async_observable::Observable;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// What [`SubscriptionMap::compact`] rebuilt.
+///
+/// Safe Rust can't observe how much memory the allocator actually returns to the operating
+/// system, hence this reports the amount of rebuilt structures instead of bytes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Compaction {
+    /// Amount of entries moved into freshly allocated storage
+    pub entries: usize,
+    /// Amount of unreferenced observables replaced by ones without retained waker storage
+    pub observables: usize,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Rebuild the internal storage to release memory retained after heavy churn.
+    ///
+    /// The entries are moved into a densely packed tree and observables of unreferenced entries,
+    /// which retain the storage of every subscriber they ever had, are recreated. Values and
+    /// subscriptions are unaffected. This holds the lock of the map for the whole rebuild, so it
+    /// should be called in quiet periods.
+    pub async fn compact(&self) -> Compaction {
+        let mut map = self.lock_entries(&self.0.entries()).await;
+        let mut compaction = Compaction::default();
+
+        let rebuilt: BTreeMap<_, _> = std::mem::take(&mut *map)
+            .into_iter()
+            .map(|(key, mut entry)| {
+                compaction.entries += 1;
+
+                // no ref holds a clone of the observable so it can be replaced
+                if entry.rc() == 0 {
+                    entry.observable = Observable::new(entry.observable.latest());
+                    compaction.observables += 1;
+                }
+
+                (key, entry)
+            })
+            .collect();
+
+        *map = rebuilt;
+        compaction
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Compaction;
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_keep_values_and_subscriptions() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 1).await.unwrap();
+        map.pin(2, 2).await.unwrap();
+
+        for key in 10..100 {
+            drop(map.get_or_insert(key, 0).await.unwrap());
+        }
+
+        let compaction = map.compact().await;
+        assert_eq!(
+            compaction,
+            Compaction {
+                entries: 2,
+                observables: 1
+            }
+        );
+
+        assert!(map.publish_if_changed(&1, 5).await.unwrap());
+        assert_eq!(subscription.next().await, 5);
+        assert_eq!(map.get_or_insert(2, 0).await.unwrap().latest(), 2);
+    }
+}
@@ -0,0 +1,246 @@
+//! A scenario suite which checks the map against the executor of your choice.
+//!
+//! The map doesn't spawn tasks on its own, but its cleanup runs in the drop of refs, which
+//! happens on whatever executor and thread the last ref is dropped on. The scenarios exercise
+//! exactly that across tasks and threads, implement [`Runtime`] for an executor to run them.
+//! The crate itself runs them on async-std, smol and the multi and current thread runtimes of
+//! tokio.
+//!
+//! ```
+//! # use async_subscription_map::conformance::{self, Runtime};
+//! # use futures::future::BoxFuture;
+//! # use std::sync::Arc;
+//! struct AsyncStd;
+//!
+//! impl Runtime for AsyncStd {
+//!     fn block_on(&self, future: BoxFuture<'static, ()>) {
+//!         async_std::task::block_on(future)
+//!     }
+//!
+//!     fn spawn(&self, future: BoxFuture<'static, ()>) -> BoxFuture<'static, ()> {
+//!         Box::pin(async_std::task::spawn(future))
+//!     }
+//! }
+//!
+//! conformance::run_all(Arc::new(AsyncStd));
+//! ```
+use crate::SubscriptionMap;
+use futures::future::{self, BoxFuture};
+use std::sync::Arc;
+
+/// An executor the scenarios run on
+pub trait Runtime: Send + Sync + 'static {
+    /// Run the future to completion on the calling thread
+    fn block_on(&self, future: BoxFuture<'static, ()>);
+
+    /// Run the future concurrently, the returned future resolves once it completed
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> BoxFuture<'static, ()>;
+}
+
+/// A named scenario, it panics if the map misbehaves on the runtime
+pub type Scenario = (&'static str, fn(Arc<dyn Runtime>) -> BoxFuture<'static, ()>);
+
+/// All scenarios of the suite
+pub fn scenarios() -> Vec<Scenario> {
+    vec![
+        ("drop_on_other_task", |rt| Box::pin(drop_on_other_task(rt))),
+        ("publish_across_tasks", |rt| {
+            Box::pin(publish_across_tasks(rt))
+        }),
+        ("concurrent_subscribe_and_release", |rt| {
+            Box::pin(concurrent_subscribe_and_release(rt))
+        }),
+        ("release_while_locked", |rt| {
+            Box::pin(release_while_locked(rt))
+        }),
+    ]
+}
+
+/// Run all scenarios one after another, panics with the name of the first failing one
+pub fn run_all(runtime: Arc<dyn Runtime>) {
+    for (name, scenario) in scenarios() {
+        let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            runtime.block_on(scenario(runtime.clone()))
+        }));
+
+        if let Err(panic) = outcome {
+            let reason = panic
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| panic.downcast_ref::<&str>().copied())
+                .unwrap_or("unknown panic");
+
+            panic!("scenario {} failed: {}", name, reason);
+        }
+    }
+}
+
+async fn drop_on_other_task(runtime: Arc<dyn Runtime>) {
+    let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+    let subscription = map.get_or_insert(1, 0).await.unwrap();
+
+    runtime
+        .spawn(Box::pin(async move { drop(subscription) }))
+        .await;
+    assert!(map.is_empty().await, "entry survived its last ref");
+}
+
+async fn publish_across_tasks(runtime: Arc<dyn Runtime>) {
+    let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+    let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    let (received, receive) = futures::channel::oneshot::channel();
+
+    let subscriber = runtime.spawn(Box::pin(async move {
+        received.send(subscription.next().await).ok();
+    }));
+
+    map.publish_if_changed(&1, 1).await.unwrap();
+    subscriber.await;
+    assert_eq!(receive.await, Ok(1), "subscriber missed the publish");
+
+    // the subscriber may drop its ref while the publish still holds the entries
+    map.sweep().await;
+    assert!(map.is_empty().await, "entry survived its last ref");
+}
+
+async fn concurrent_subscribe_and_release(runtime: Arc<dyn Runtime>) {
+    let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+
+    let tasks = (0..8).map(|task| {
+        let map = map.clone();
+
+        runtime.spawn(Box::pin(async move {
+            for round in 0..100 {
+                let subscription = map.get_or_insert(round % 4, task).await.unwrap();
+                drop(subscription);
+            }
+        }))
+    });
+
+    future::join_all(tasks).await;
+    map.sweep().await;
+    assert!(map.is_empty().await, "entries survived their refs");
+}
+
+async fn release_while_locked(runtime: Arc<dyn Runtime>) {
+    let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+    let subscription = map.get_or_insert(1, 0).await.unwrap();
+    let locked = map.entry(2).await;
+
+    // the release can't lock the entries and is deferred
+    runtime
+        .spawn(Box::pin(async move { drop(subscription) }))
+        .await;
+    assert!(locked.or_insert(0).is_ok());
+
+    map.sweep().await;
+    assert!(!map.contains_key(&1).await, "deferred release was lost");
+    assert!(map.is_empty().await, "entries survived their refs");
+}
+
+#[cfg(test)]
+mod test {
+    use super::Runtime;
+    use async_executor::Executor;
+    use futures::future::BoxFuture;
+    use std::sync::Arc;
+
+    struct AsyncStd;
+
+    impl Runtime for AsyncStd {
+        fn block_on(&self, future: BoxFuture<'static, ()>) {
+            async_std::task::block_on(future)
+        }
+
+        fn spawn(&self, future: BoxFuture<'static, ()>) -> BoxFuture<'static, ()> {
+            Box::pin(async_std::task::spawn(future))
+        }
+    }
+
+    /// The executor of smol, driven by the calling thread and optional worker threads
+    struct Smol(Arc<Executor<'static>>);
+
+    impl Smol {
+        fn with_workers(workers: usize) -> Self {
+            let executor = Arc::new(Executor::new());
+
+            for _ in 0..workers {
+                let executor = executor.clone();
+                std::thread::spawn(move || {
+                    futures::executor::block_on(executor.run(futures::future::pending::<()>()))
+                });
+            }
+
+            Self(executor)
+        }
+    }
+
+    impl Runtime for Smol {
+        fn block_on(&self, future: BoxFuture<'static, ()>) {
+            futures::executor::block_on(self.0.run(future))
+        }
+
+        fn spawn(&self, future: BoxFuture<'static, ()>) -> BoxFuture<'static, ()> {
+            Box::pin(self.0.spawn(future))
+        }
+    }
+
+    /// A tokio runtime, either multi threaded or driven by the calling thread only
+    struct Tokio(tokio::runtime::Runtime);
+
+    impl Tokio {
+        fn multi_thread() -> Self {
+            let runtime = tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(4)
+                .build()
+                .unwrap();
+
+            Self(runtime)
+        }
+
+        fn current_thread() -> Self {
+            Self(
+                tokio::runtime::Builder::new_current_thread()
+                    .build()
+                    .unwrap(),
+            )
+        }
+    }
+
+    impl Runtime for Tokio {
+        fn block_on(&self, future: BoxFuture<'static, ()>) {
+            self.0.block_on(future)
+        }
+
+        fn spawn(&self, future: BoxFuture<'static, ()>) -> BoxFuture<'static, ()> {
+            let task = self.0.spawn(future);
+
+            Box::pin(async move {
+                if let Err(e) = task.await {
+                    std::panic::resume_unwind(e.into_panic());
+                }
+            })
+        }
+    }
+
+    #[test]
+    fn should_pass_on_async_std() {
+        super::run_all(Arc::new(AsyncStd));
+    }
+
+    #[test]
+    fn should_pass_on_multi_and_current_thread_executors() {
+        super::run_all(Arc::new(Smol::with_workers(4)));
+        super::run_all(Arc::new(Smol::with_workers(0)));
+    }
+
+    #[test]
+    fn should_pass_on_tokio_multi_thread() {
+        super::run_all(Arc::new(Tokio::multi_thread()));
+    }
+
+    #[test]
+    fn should_pass_on_tokio_current_thread() {
+        super::run_all(Arc::new(Tokio::current_thread()));
+    }
+}
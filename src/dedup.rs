@@ -0,0 +1,141 @@
+use crate::{diag, Clock, SubscriptionRef};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + PartialEq,
+{
+    /// Suppress values which equal the previously delivered one within the window.
+    ///
+    /// Producers which republish unchanged snapshots on a timer wake subscribers with a new
+    /// version every time. The returned ref only delivers such a value again once the window
+    /// since the previous delivery passed, according to the [`Clock`] of the map. The latest
+    /// value at the time of the call counts as delivered.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # use std::time::Duration;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut producer = map.get_or_insert(1, 0).await.unwrap();
+    /// let mut consumer = map
+    ///     .get_or_insert(1, 0)
+    ///     .await
+    ///     .unwrap()
+    ///     .dedup_within(Duration::from_secs(60));
+    ///
+    /// producer.publish(0).unwrap();
+    /// producer.publish(1).unwrap();
+    /// assert_eq!(consumer.next().await, 1);
+    /// # };
+    /// ```
+    pub fn dedup_within(self, window: Duration) -> DedupRef<K, V> {
+        let clock = self.owner.clock();
+        let delivered = (self.latest(), clock.now());
+
+        DedupRef {
+            subscription: self,
+            window,
+            clock,
+            delivered,
+        }
+    }
+}
+
+/// A subscription which skips repeated values, see [`SubscriptionRef::dedup_within`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct DedupRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscription: SubscriptionRef<K, V>,
+    window: Duration,
+    clock: Arc<dyn Clock>,
+    /// The previously delivered value and when it was delivered
+    delivered: (V, Instant),
+}
+
+impl<K, V> DedupRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + PartialEq,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// A clone of the latest value, without consuming the change.
+    pub fn latest(&self) -> V {
+        self.subscription.latest()
+    }
+
+    /// Wait until a value is published which differs from the previously delivered one or the
+    /// window since its delivery passed, and return a clone of it.
+    pub async fn next(&mut self) -> V {
+        loop {
+            let value = self.subscription.next().await;
+            let now = self.clock.now();
+            let (previous, at) = &self.delivered;
+
+            if value != *previous || now.saturating_duration_since(*at) >= self.window {
+                self.delivered = (value.clone(), now);
+                return value;
+            }
+
+            diag::trace!("suppressed repeated value within dedup window");
+        }
+    }
+
+    /// The wrapped subscription, which delivers every published version again
+    pub fn into_inner(self) -> SubscriptionRef<K, V> {
+        self.subscription
+    }
+}
+
+impl<K, V> Debug for DedupRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DedupRef")
+            .field("subscription", &self.subscription)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_suppress_repeated_values_within_window() {
+        let clock = ManualClock::new();
+        let map: SubscriptionMap<usize, usize> =
+            SubscriptionMap::builder().clock(clock.clone()).build();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        let mut consumer = producer.fork().await.dedup_within(Duration::from_secs(10));
+
+        producer.publish(1).unwrap();
+        assert_eq!(consumer.next().await, 1);
+
+        clock.advance(Duration::from_secs(5));
+        producer.publish(1).unwrap();
+        let pending = async_std::future::timeout(Duration::from_millis(10), consumer.next());
+        assert!(pending.await.is_err());
+
+        // the window starts with the delivery, not the suppressed publish
+        clock.advance(Duration::from_secs(5));
+        producer.publish(1).unwrap();
+        assert_eq!(consumer.next().await, 1);
+        assert_eq!(consumer.into_inner().latest(), 1);
+    }
+}
@@ -0,0 +1,386 @@
+//! A reference server which exposes a map over HTTP, subscriptions are streamed as server sent
+//! events.
+//!
+//! It exercises the map end to end the way a service would: every open event stream holds a ref,
+//! so closing the connection cleans up the entry once no one else subscribes to it. The server
+//! only understands the two endpoints below and is meant as a starting point and test bed, not as
+//! a production HTTP stack.
+//!
+//! - `GET /subscribe/<key>` streams the latest value of the key and every update after it, the
+//!   entry is initialized with an empty value if it doesn't exist yet
+//! - `POST /publish/<key>` publishes the request body to the existing entry of the key
+//!
+//! Keys are taken verbatim from the path, values are UTF-8 strings.
+//!
+//! ```
+//! # use async_subscription_map::{demo::DemoServer, SubscriptionMap};
+//! # async {
+//! let map = SubscriptionMap::<String, String>::default();
+//! let server = DemoServer::bind(([127, 0, 0, 1], 0), map.clone()).unwrap();
+//! println!("listening on {}", server.local_addr().unwrap());
+//!
+//! async_std::task::spawn(server.run());
+//! # };
+//! ```
+use crate::{diag, SubscriptionMap};
+use anyhow::{bail, Context};
+use async_io::Async;
+use futures::future::{self, Either};
+use futures::stream::FuturesUnordered;
+use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
+use std::fmt::{self, Debug};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+
+/// The largest request head the server accepts
+const MAX_HEAD: usize = 8 * 1024;
+
+/// The largest request body the server accepts
+const MAX_BODY: usize = 64 * 1024;
+
+/// The response head of subscriptions, followed by the events
+const EVENT_STREAM_HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n";
+
+/// The response to subscriptions the map rejects, e.g. while it is draining
+const UNAVAILABLE_HEAD: &[u8] = b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n";
+
+/// A reference HTTP server for a map, see the [module documentation](self).
+pub struct DemoServer {
+    map: SubscriptionMap<String, String>,
+    listener: Async<TcpListener>,
+}
+
+impl DemoServer {
+    /// Listen on the address, connections are only accepted once the server [runs](Self::run).
+    pub fn bind<A>(addr: A, map: SubscriptionMap<String, String>) -> anyhow::Result<Self>
+    where
+        A: Into<SocketAddr>,
+    {
+        let addr = addr.into();
+        let listener = Async::<TcpListener>::bind(addr)
+            .with_context(|| format!("unable to listen on {}", addr))?;
+
+        Ok(Self { map, listener })
+    }
+
+    /// The address the server listens on
+    pub fn local_addr(&self) -> anyhow::Result<SocketAddr> {
+        self.listener
+            .get_ref()
+            .local_addr()
+            .context("unable to get local address")
+    }
+
+    /// Serve all connections concurrently, only resolves if accepting connections fails.
+    ///
+    /// Like [`maintain`](SubscriptionMap::maintain) this doesn't spawn tasks, the connections
+    /// are driven by the returned future on the runtime of your choice.
+    pub async fn run(self) -> anyhow::Result<()> {
+        let mut connections = FuturesUnordered::new();
+
+        loop {
+            let accepted = {
+                let accept = self.listener.accept();
+                let served = async {
+                    match connections.next().await {
+                        Some(served) => served,
+                        None => future::pending().await,
+                    }
+                };
+                futures::pin_mut!(accept, served);
+
+                match future::select(accept, served).await {
+                    Either::Left((accepted, _)) => Some(accepted),
+                    Either::Right((served, _)) => {
+                        if let Err(e) = served {
+                            diag::debug!("demo connection failed: {:#}", e);
+                        }
+
+                        None
+                    }
+                }
+            };
+
+            if let Some(accepted) = accepted {
+                let (stream, _) = accepted.context("unable to accept connection")?;
+                connections.push(serve(self.map.clone(), stream));
+            }
+        }
+    }
+}
+
+impl Debug for DemoServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DemoServer")
+            .field("addr", &self.local_addr().ok())
+            .field("map", &self.map)
+            .finish()
+    }
+}
+
+/// The parts of a request the endpoints need
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+async fn serve(
+    map: SubscriptionMap<String, String>,
+    stream: Async<TcpStream>,
+) -> anyhow::Result<()> {
+    let request = read_request(&stream).await?;
+
+    if let ("GET", Some(key)) = (
+        request.method.as_str(),
+        request.path.strip_prefix("/subscribe/"),
+    ) {
+        return stream_updates(&map, key, &stream).await;
+    }
+
+    let (status, message) = match (
+        request.method.as_str(),
+        request.path.strip_prefix("/publish/"),
+    ) {
+        ("POST", Some(key)) => publish(&map, key, request.body).await,
+        _ => ("404 Not Found", String::from("unknown endpoint")),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+        status,
+        message.len(),
+        message
+    );
+
+    (&stream).write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+async fn publish(
+    map: &SubscriptionMap<String, String>,
+    key: &str,
+    body: Vec<u8>,
+) -> (&'static str, String) {
+    let value = match String::from_utf8(body) {
+        Ok(value) => value,
+        Err(_) => return ("400 Bad Request", String::from("value is not utf-8")),
+    };
+
+    let key = key.to_string();
+
+    if !map.contains_key(&key).await {
+        return (
+            "404 Not Found",
+            String::from("no one subscribes to the key"),
+        );
+    }
+
+    match map.publish(&key, value).await {
+        Ok(()) => ("200 OK", String::new()),
+        Err(e) => ("409 Conflict", format!("{:#}", e)),
+    }
+}
+
+/// Stream the value of the key until the client closes the connection
+async fn stream_updates(
+    map: &SubscriptionMap<String, String>,
+    key: &str,
+    stream: &Async<TcpStream>,
+) -> anyhow::Result<()> {
+    let mut writer = stream;
+
+    let mut subscription = match map.get_or_insert(key.to_string(), String::new()).await {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            writer.write_all(UNAVAILABLE_HEAD).await?;
+            return Err(e);
+        }
+    };
+
+    writer.write_all(EVENT_STREAM_HEAD).await?;
+
+    let mut value = subscription.latest();
+
+    loop {
+        writer.write_all(event(&value).as_bytes()).await?;
+
+        let next = subscription.next();
+        let closed = closed(stream);
+        futures::pin_mut!(next, closed);
+
+        match future::select(next, closed).await {
+            Either::Left((next, _)) => value = next,
+            // dropping the ref cleans up the entry if this was the last subscriber
+            Either::Right(_) => return Ok(()),
+        }
+    }
+}
+
+/// Format the value as a server sent event, every line of the value is a data line
+fn event(value: &str) -> String {
+    let mut event: String = value
+        .split('\n')
+        .map(|line| format!("data: {}\n", line))
+        .collect();
+
+    event.push('\n');
+    event
+}
+
+/// Wait until the client closes its side of the connection
+async fn closed(stream: &Async<TcpStream>) {
+    let mut reader = stream;
+    let mut buf = [0; 512];
+
+    while let Ok(1..) = reader.read(&mut buf).await {}
+}
+
+async fn read_request(stream: &Async<TcpStream>) -> anyhow::Result<Request> {
+    let mut reader = stream;
+    let mut buf = Vec::new();
+    let mut chunk = [0; 1024];
+
+    let head_len = loop {
+        if let Some(end) = buf.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+
+        if buf.len() > MAX_HEAD {
+            bail!("request head exceeds {} bytes", MAX_HEAD);
+        }
+
+        match reader.read(&mut chunk).await? {
+            0 => bail!("connection closed before the request head was complete"),
+            read => buf.extend_from_slice(&chunk[..read]),
+        }
+    };
+
+    let head = std::str::from_utf8(&buf[..head_len]).context("request head is not utf-8")?;
+    let mut lines = head.split("\r\n");
+
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (method, path) = match (request_line.next(), request_line.next()) {
+        (Some(method), Some(path)) => (method.to_string(), path.to_string()),
+        _ => bail!("malformed request line"),
+    };
+
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.trim().parse::<usize>())
+        .transpose()
+        .context("malformed content length")?
+        .unwrap_or(0);
+
+    if content_length > MAX_BODY {
+        bail!("request body exceeds {} bytes", MAX_BODY);
+    }
+
+    let mut body = buf.split_off(head_len);
+    body.truncate(content_length);
+
+    let start = body.len();
+    body.resize(content_length, 0);
+    reader.read_exact(&mut body[start..]).await?;
+
+    Ok(Request { method, path, body })
+}
+
+#[cfg(test)]
+mod test {
+    use super::DemoServer;
+    use crate::SubscriptionMap;
+    use async_io::Async;
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use std::net::{SocketAddr, TcpStream};
+    use std::time::Duration;
+
+    async fn start(map: &SubscriptionMap<String, String>) -> SocketAddr {
+        let server = DemoServer::bind(([127, 0, 0, 1], 0), map.clone()).unwrap();
+        let addr = server.local_addr().unwrap();
+
+        async_std::task::spawn(server.run());
+        addr
+    }
+
+    async fn request(addr: SocketAddr, request: &str) -> Async<TcpStream> {
+        let mut stream = Async::<TcpStream>::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        stream
+    }
+
+    /// Read until the stream received the text
+    async fn expect(stream: &mut Async<TcpStream>, text: &str) {
+        let mut received = Vec::new();
+        let mut chunk = [0; 256];
+
+        while !String::from_utf8_lossy(&received).contains(text) {
+            let read = stream.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "connection closed before receiving {:?}", text);
+            received.extend_from_slice(&chunk[..read]);
+        }
+    }
+
+    async fn publish(addr: SocketAddr, key: &str, value: &str) -> String {
+        let mut stream = request(
+            addr,
+            &format!(
+                "POST /publish/{} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                key,
+                value.len(),
+                value
+            ),
+        )
+        .await;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[async_std::test]
+    async fn should_stream_published_values() {
+        let map = SubscriptionMap::<String, String>::new();
+        let addr = start(&map).await;
+
+        let mut subscriber = request(addr, "GET /subscribe/topic HTTP/1.1\r\n\r\n").await;
+        expect(
+            &mut subscriber,
+            "text/event-stream\r\nCache-Control: no-cache\r\n\r\ndata: \n\n",
+        )
+        .await;
+
+        assert!(publish(addr, "topic", "hello\nworld")
+            .await
+            .starts_with("HTTP/1.1 200 OK"));
+        expect(&mut subscriber, "data: hello\ndata: world\n\n").await;
+
+        assert!(publish(addr, "other", "value")
+            .await
+            .starts_with("HTTP/1.1 404 Not Found"));
+    }
+
+    #[async_std::test]
+    async fn should_clean_up_entries_of_closed_streams() {
+        let map = SubscriptionMap::<String, String>::new();
+        let addr = start(&map).await;
+
+        let mut subscriber = request(addr, "GET /subscribe/topic HTTP/1.1\r\n\r\n").await;
+        expect(&mut subscriber, "data: \n\n").await;
+        assert!(map.contains_key(&"topic".to_string()).await);
+
+        drop(subscriber);
+
+        // the server notices the closed connection on another task in real time
+        for _ in 0..1000 {
+            if map.is_empty().await {
+                return;
+            }
+
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+
+        panic!("entry of the closed stream was not cleaned up");
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{diag, SubscriptionEntry, SubscriptionMap};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::MutexGuard;
+
+/// What happens to a derived entry once one of its sources is removed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Invalidation {
+    /// Notify the subscribers of the derived entry through
+    /// [`SubscriptionRef::invalidated`](crate::SubscriptionRef::invalidated)
+    Notify,
+    /// Notify the subscribers and remove the derived entry as soon as it is unreferenced,
+    /// regardless of pins and retention
+    Remove,
+}
+
+/// The declared dependencies between entries of a map
+pub(crate) struct Dependencies<K> {
+    /// The sources and invalidation of every derived key
+    derived: BTreeMap<K, (BTreeSet<K>, Invalidation)>,
+    /// The derived keys of every source key
+    dependents: BTreeMap<K, BTreeSet<K>>,
+}
+
+impl<K> Default for Dependencies<K> {
+    fn default() -> Self {
+        Self {
+            derived: BTreeMap::new(),
+            dependents: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K> Dependencies<K>
+where
+    K: Clone + Ord,
+{
+    /// Forget the sources of the derived key
+    fn forget(&mut self, derived: &K) {
+        let sources = match self.derived.remove(derived) {
+            Some((sources, _)) => sources,
+            None => return,
+        };
+
+        for source in sources {
+            if let Some(dependents) = self.dependents.get_mut(&source) {
+                dependents.remove(derived);
+
+                if dependents.is_empty() {
+                    self.dependents.remove(&source);
+                }
+            }
+        }
+    }
+
+    /// The derived keys of the source and how they are invalidated
+    fn dependents_of(&self, source: &K) -> Vec<(K, Invalidation)> {
+        self.dependents
+            .get(source)
+            .into_iter()
+            .flatten()
+            .filter_map(|derived| {
+                let (_, invalidation) = self.derived.get(derived)?;
+                Some((derived.clone(), *invalidation))
+            })
+            .collect()
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn dependencies(&self) -> MutexGuard<'_, Dependencies<K>> {
+        self.0
+            .dependencies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Declare that the derived entry is computed from the source entries, so it is invalidated
+    /// as soon as any of them is removed from the map.
+    ///
+    /// This replaces earlier declarations of the derived key. Declarations are forgotten once the
+    /// derived entry is removed, invalidations cascade through derived entries which are removed
+    /// themselves.
+    ///
+    /// ```
+    /// # use async_subscription_map::{Invalidation, SubscriptionMap};
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let source = map.get_or_insert("celsius", 21).await.unwrap();
+    /// let mut derived = map.get_or_insert("fahrenheit", 70).await.unwrap();
+    ///
+    /// map.depends_on("fahrenheit", ["celsius"], Invalidation::Notify);
+    ///
+    /// drop(source);
+    /// derived.invalidated().await;
+    /// # };
+    /// ```
+    pub fn depends_on<I>(&self, derived: K, sources: I, invalidation: Invalidation)
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let sources: BTreeSet<K> = sources.into_iter().collect();
+        let mut dependencies = self.dependencies();
+
+        dependencies.forget(&derived);
+
+        for source in sources.iter() {
+            dependencies
+                .dependents
+                .entry(source.clone())
+                .or_default()
+                .insert(derived.clone());
+        }
+
+        dependencies
+            .derived
+            .insert(derived, (sources, invalidation));
+    }
+
+    /// Forget the declared sources of the derived key, returns if there were any
+    pub fn remove_dependencies(&self, derived: &K) -> bool {
+        let mut dependencies = self.dependencies();
+        let declared = dependencies.derived.contains_key(derived);
+
+        dependencies.forget(derived);
+        declared
+    }
+
+    /// Invalidate the dependents of a removed entry, removing the ones which are due as well
+    pub(crate) fn invalidate_dependents(
+        &self,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+        removed: &K,
+    ) {
+        let mut dependencies = self.dependencies();
+        let mut pending = vec![removed.clone()];
+
+        while let Some(source) = pending.pop() {
+            dependencies.forget(&source);
+
+            for (derived, invalidation) in dependencies.dependents_of(&source) {
+                let entry = match map.get_mut(&derived) {
+                    Some(entry) => entry,
+                    None => continue,
+                };
+
+                diag::debug!(
+                    "invalidating {:?} after removal of {:?}",
+                    self.redacted_key(&derived),
+                    self.redacted_key(&source)
+                );
+                entry.invalidation.publish(());
+
+                if invalidation == Invalidation::Remove {
+                    entry.pinned = false;
+                    entry.retain_until = None;
+
+                    if entry.rc() == 0 {
+                        if let Some(entry) = map.remove(&derived) {
+                            self.record_lifetime(&derived, &entry);
+                            pending.push(derived);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Invalidation;
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_notify_dependents_of_removed_sources() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let source = map.get_or_insert(1, 0).await.unwrap();
+        let mut derived = map.get_or_insert(2, 0).await.unwrap();
+
+        map.depends_on(2, [1], Invalidation::Notify);
+        drop(source);
+        derived.invalidated().await;
+
+        // the declaration is kept, the derived entry is not removed
+        let _source = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(map.entries_snapshot().await.len(), 2);
+        assert!(map.remove_dependencies(&2));
+        assert!(!map.remove_dependencies(&2));
+    }
+
+    #[async_std::test]
+    async fn should_cascade_removal_of_dependents() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let source = map.get_or_insert(1, 0).await.unwrap();
+        map.pin(2, 0).await.unwrap();
+        map.pin(3, 0).await.unwrap();
+        let mut referenced = map.get_or_insert(4, 0).await.unwrap();
+
+        map.depends_on(2, [1], Invalidation::Remove);
+        map.depends_on(3, [2], Invalidation::Remove);
+        map.depends_on(4, [1], Invalidation::Remove);
+
+        drop(source);
+        referenced.invalidated().await;
+        assert_eq!(
+            map.entries_snapshot().await.keys().collect::<Vec<_>>(),
+            vec![&4]
+        );
+
+        drop(referenced);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+        assert!(map.dependencies().dependents.is_empty());
+    }
+}
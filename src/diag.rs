@@ -0,0 +1,38 @@
+//! Logging of the map, compiled out entirely without the `log` feature.
+//!
+//! The macros take the same arguments as the ones of the `log` crate, `warning` stands in for
+//! `warn`. With the feature they forward to them, without it the arguments are only type checked
+//! so they still count as used.
+
+macro_rules! emit {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        log::$level!($($arg)+);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+macro_rules! trace {
+    ($($arg:tt)+) => { $crate::diag::emit!(trace, $($arg)+) };
+}
+
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::diag::emit!(debug, $($arg)+) };
+}
+
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::diag::emit!(info, $($arg)+) };
+}
+
+macro_rules! warning {
+    ($($arg:tt)+) => { $crate::diag::emit!(warn, $($arg)+) };
+}
+
+macro_rules! error {
+    ($($arg:tt)+) => { $crate::diag::emit!(error, $($arg)+) };
+}
+
+pub(crate) use {debug, emit, error, info, trace, warning};
@@ -0,0 +1,117 @@
+use crate::SubscriptionMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Reject all new subscriptions with [`Error::Draining`](crate::Error::Draining).
+    ///
+    /// Existing refs keep receiving updates and may still publish, so the map empties itself
+    /// as they are dropped. This enables rolling restarts where new load is directed to another
+    /// instance while this one finishes its work.
+    ///
+    /// ```
+    /// # use async_subscription_map::{Error, SubscriptionMap};
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    ///
+    /// map.drain();
+    /// let err = map.get_or_insert(1, 0).await.unwrap_err();
+    /// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+    ///
+    /// map.publish_if_changed(&1, 1).await.unwrap();
+    /// assert_eq!(subscription.next().await, 1);
+    /// # };
+    /// ```
+    pub fn drain(&self) {
+        self.0.draining.store(true, Ordering::SeqCst);
+    }
+
+    /// Accept new subscriptions again after [`drain`](Self::drain).
+    pub fn undrain(&self) {
+        self.0.draining.store(false, Ordering::SeqCst);
+        self.0.undrained.clone().publish(());
+    }
+
+    /// Check if the map is currently draining
+    pub fn is_draining(&self) -> bool {
+        self.0.draining.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_reject_new_subscriptions_while_draining() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.drain();
+        assert!(map.is_draining());
+
+        let err = map.get_or_insert(1, 0).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+        assert!(map.get_or_insert(2, 0).await.is_err());
+        assert!(map.get_or_insert_read_only(2, 0).await.is_err());
+        assert!(map
+            .restricted_view(|_, _| true)
+            .subscribe(2, 0)
+            .await
+            .is_err());
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 1);
+
+        subscription.publish(1).unwrap();
+        assert_eq!(subscription.next().await, 1);
+
+        drop(subscription);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_reject_subscriptions_to_existing_entries_while_draining() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let base: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let weak = subscription.downgrade();
+
+        map.drain();
+        assert!(weak.upgrade().await.is_none());
+
+        let err = subscription.frozen().await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+
+        let err = map.overlay(&base).get_or_insert(1, 0).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+
+        let ttl = Duration::from_secs(10);
+        let err = map.get_or_insert_leased(1, 0, ttl).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+
+        let err = map.task_scope().get_or_insert(1, 0).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 1);
+
+        map.undrain();
+        assert!(weak.upgrade().await.is_some());
+    }
+
+    #[async_std::test]
+    async fn should_accept_subscriptions_after_undrain() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+
+        map.drain();
+        assert!(map.get_or_insert(1, 0).await.is_err());
+
+        map.undrain();
+        assert!(!map.is_draining());
+        assert!(map.get_or_insert(1, 0).await.is_ok());
+    }
+}
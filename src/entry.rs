@@ -0,0 +1,202 @@
+use crate::{Entries, Error, SubscriptionEntry, SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use async_lock::RwLockWriteGuardArc;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Lock the map and inspect the entry at the key, similar to the entry api of std maps.
+    ///
+    /// The whole initialization flow happens under a single lock acquisition, hence the entry
+    /// must be consumed before the map is used again by the same task.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, Vec<u8>>::default();
+    ///
+    /// let subscription = map
+    ///     .entry("config")
+    ///     .await
+    ///     .and_modify(|config| config.push(1))
+    ///     .unwrap()
+    ///     .pin()
+    ///     .or_insert_with(|| vec![0])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(subscription.latest(), vec![0]);
+    /// # };
+    /// ```
+    pub async fn entry(&self, key: K) -> Entry<K, V> {
+        let entries = self.0.entries();
+        let map = self.lock_entries(&entries).await;
+
+        Entry {
+            owner: self.internal(),
+            entries,
+            map,
+            key,
+            pin: false,
+        }
+    }
+}
+
+/// A locked view of a single entry of the map, see [`SubscriptionMap::entry`].
+#[must_use = "the map stays locked until the entry is consumed or dropped"]
+pub struct Entry<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    owner: SubscriptionMap<K, V>,
+    entries: Arc<Entries<K, V>>,
+    map: RwLockWriteGuardArc<BTreeMap<K, SubscriptionEntry<V>>>,
+    key: K,
+    pin: bool,
+}
+
+impl<K, V> Entry<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the entry
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Check if the entry already exists
+    pub fn is_occupied(&self) -> bool {
+        self.map.contains_key(&self.key)
+    }
+
+    /// Modify and publish the value if the entry exists.
+    ///
+    /// Fails like [`SubscriptionRef::modify`] if the map rejects publishes.
+    pub fn and_modify<F>(mut self, modify: F) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&mut V),
+    {
+        if let Some(entry) = self.map.get_mut(&self.key) {
+            self.owner
+                .publish_gate(&self.key, entry.target(), |_| true, modify)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Pin the entry once the ref is created, see [`SubscriptionMap::pin`].
+    pub fn pin(mut self) -> Self {
+        self.pin = true;
+        self
+    }
+
+    /// Subscribe to the entry and insert the value if it doesn't exist yet.
+    pub fn or_insert(self, value: V) -> anyhow::Result<SubscriptionRef<K, V>> {
+        self.or_insert_with(|| value)
+    }
+
+    /// Subscribe to the entry and insert the computed value if it doesn't exist yet.
+    ///
+    /// Fails with [`Error::Draining`] once the map is [draining](SubscriptionMap::drain).
+    pub fn or_insert_with<F>(mut self, value: F) -> anyhow::Result<SubscriptionRef<K, V>>
+    where
+        F: FnOnce() -> V,
+    {
+        if self.owner.0.draining.load(Ordering::SeqCst) {
+            return Err(Error::Draining).with_context(|| {
+                format!(
+                    "unable to subscribe to {:?}",
+                    self.owner.redacted_key(&self.key)
+                )
+            });
+        }
+
+        let subscription =
+            self.owner
+                .attach_with(&self.entries, &mut self.map, self.key.clone(), value)?;
+
+        if self.pin {
+            if let Some(entry) = self.map.get_mut(&self.key) {
+                entry.pinned = true;
+            }
+        }
+
+        Ok(subscription)
+    }
+}
+
+impl<K, V> Entry<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Default,
+{
+    /// Subscribe to the entry and insert the default value if it doesn't exist yet.
+    pub fn or_default(self) -> anyhow::Result<SubscriptionRef<K, V>> {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<K, V> Debug for Entry<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Entry")
+            .field("key", &self.owner.redacted_key(&self.key))
+            .field("occupied", &self.is_occupied())
+            .field("pin", &self.pin)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+
+    #[async_std::test]
+    async fn should_insert_or_modify_entry() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+
+        let first = map
+            .entry(1)
+            .await
+            .and_modify(|v| *v += 1)
+            .unwrap()
+            .or_insert(1)
+            .unwrap();
+        assert_eq!(first.latest(), 1);
+
+        let second = map
+            .entry(1)
+            .await
+            .and_modify(|v| *v += 1)
+            .unwrap()
+            .or_insert_with(|| unreachable!())
+            .unwrap();
+        assert_eq!(second.latest(), 2);
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 2);
+
+        drop((first, second));
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_pin_entry() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        drop(map.entry(1).await.pin().or_default().unwrap());
+        assert!(map.entries_snapshot().await.get(&1).unwrap().pinned);
+
+        map.drain();
+        let err = map.entry(2).await.or_default().unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+    }
+}
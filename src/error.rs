@@ -0,0 +1,65 @@
+#[cfg(feature = "fault-injection")]
+use crate::fault::FaultPoint;
+use crate::view::Access;
+use std::fmt;
+
+/// Typed failure conditions of the subscription map.
+///
+/// All fallible operations return an `anyhow::Result`, errors which callers might want to handle
+/// explicitly carry one of these variants and can be recovered via `downcast_ref::<Error>()`.
+///
+/// ```
+/// # use async_subscription_map::{Access, Error, SubscriptionMap};
+/// # async {
+/// let map = SubscriptionMap::<usize, usize>::default();
+/// let view = map.restricted_view(|key, _| *key < 10);
+///
+/// let err = view.get_or_insert(42, 0).await.unwrap_err();
+/// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::AccessDenied(Access::Subscribe)));
+/// # };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The policy of a restricted view rejected the operation
+    AccessDenied(Access),
+    /// The map is frozen and rejects publishes
+    Frozen,
+    /// The map is draining and rejects new subscriptions
+    Draining,
+    /// All entries of a bounded map are occupied
+    CapacityExceeded,
+    /// The lease of a subscription expired or the task it was bound to ended
+    LeaseExpired,
+    /// No value was published within the heartbeat interval, the value is unchanged
+    StalePublisher,
+    /// The value exceeds the configured maximum value size
+    ValueTooLarge,
+    /// The p99 publish latency exceeds the configured budget
+    LatencyBudgetExceeded,
+    /// The circuit breaker of the key is open after repeated failures of its producers
+    CircuitOpen,
+    /// A failure was injected at a fault point
+    #[cfg(feature = "fault-injection")]
+    InjectedFault(FaultPoint),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AccessDenied(access) => write!(f, "{:?} access denied by policy", access),
+            Error::Frozen => write!(f, "map is frozen"),
+            Error::Draining => write!(f, "map is draining"),
+            Error::CapacityExceeded => write!(f, "map capacity exceeded"),
+            Error::LeaseExpired => write!(f, "lease expired"),
+            Error::StalePublisher => write!(f, "no publish within heartbeat interval"),
+            Error::ValueTooLarge => write!(f, "value exceeds the size limit"),
+            Error::LatencyBudgetExceeded => write!(f, "publish latency exceeds the budget"),
+            Error::CircuitOpen => write!(f, "circuit breaker is open"),
+            #[cfg(feature = "fault-injection")]
+            Error::InjectedFault(point) => write!(f, "injected fault at {:?}", point),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
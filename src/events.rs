@@ -0,0 +1,539 @@
+use crate::{EntryState, KeyPrefix, SubscriptionMap, SubscriptionRef};
+use futures::future::{self, Either};
+use futures::{Stream, StreamExt};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::task::{Context, Poll, Waker};
+
+/// A change of the set of entries in a map
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MapEvent<K> {
+    /// An entry was created
+    Inserted(K),
+    /// An entry was removed
+    Removed(K),
+    /// The last clone of the map was dropped, see [`SubscriptionMap`]
+    Closed,
+    /// An entry moved to another stage of its lifecycle, only delivered by
+    /// [`state_events`](SubscriptionMap::state_events)
+    StateChanged(K, EntryState),
+}
+
+/// A prefix and the function matching keys against it
+type PrefixFilter<K> = (K, fn(&K, &K) -> bool);
+
+/// The events a subscriber didn't receive yet
+pub(crate) struct EventQueue<K> {
+    state: Mutex<QueueState<K>>,
+    /// Only events of keys under the prefix are queued
+    prefix: Option<PrefixFilter<K>>,
+    /// If state changes are queued as well
+    states: bool,
+}
+
+struct QueueState<K> {
+    /// Cancelled events are left as holes, so cancelling doesn't move the events queued since
+    events: Vec<Option<MapEvent<K>>>,
+    /// The amount of events which weren't cancelled
+    pending: usize,
+    /// Set once [`MapEvent::Closed`] is queued, the stream ends after delivering it
+    closed: bool,
+    /// The positions of the pending insertion of a key and the state changes queued since
+    inserted: BTreeMap<K, Vec<usize>>,
+    waker: Option<Waker>,
+}
+
+impl<K> QueueState<K> {
+    fn push(&mut self, event: MapEvent<K>) -> usize {
+        self.events.push(Some(event));
+        self.pending += 1;
+        self.events.len() - 1
+    }
+
+    fn take(&mut self) -> Vec<MapEvent<K>> {
+        self.inserted.clear();
+        self.pending = 0;
+        std::mem::take(&mut self.events)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+impl<K> EventQueue<K> {
+    fn state(&self) -> MutexGuard<'_, QueueState<K>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<K> EventQueue<K>
+where
+    K: Clone + Ord,
+{
+    /// Queue the event, a removal cancels out the pending insertion of the same key and the
+    /// state changes since
+    fn push(&self, event: MapEvent<K>) {
+        if let (MapEvent::StateChanged(..), false) = (&event, self.states) {
+            return;
+        }
+
+        if let (
+            Some((prefix, matches)),
+            MapEvent::Inserted(key) | MapEvent::Removed(key) | MapEvent::StateChanged(key, _),
+        ) = (&self.prefix, &event)
+        {
+            if !matches(key, prefix) {
+                return;
+            }
+        }
+
+        let mut state = self.state();
+
+        match event {
+            MapEvent::Removed(key) => match state.inserted.remove(&key) {
+                Some(cancelled) => {
+                    state.pending -= cancelled.len();
+
+                    for index in cancelled {
+                        state.events[index] = None;
+                    }
+                }
+                None => {
+                    state.push(MapEvent::Removed(key));
+                }
+            },
+            MapEvent::Inserted(key) => {
+                let index = state.push(MapEvent::Inserted(key.clone()));
+                state.inserted.insert(key, vec![index]);
+            }
+            MapEvent::StateChanged(key, entry_state) => {
+                let index = state.push(MapEvent::StateChanged(key.clone(), entry_state));
+
+                if let Some(since) = state.inserted.get_mut(&key) {
+                    since.push(index);
+                }
+            }
+            MapEvent::Closed => {
+                state.closed = true;
+                state.push(MapEvent::Closed);
+            }
+        }
+
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn event_queues(&self) -> MutexGuard<'_, Vec<Weak<EventQueue<K>>>> {
+        self.0.events.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Subscribe to insertions and removals of entries, delivered in batches.
+    ///
+    /// Every item contains all events since the previous one, so the subscriber is woken once
+    /// per burst instead of once per entry, e.g. when thousands of entries are created at once.
+    /// Entries which are inserted and removed again within the same batch are left out entirely.
+    /// Events are queued until they are received, so the stream should be polled continuously.
+    /// The stream ends after the batch containing [`MapEvent::Closed`].
+    ///
+    /// ```
+    /// # use async_subscription_map::{MapEvent, SubscriptionMap};
+    /// # use futures::StreamExt;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut events = map.lifecycle_events();
+    ///
+    /// map.warm((0..1000).map(|key| (key, 0)), std::time::Duration::from_secs(60))
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let batch = events.next().await.unwrap();
+    /// assert_eq!(batch.len(), 1000);
+    /// assert_eq!(batch[0], MapEvent::Inserted(0));
+    /// # };
+    /// ```
+    pub fn lifecycle_events(&self) -> LifecycleEvents<K> {
+        self.subscribe_events(None, false)
+    }
+
+    /// Subscribe to insertions and removals of entries one event at a time.
+    ///
+    /// This is the unbatched form of [`lifecycle_events`](Self::lifecycle_events), e.g. for a
+    /// coordinator which spawns a worker per key. Entries which are inserted and removed again
+    /// before the stream is polled are left out just the same.
+    ///
+    /// ```
+    /// # use async_subscription_map::{MapEvent, SubscriptionMap};
+    /// # use futures::StreamExt;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let mut events = map.events();
+    ///
+    /// let topic = map.get_or_insert("topics/1", 0).await.unwrap();
+    /// assert_eq!(events.next().await, Some(MapEvent::Inserted("topics/1")));
+    ///
+    /// drop(topic);
+    /// assert_eq!(events.next().await, Some(MapEvent::Removed("topics/1")));
+    /// # };
+    /// ```
+    pub fn events(&self) -> MapEvents<K> {
+        MapEvents::new(self.subscribe_events(None, false))
+    }
+
+    pub(crate) fn subscribe_events(
+        &self,
+        prefix: Option<PrefixFilter<K>>,
+        states: bool,
+    ) -> LifecycleEvents<K> {
+        let queue = Arc::new(EventQueue {
+            state: Mutex::new(QueueState {
+                events: Vec::new(),
+                pending: 0,
+                // subscribers of a closed map never receive its closing event
+                closed: self.is_closed(),
+                inserted: BTreeMap::new(),
+                waker: None,
+            }),
+            prefix,
+            states,
+        });
+
+        let mut queues = self.event_queues();
+        queues.retain(|queue| queue.strong_count() > 0);
+        queues.push(Arc::downgrade(&queue));
+
+        LifecycleEvents { queue }
+    }
+
+    /// Wait until someone else creates the entry at the key and subscribe to it.
+    ///
+    /// Unlike [`get_or_insert`](Self::get_or_insert) readers don't need a placeholder value,
+    /// they are parked until a producer inserts the key. Returns right away if the entry already
+    /// exists, waits for the next insertion if it is removed again before the ref is created and
+    /// keeps waiting while the map is [draining](Self::drain). Returns `None` once the map is
+    /// closed, see [`MapEvent::Closed`].
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let reader = map.subscribe_created(1);
+    ///
+    /// let _producer = map.get_or_insert(1, 5).await.unwrap();
+    /// assert_eq!(reader.await.unwrap().latest(), 5);
+    /// # };
+    /// ```
+    pub async fn subscribe_created(&self, key: K) -> Option<SubscriptionRef<K, V>> {
+        // subscribe before looking at the entries so an insertion in between isn't missed
+        let mut events = self.subscribe_events(None, false);
+        let mut undrained = self.0.undrained.clone();
+
+        loop {
+            if let Some(subscription) = self.get(&key).await {
+                return Some(subscription);
+            }
+
+            // every batch or the end of a drain may have made the entry available, e.g. if it
+            // already existed while the map was draining
+            let batch = events.next();
+            let undrain = undrained.next();
+            futures::pin_mut!(undrain);
+
+            if let Either::Left((None, _)) = future::select(batch, undrain).await {
+                return None;
+            }
+        }
+    }
+
+    /// The largest amount of events a single subscriber didn't receive yet
+    pub(crate) fn event_backlog(&self) -> usize {
+        self.event_queues()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|queue| queue.state().pending)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Deliver the event to all subscribers
+    pub(crate) fn emit(&self, event: MapEvent<K>) {
+        let queues = self.event_queues();
+
+        for queue in queues.iter().filter_map(Weak::upgrade) {
+            queue.push(event.clone());
+        }
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord + KeyPrefix,
+    V: Clone + Debug,
+{
+    /// Same as [`lifecycle_events`](Self::lifecycle_events) but only for keys under the prefix.
+    ///
+    /// Consumers which mirror a group of keys, e.g. as rows of a UI or in a cache, learn about
+    /// cleaned up keys this way and can drop their rows instead of keeping them around forever.
+    ///
+    /// ```
+    /// # use async_subscription_map::{MapEvent, SubscriptionMap};
+    /// # use futures::StreamExt;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let mut sessions = map.lifecycle_events_under("sessions/");
+    ///
+    /// drop(map.get_or_insert("sessions/1", 0).await.unwrap());
+    /// let _config = map.get_or_insert("config", 0).await.unwrap();
+    /// let _session = map.get_or_insert("sessions/2", 0).await.unwrap();
+    ///
+    /// assert_eq!(
+    ///     sessions.next().await.unwrap(),
+    ///     vec![MapEvent::Inserted("sessions/2")]
+    /// );
+    /// # };
+    /// ```
+    pub fn lifecycle_events_under(&self, prefix: K) -> LifecycleEvents<K> {
+        self.subscribe_events(Some((prefix, |key, prefix| key.starts_with(prefix))), false)
+    }
+}
+
+/// A stream of batched [`MapEvent`]s, see [`SubscriptionMap::lifecycle_events`].
+#[must_use = "streams do nothing unless polled"]
+pub struct LifecycleEvents<K> {
+    queue: Arc<EventQueue<K>>,
+}
+
+impl<K> Stream for LifecycleEvents<K> {
+    type Item = Vec<MapEvent<K>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.queue.state();
+
+        if state.pending == 0 {
+            if state.closed {
+                return Poll::Ready(None);
+            }
+
+            state.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        Poll::Ready(Some(state.take()))
+    }
+}
+
+impl<K> Debug for LifecycleEvents<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LifecycleEvents")
+            .field("pending", &self.queue.state().pending)
+            .finish()
+    }
+}
+
+/// A stream of single [`MapEvent`]s, see [`SubscriptionMap::events`].
+#[must_use = "streams do nothing unless polled"]
+pub struct MapEvents<K> {
+    batches: LifecycleEvents<K>,
+    /// The rest of the last received batch
+    pending: VecDeque<MapEvent<K>>,
+}
+
+impl<K> MapEvents<K> {
+    pub(crate) fn new(batches: LifecycleEvents<K>) -> Self {
+        Self {
+            batches,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+// the events are never pinned, they are moved out of the queue
+impl<K> Unpin for MapEvents<K> {}
+
+impl<K> Stream for MapEvents<K> {
+    type Item = MapEvent<K>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.pending.is_empty() {
+            match this.batches.poll_next_unpin(cx) {
+                Poll::Ready(Some(batch)) => this.pending.extend(batch),
+                other => return other.map(|_| None),
+            }
+        }
+
+        Poll::Ready(this.pending.pop_front())
+    }
+}
+
+impl<K> Debug for MapEvents<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapEvents")
+            .field(
+                "pending",
+                &(self.pending.len() + self.batches.queue.state().pending),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MapEvent;
+    use crate::SubscriptionMap;
+    use futures::StreamExt;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_batch_events_since_last_poll() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut events = map.lifecycle_events();
+
+        let one = map.get_or_insert(1, 0).await.unwrap();
+        let _two = map.get_or_insert(2, 0).await.unwrap();
+        assert_eq!(
+            events.next().await.unwrap(),
+            vec![MapEvent::Inserted(1), MapEvent::Inserted(2)]
+        );
+
+        drop(one);
+        map.pin(3, 0).await.unwrap();
+        assert_eq!(
+            events.next().await.unwrap(),
+            vec![MapEvent::Removed(1), MapEvent::Inserted(3)]
+        );
+    }
+
+    #[async_std::test]
+    async fn should_coalesce_transient_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut events = map.lifecycle_events();
+
+        drop(map.get_or_insert(1, 0).await.unwrap());
+        let _two = map.get_or_insert(2, 0).await.unwrap();
+        assert_eq!(events.next().await.unwrap(), vec![MapEvent::Inserted(2)]);
+
+        drop(events);
+        drop(map.get_or_insert(3, 0).await.unwrap());
+        assert_eq!(
+            map.event_queues()
+                .iter()
+                .filter(|q| q.strong_count() > 0)
+                .count(),
+            0
+        );
+    }
+
+    #[async_std::test]
+    async fn should_cancel_insertions_of_cleared_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut events = map.lifecycle_events();
+
+        map.pin(0, 0).await.unwrap();
+        assert_eq!(events.next().await.unwrap(), vec![MapEvent::Inserted(0)]);
+
+        for key in 1..=10_000 {
+            map.pin(key, 0).await.unwrap();
+        }
+
+        assert_eq!(map.event_backlog(), 10_000);
+        assert_eq!(map.clear().await, 10_001);
+        assert_eq!(map.event_backlog(), 1);
+        assert_eq!(events.next().await.unwrap(), vec![MapEvent::Removed(0)]);
+    }
+
+    #[async_std::test]
+    async fn should_only_queue_events_under_prefix() {
+        let map: SubscriptionMap<&'static str, usize> = SubscriptionMap::new();
+        let mut events = map.lifecycle_events_under("a/");
+
+        let a = map.get_or_insert("a/1", 0).await.unwrap();
+        let _b = map.get_or_insert("b/1", 0).await.unwrap();
+        assert_eq!(
+            events.next().await.unwrap(),
+            vec![MapEvent::Inserted("a/1")]
+        );
+
+        drop(a);
+        assert_eq!(events.next().await.unwrap(), vec![MapEvent::Removed("a/1")]);
+    }
+
+    #[async_std::test]
+    async fn should_yield_events_one_at_a_time() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut events = map.events();
+
+        let one = map.get_or_insert(1, 0).await.unwrap();
+        let _two = map.get_or_insert(2, 0).await.unwrap();
+        drop(one);
+        assert_eq!(events.next().await, Some(MapEvent::Inserted(2)));
+
+        map.pin(3, 0).await.unwrap();
+        assert_eq!(events.next().await, Some(MapEvent::Inserted(3)));
+        assert_eq!(format!("{:?}", events), "MapEvents { pending: 0 }");
+    }
+
+    #[async_std::test]
+    async fn should_wait_until_entry_is_created() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let reader = async_std::task::spawn({
+            let map = map.clone();
+            async move { map.subscribe_created(1).await.unwrap().latest() }
+        });
+
+        async_std::task::sleep(Duration::from_millis(10)).await;
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+
+        let _other = map.get_or_insert(2, 0).await.unwrap();
+        let _producer = map.get_or_insert(1, 5).await.unwrap();
+        assert_eq!(reader.await, 5);
+    }
+
+    #[async_std::test]
+    async fn should_subscribe_existing_entries_once_draining_ends() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let _producer = map.get_or_insert(1, 5).await.unwrap();
+        map.drain();
+
+        let reader = async_std::task::spawn({
+            let map = map.clone();
+            async move { map.subscribe_created(1).await.unwrap().latest() }
+        });
+
+        async_std::task::sleep(Duration::from_millis(10)).await;
+        map.undrain();
+
+        let latest = async_std::future::timeout(Duration::from_secs(1), reader)
+            .await
+            .expect("reader kept waiting after the drain ended");
+        assert_eq!(latest, 5);
+    }
+
+    #[async_std::test]
+    async fn should_end_streams_once_closed() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut batches = map.lifecycle_events();
+        let mut events = map.events();
+        let internal = map.internal();
+
+        drop(map);
+        assert_eq!(batches.next().await, Some(vec![MapEvent::Closed]));
+        assert_eq!(batches.next().await, None);
+        assert_eq!(events.next().await, Some(MapEvent::Closed));
+        assert_eq!(events.next().await, None);
+
+        assert_eq!(internal.lifecycle_events().next().await, None);
+        assert!(internal.subscribe_created(1).await.is_none());
+    }
+}
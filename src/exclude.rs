@@ -0,0 +1,169 @@
+use crate::{KeyPrefix, SubscriptionMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// The key prefixes which are protected from cleanup at runtime
+pub(crate) struct Exclusions<K> {
+    prefixes: Vec<K>,
+    matcher: Option<fn(&K, &K) -> bool>,
+}
+
+impl<K> Default for Exclusions<K> {
+    fn default() -> Self {
+        Self {
+            prefixes: Vec::new(),
+            matcher: None,
+        }
+    }
+}
+
+impl<K> Exclusions<K> {
+    /// The protected prefixes in the order they were excluded
+    pub fn prefixes(&self) -> &[K] {
+        &self.prefixes
+    }
+
+    fn matches(&self, key: &K) -> bool {
+        match self.matcher {
+            Some(matches) => self.prefixes.iter().any(|prefix| matches(key, prefix)),
+            None => false,
+        }
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Check if the key is currently protected from being cleaned up
+    pub(crate) fn is_excluded_from_cleanup(&self, key: &K) -> bool {
+        self.0
+            .exclusions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .matches(key)
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord + KeyPrefix,
+    V: Clone + Debug,
+{
+    /// Keep unreferenced entries with the prefix until it is [included](Self::include) again.
+    ///
+    /// Protected entries survive the drop of their last ref, expired retention and capacity
+    /// eviction, as if they were pinned. This is meant to hold on to key ranges temporarily, e.g.
+    /// while debugging or during a migration, without redeploying with pinned entries.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// map.exclude_from_cleanup("jobs/");
+    ///
+    /// drop(map.get_or_insert("jobs/1", 0).await.unwrap());
+    /// assert!(map.contains_key(&"jobs/1").await);
+    ///
+    /// assert_eq!(map.include(&"jobs/").await, 1);
+    /// assert!(!map.contains_key(&"jobs/1").await);
+    /// # };
+    /// ```
+    pub fn exclude_from_cleanup(&self, prefix: K) {
+        let mut exclusions = self.0.exclusions.write().unwrap_or_else(|e| e.into_inner());
+        exclusions.matcher = Some(<K as KeyPrefix>::starts_with);
+
+        if !exclusions.prefixes.contains(&prefix) {
+            exclusions.prefixes.push(prefix);
+        }
+    }
+
+    /// Stop protecting keys with the prefix, returns the amount of entries which were only kept
+    /// because of it and are removed now.
+    pub async fn include(&self, prefix: &K) -> usize {
+        let mut map = self.lock_entries(&self.0.entries()).await;
+
+        {
+            let mut exclusions = self.0.exclusions.write().unwrap_or_else(|e| e.into_inner());
+            let len = exclusions.prefixes.len();
+            exclusions.prefixes.retain(|p| p != prefix);
+
+            if exclusions.prefixes.len() == len {
+                return 0;
+            }
+        }
+
+        let now = self.0.config.clock.now();
+        let released: Vec<K> = map
+            .iter()
+            .filter(|(key, entry)| key.starts_with(prefix) && entry.removable(now))
+            .map(|(key, _)| key.clone())
+            .filter(|key| !self.is_excluded_from_cleanup(key))
+            .collect();
+
+        for key in released.iter() {
+            if let Some(entry) = map.remove(key) {
+                self.record_removal(&mut map, key, &entry);
+            }
+        }
+
+        released.len()
+    }
+
+    /// The prefixes which are currently protected from cleanup
+    pub fn excluded_from_cleanup(&self) -> Vec<K> {
+        self.0
+            .exclusions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .prefixes()
+            .to_vec()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_protect_excluded_prefixes_from_cleanup() {
+        let clock = ManualClock::new();
+        let map: SubscriptionMap<&'static str, usize> =
+            SubscriptionMap::builder().clock(clock.clone()).build();
+        map.exclude_from_cleanup("a/");
+        map.exclude_from_cleanup("a/b/");
+        map.exclude_from_cleanup("a/");
+        assert_eq!(map.excluded_from_cleanup(), vec!["a/", "a/b/"]);
+
+        drop(map.get_or_insert("a/1", 0).await.unwrap());
+        drop(map.get_or_insert("a/b/1", 0).await.unwrap());
+        drop(map.get_or_insert("b/1", 0).await.unwrap());
+        map.warm([("a/2", 0)], Duration::from_secs(1))
+            .await
+            .unwrap();
+        let _referenced = map.get_or_insert("a/3", 0).await.unwrap();
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(map.sweep().await, 0);
+        assert_eq!(map.keys().await, vec!["a/1", "a/2", "a/3", "a/b/1"]);
+
+        // keys under a prefix which is still excluded stay protected
+        assert_eq!(map.include(&"a/").await, 2);
+        assert_eq!(map.include(&"a/").await, 0);
+        assert_eq!(map.keys().await, vec!["a/3", "a/b/1"]);
+    }
+
+    #[async_std::test]
+    async fn should_not_evict_excluded_entries() {
+        let map: SubscriptionMap<&'static str, usize> =
+            SubscriptionMap::builder().capacity(1).build();
+        map.exclude_from_cleanup("a/");
+        drop(map.get_or_insert("a/1", 0).await.unwrap());
+
+        assert!(map.get_or_insert("b/1", 0).await.is_err());
+        map.include(&"a/").await;
+        assert!(map.get_or_insert("b/1", 0).await.is_ok());
+    }
+}
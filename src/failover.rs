@@ -0,0 +1,275 @@
+use crate::{diag, Error, SubscriptionMap, SubscriptionRef};
+use async_observable::Observable;
+use futures::future::{self, Either};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+/// A hot/standby pair of maps which moves subscriptions to the standby once the primary fails.
+///
+/// New subscriptions go to the active map. Switching happens explicitly through
+/// [`fail_over`](Self::fail_over) and [`fail_back`](Self::fail_back), or automatically once the
+/// primary rejects new subscriptions because it is [draining](SubscriptionMap::drain). Live
+/// [`FailoverRef`]s re-subscribe to the newly active map on their next wait, an entry missing
+/// there is initialized with the latest value seen on the previous map.
+///
+/// ```
+/// # use async_subscription_map::{Failover, SubscriptionMap};
+/// # async {
+/// let primary = SubscriptionMap::<usize, usize>::default();
+/// let standby = SubscriptionMap::<usize, usize>::default();
+/// let failover = Failover::new(primary.clone(), standby.clone());
+///
+/// let mut subscription = failover.get_or_insert(1, 0).await.unwrap();
+/// primary.publish_if_changed(&1, 1).await.unwrap();
+/// assert_eq!(subscription.next().await, 1);
+///
+/// failover.fail_over();
+/// assert_eq!(subscription.next().await, 1);
+/// standby.publish_if_changed(&1, 2).await.unwrap();
+/// assert_eq!(subscription.next().await, 2);
+/// # };
+/// ```
+#[derive(Clone)]
+pub struct Failover<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    primary: SubscriptionMap<K, V>,
+    standby: SubscriptionMap<K, V>,
+    /// If the standby is the active map
+    failed_over: Observable<bool>,
+}
+
+impl<K, V> Failover<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Pair the maps, the primary starts out active
+    pub fn new(primary: SubscriptionMap<K, V>, standby: SubscriptionMap<K, V>) -> Self {
+        Self {
+            primary,
+            standby,
+            failed_over: Observable::new(false),
+        }
+    }
+
+    /// The primary map
+    pub fn primary(&self) -> &SubscriptionMap<K, V> {
+        &self.primary
+    }
+
+    /// The standby map
+    pub fn standby(&self) -> &SubscriptionMap<K, V> {
+        &self.standby
+    }
+
+    /// The map new subscriptions go to
+    pub fn active(&self) -> &SubscriptionMap<K, V> {
+        if self.is_failed_over() {
+            &self.standby
+        } else {
+            &self.primary
+        }
+    }
+
+    /// Check if the standby is the active map
+    pub fn is_failed_over(&self) -> bool {
+        self.failed_over.latest()
+    }
+
+    /// Make the standby the active map and move all subscriptions to it
+    pub fn fail_over(&self) {
+        self.switch(true);
+    }
+
+    /// Make the primary the active map again and move all subscriptions back to it
+    pub fn fail_back(&self) {
+        self.switch(false);
+    }
+
+    fn switch(&self, failed_over: bool) {
+        if self.failed_over.clone().publish_if_changed(failed_over) {
+            diag::info!(
+                "switched subscriptions to {}",
+                if failed_over { "standby" } else { "primary" }
+            );
+        }
+    }
+
+    /// A clone which doesn't keep the maps open
+    fn internal(&self) -> Self {
+        Self {
+            primary: self.primary.internal(),
+            standby: self.standby.internal(),
+            failed_over: self.failed_over.clone(),
+        }
+    }
+
+    /// Subscribe to the key on the active map, fails over if the primary is draining.
+    pub async fn get_or_insert(&self, key: K, value: V) -> anyhow::Result<FailoverRef<K, V>> {
+        let failed_over = self.failed_over.clone();
+
+        let subscription = match self
+            .active()
+            .get_or_insert(key.clone(), value.clone())
+            .await
+        {
+            Err(e)
+                if !self.is_failed_over()
+                    && e.downcast_ref::<Error>() == Some(&Error::Draining) =>
+            {
+                self.fail_over();
+                self.standby.get_or_insert(key, value).await?
+            }
+            result => result?,
+        };
+
+        Ok(FailoverRef {
+            failover: self.internal(),
+            subscription,
+            failed_over,
+        })
+    }
+}
+
+impl<K, V> Debug for Failover<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Failover")
+            .field("failed_over", &self.is_failed_over())
+            .finish()
+    }
+}
+
+/// A subscription which follows the active map of a [`Failover`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct FailoverRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    failover: Failover<K, V>,
+    subscription: SubscriptionRef<K, V>,
+    failed_over: Observable<bool>,
+}
+
+impl<K, V> FailoverRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// A clone of the latest value of the currently subscribed map.
+    pub fn latest(&self) -> V {
+        self.subscription.latest()
+    }
+
+    /// Wait until a new version is published or the active map changed and return a clone of
+    /// the latest value.
+    pub async fn next(&mut self) -> V {
+        let switched = {
+            let next = self.subscription.next();
+            let switched = self.failed_over.next();
+            futures::pin_mut!(next, switched);
+
+            match future::select(next, switched).await {
+                Either::Left((value, _)) => return value,
+                Either::Right((failed_over, _)) => failed_over,
+            }
+        };
+
+        self.resubscribe(switched).await;
+        self.subscription.synchronize()
+    }
+
+    /// Store the provided value on the currently subscribed map and notify all subscribers.
+    pub fn publish(&mut self, value: V) -> anyhow::Result<()> {
+        self.subscription.publish(value)
+    }
+
+    /// Move the subscription to the map which is active now
+    async fn resubscribe(&mut self, failed_over: bool) {
+        let map = if failed_over {
+            &self.failover.standby
+        } else {
+            &self.failover.primary
+        };
+
+        let key = self.subscription.key().clone();
+        let latest = self.subscription.latest();
+
+        match map.get_or_insert(key, latest).await {
+            Ok(subscription) => self.subscription = subscription,
+            Err(e) => diag::warning!("unable to move subscription after failover: {:#}", e),
+        }
+    }
+}
+
+impl<K, V> Debug for FailoverRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FailoverRef")
+            .field("subscription", &self.subscription)
+            .field("failed_over", &self.failed_over.latest())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Failover;
+    use crate::SubscriptionMap;
+
+    fn pair() -> Failover<usize, usize> {
+        Failover::new(SubscriptionMap::new(), SubscriptionMap::new())
+    }
+
+    #[async_std::test]
+    async fn should_move_subscriptions_between_maps() {
+        let failover = pair();
+        let mut subscription = failover.get_or_insert(1, 0).await.unwrap();
+        failover.primary().publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(subscription.next().await, 1);
+
+        failover.standby().pin(1, 5).await.unwrap();
+        failover.fail_over();
+        assert_eq!(subscription.next().await, 5);
+        assert_eq!(failover.primary().entries_snapshot().await.len(), 0);
+
+        failover.fail_back();
+        assert_eq!(subscription.next().await, 5);
+        assert_eq!(failover.primary().entries_snapshot().await[&1].rc(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_not_keep_maps_open() {
+        let failover = pair();
+        let subscription = failover.get_or_insert(1, 0).await.unwrap();
+
+        drop(failover);
+        assert!(subscription.failover.primary.is_closed());
+        assert!(subscription.failover.standby.is_closed());
+    }
+
+    #[async_std::test]
+    async fn should_fail_over_once_primary_drains() {
+        let failover = pair();
+        failover.primary().drain();
+
+        let _subscription = failover.get_or_insert(1, 0).await.unwrap();
+        assert!(failover.is_failed_over());
+        assert_eq!(failover.standby().entries_snapshot().await.len(), 1);
+    }
+}
@@ -0,0 +1,200 @@
+#[cfg(feature = "fault-injection")]
+use crate::fault;
+use crate::gate::PublishTarget;
+use crate::{SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use std::cell::Cell;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Same as [`modify_and_publish`](Self::modify_and_publish), but the closure may fail, which
+    /// aborts the publish and leaves the previous value untouched.
+    ///
+    /// The closure modifies a clone of the latest value, which replaces it only once the closure
+    /// succeeded. Subscribers never observe a value which failed validation halfway through.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, Vec<u32>>::default();
+    /// let subscription = map.get_or_insert("ports", vec![80]).await.unwrap();
+    ///
+    /// let result = map
+    ///     .try_modify_and_publish(&"ports", |ports| {
+    ///         ports.push(0);
+    ///         match ports.contains(&0) {
+    ///             true => Err(anyhow::anyhow!("port 0 is reserved")),
+    ///             false => Ok(()),
+    ///         }
+    ///     })
+    ///     .await;
+    ///
+    /// assert!(result.is_err());
+    /// assert_eq!(subscription.latest(), vec![80]);
+    /// # };
+    /// ```
+    pub async fn try_modify_and_publish<F, R, E>(&self, key: &K, modify: F) -> anyhow::Result<R>
+    where
+        F: FnOnce(&mut V) -> Result<R, E>,
+        E: Into<anyhow::Error>,
+    {
+        #[cfg(feature = "fault-injection")]
+        self.inject_fault(fault::FaultPoint::BeforePublish, key)
+            .await?;
+
+        let map = self.read_entries().await;
+        let entry = map.get(key).with_context(|| {
+            format!("unable modify not present key {:?}", self.redacted_key(key))
+        })?;
+
+        self.try_publish_gate(key, None, entry.target(), modify)
+    }
+
+    /// Run the fallible modification on a clone of the value under the lock of the observable
+    /// and publish the clone only if it succeeded, failures count towards the circuit breaker
+    fn try_publish_gate<F, R, E>(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        mut target: PublishTarget<'_, V>,
+        modify: F,
+    ) -> anyhow::Result<R>
+    where
+        F: FnOnce(&mut V) -> Result<R, E>,
+        E: Into<anyhow::Error>,
+    {
+        self.check_breaker(key, target.meta)?;
+
+        let outcome = Cell::new(None);
+        let candidate = Cell::new(None);
+
+        let published = self.apply_gate(
+            key,
+            producer,
+            &mut target,
+            |current| {
+                let mut new = current.clone();
+                let result = modify(&mut new);
+                let succeeded = result.is_ok();
+
+                outcome.set(Some(result));
+                if succeeded {
+                    candidate.set(Some(new));
+                }
+
+                succeeded
+            },
+            |current| {
+                if let Some(new) = candidate.take() {
+                    *current = new;
+                }
+            },
+        );
+
+        let outcome = outcome.into_inner();
+
+        // values which fail validation count as failures of the producer, just like values
+        // which are rejected by the map
+        match outcome {
+            Some(Err(_)) => {
+                self.record_failure(key, target.meta);
+            }
+            _ => self.record_outcome(key, target.meta, &published),
+        }
+
+        self.account_publish(key, &target, published?);
+
+        outcome
+            .expect("condition is evaluated unless the gate fails")
+            .map_err(Into::into)
+    }
+}
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Same as [`SubscriptionMap::try_modify_and_publish`] through the ref.
+    pub fn try_modify<F, R, E>(&mut self, modify: F) -> anyhow::Result<R>
+    where
+        F: FnOnce(&mut V) -> Result<R, E>,
+        E: Into<anyhow::Error>,
+    {
+        self.owner
+            .try_publish_gate(&self.key, self.tag, self.target(), modify)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{FeedStatus, SubscriptionMap};
+    use futures::FutureExt;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_abort_publish_on_failure() {
+        let map: SubscriptionMap<usize, Vec<usize>> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, vec![]).await.unwrap();
+
+        let err = subscription
+            .try_modify(|v| {
+                v.push(1);
+                Err::<(), _>(anyhow::anyhow!("invalid"))
+            })
+            .unwrap_err();
+        assert_eq!(err.to_string(), "invalid");
+        assert!(subscription.latest().is_empty());
+        assert!(subscription.next().now_or_never().is_none());
+
+        let len = map
+            .try_modify_and_publish(&1, |v| {
+                v.push(2);
+                Ok::<_, std::fmt::Error>(v.len())
+            })
+            .await
+            .unwrap();
+        assert_eq!(len, 1);
+        assert_eq!(subscription.next().await, vec![2]);
+    }
+
+    #[async_std::test]
+    async fn should_keep_buffered_value_on_failure_while_paused() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.pause();
+        subscription.publish(1).unwrap();
+        assert!(subscription
+            .try_modify(|v| {
+                *v = 5;
+                Err::<(), _>(std::fmt::Error)
+            })
+            .is_err());
+        map.resume().await;
+        assert_eq!(subscription.next().await, 1);
+    }
+
+    #[async_std::test]
+    async fn should_count_failed_validations_towards_breaker() {
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .circuit_breaker(3, Duration::from_secs(10))
+            .build();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+
+        producer.publish_err("timeout");
+        producer.publish_err("timeout");
+        assert!(map
+            .try_modify_and_publish(&1, |_| Err::<(), _>(std::fmt::Error))
+            .await
+            .is_err());
+
+        assert_eq!(producer.status(), FeedStatus::Degraded);
+        assert_eq!(producer.latest(), 0);
+    }
+}
@@ -0,0 +1,200 @@
+//! Hooks to inject delays and failures into the critical sections of the map.
+//!
+//! These allow downstream crates to verify that their code tolerates the edge case timings of the
+//! map, for example a ref whose cleanup is delayed while another task subscribes to the same key.
+use crate::{diag, Error, SubscriptionMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Duration;
+
+/// A critical section of the map at which faults can be injected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FaultPoint {
+    /// Before a map level publish is applied, failing makes the publish return an error
+    BeforePublish,
+    /// Before a dropped ref decrements the subscription count, failing leaks the count
+    BeforeRcDecrement,
+    /// Before the entry of the last dropped ref is removed, failing keeps the unreferenced entry
+    /// in the map
+    BeforeRemoval,
+}
+
+/// What should happen at a fault point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// Proceed normally
+    None,
+    /// Wait on the clock of the map before proceeding, points inside of destructors can't wait
+    /// and defer their work to the first [`sweep`](crate::SubscriptionMap::sweep) after the delay
+    Delay(Duration),
+    /// Take the failure path
+    Fail,
+}
+
+/// Decides which fault to inject at a fault point for a key.
+///
+/// Any closure with a matching signature is an injector.
+///
+/// ```
+/// # use async_subscription_map::{fault::{Fault, FaultPoint}, SubscriptionMap};
+/// # use std::time::Duration;
+/// let map = SubscriptionMap::<usize, usize>::builder()
+///     .fault_injector(|point: FaultPoint, _: &usize| match point {
+///         FaultPoint::BeforeRemoval => Fault::Delay(Duration::from_millis(10)),
+///         _ => Fault::None,
+///     })
+///     .build();
+/// ```
+pub trait FaultInjector<K>: Send + Sync {
+    /// The fault to inject at the point for this key
+    fn inject(&self, point: FaultPoint, key: &K) -> Fault;
+}
+
+impl<K, F> FaultInjector<K> for F
+where
+    F: Fn(FaultPoint, &K) -> Fault + Send + Sync,
+{
+    fn inject(&self, point: FaultPoint, key: &K) -> Fault {
+        self(point, key)
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fault(&self, point: FaultPoint, key: &K) -> Fault {
+        match self.0.config.fault_injector.as_ref() {
+            Some(injector) => injector.inject(point, key),
+            None => Fault::None,
+        }
+    }
+
+    pub(crate) async fn inject_fault(&self, point: FaultPoint, key: &K) -> anyhow::Result<()> {
+        match self.fault(point, key) {
+            Fault::None => Ok(()),
+            Fault::Delay(duration) => {
+                let clock = &self.0.config.clock;
+                clock.sleep_until(clock.now() + duration).await;
+                Ok(())
+            }
+            Fault::Fail => {
+                diag::warning!(
+                    "injecting fault at {:?} for key {:?}",
+                    point,
+                    self.redacted_key(key)
+                );
+                Err(Error::InjectedFault(point).into())
+            }
+        }
+    }
+
+    /// Same as [`inject_fault`](Self::inject_fault) for points which can't wait, such as
+    /// destructors. Returns the delay the work should be deferred by.
+    pub(crate) fn inject_fault_now(
+        &self,
+        point: FaultPoint,
+        key: &K,
+    ) -> anyhow::Result<Option<Duration>> {
+        match self.fault(point, key) {
+            Fault::None => Ok(None),
+            Fault::Delay(duration) => Ok(Some(duration)),
+            Fault::Fail => {
+                diag::warning!(
+                    "injecting fault at {:?} for key {:?}",
+                    point,
+                    self.redacted_key(key)
+                );
+                Err(Error::InjectedFault(point).into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Fault, FaultPoint};
+    use crate::{Error, ManualClock, SubscriptionMap};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_fail_map_publishes() {
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .fault_injector(|point, key: &usize| match (point, key) {
+                (FaultPoint::BeforePublish, 1) => Fault::Fail,
+                _ => Fault::None,
+            })
+            .build();
+
+        let _one = map.get_or_insert(1, 0).await.unwrap();
+        let _two = map.get_or_insert(2, 0).await.unwrap();
+
+        let err = map.publish_if_changed(&1, 1).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::InjectedFault(FaultPoint::BeforePublish))
+        );
+        assert!(map.publish_if_changed(&2, 1).await.unwrap());
+    }
+
+    #[async_std::test]
+    async fn should_keep_entry_on_failed_removal() {
+        let fail = Arc::new(AtomicBool::new(true));
+        let injector = fail.clone();
+
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .fault_injector(move |point, _: &usize| {
+                if point == FaultPoint::BeforeRemoval && injector.load(Ordering::SeqCst) {
+                    Fault::Fail
+                } else {
+                    Fault::None
+                }
+            })
+            .build();
+
+        drop(map.get_or_insert(1, 0).await.unwrap());
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 0);
+
+        // the stale entry is reused and cleaned up once removal succeeds again
+        fail.store(false, Ordering::SeqCst);
+        drop(map.get_or_insert(1, 0).await.unwrap());
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_leak_rc_on_failed_decrement() {
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .fault_injector(|point, _: &usize| match point {
+                FaultPoint::BeforeRcDecrement => Fault::Fail,
+                _ => Fault::None,
+            })
+            .build();
+
+        drop(map.get_or_insert(1, 0).await.unwrap());
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_defer_delayed_removal_to_sweep() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .fault_injector(|point, _: &usize| match point {
+                FaultPoint::BeforeRemoval => Fault::Delay(Duration::from_secs(1)),
+                _ => Fault::None,
+            })
+            .build();
+
+        drop(map.get_or_insert(1, 0).await.unwrap());
+        assert_eq!(map.sweep().await, 0);
+        assert_eq!(map.entries_snapshot().await.len(), 1);
+
+        clock.advance(Duration::from_secs(1));
+        map.sweep().await;
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+}
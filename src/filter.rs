@@ -0,0 +1,125 @@
+use crate::SubscriptionRef;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+type Predicate<V> = Box<dyn Fn(&V) -> bool + Send + Sync>;
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Derive a subscription whose [`next`](FilteredRef::next) only resolves with values which
+    /// pass the predicate.
+    ///
+    /// Values which don't pass are skipped while waiting, the caller isn't bothered with them.
+    /// The task is still polled once per publish to evaluate the predicate.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, u32>::default();
+    /// let mut producer = map.get_or_insert("temperature", 20).await.unwrap();
+    ///
+    /// let subscription = map.get_or_insert("temperature", 0).await.unwrap();
+    /// let mut alarms = subscription.filter(|celsius| *celsius > 30);
+    ///
+    /// producer.publish(25).unwrap();
+    /// producer.publish(35).unwrap();
+    /// assert_eq!(alarms.next().await, 35);
+    /// # };
+    /// ```
+    pub fn filter<P>(self, predicate: P) -> FilteredRef<K, V>
+    where
+        P: Fn(&V) -> bool + Send + Sync + 'static,
+    {
+        FilteredRef {
+            subscription: self,
+            predicate: Box::new(predicate),
+        }
+    }
+}
+
+/// A subscription which skips values that don't pass a predicate, see
+/// [`SubscriptionRef::filter`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct FilteredRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscription: SubscriptionRef<K, V>,
+    predicate: Predicate<V>,
+}
+
+impl<K, V> FilteredRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// A clone of the latest value, whether it passes the predicate or not.
+    pub fn latest(&self) -> V {
+        self.subscription.latest()
+    }
+
+    /// Wait until a value is published which passes the predicate and return a clone of it.
+    pub async fn next(&mut self) -> V {
+        loop {
+            let value = self.subscription.next().await;
+
+            if (self.predicate)(&value) {
+                return value;
+            }
+        }
+    }
+
+    /// The wrapped subscription, which yields all values again
+    pub fn into_inner(self) -> SubscriptionRef<K, V> {
+        self.subscription
+    }
+}
+
+impl<K, V> Debug for FilteredRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilteredRef")
+            .field("subscription", &self.subscription)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+    use futures::FutureExt;
+
+    #[async_std::test]
+    async fn should_only_resolve_with_passing_values() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        let mut even = map
+            .get_or_insert(1, 0)
+            .await
+            .unwrap()
+            .filter(|value| value % 2 == 0);
+
+        producer.publish(1).unwrap();
+        assert!(even.next().now_or_never().is_none());
+
+        producer.publish(3).unwrap();
+        producer.publish(4).unwrap();
+        assert_eq!(even.next().await, 4);
+        assert_eq!(even.latest(), 4);
+
+        producer.publish(5).unwrap();
+        assert_eq!(even.into_inner().next().await, 5);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::{KeyMatcher, LifecycleEvents, MapEvent, SubscriptionMap};
+use async_observable::Observable;
+use futures::future::{self, Either};
+use futures::StreamExt;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::ops::{Bound, RangeBounds};
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Observe the publishes of all keys, including keys which are created later on.
+    ///
+    /// The firehose yields the current values of all entries first, then the initial value of
+    /// every newly created entry and the new value of every publish. It doesn't keep entries
+    /// alive. Like a ref it only yields the latest value of a key if it is published faster than
+    /// the firehose is polled. This allows to mirror the whole map, e.g. to a websocket gateway,
+    /// without tracking each key individually.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let _temperature = map.get_or_insert("temperature", 20).await.unwrap();
+    ///
+    /// let mut firehose = map.subscribe_all().await;
+    /// assert_eq!(firehose.next().await, Some(("temperature", 20)));
+    ///
+    /// let _humidity = map.get_or_insert("humidity", 50).await.unwrap();
+    /// assert_eq!(firehose.next().await, Some(("humidity", 50)));
+    ///
+    /// map.publish_if_changed(&"temperature", 21).await.unwrap();
+    /// assert_eq!(firehose.next().await, Some(("temperature", 21)));
+    /// # };
+    /// ```
+    pub async fn subscribe_all(&self) -> Firehose<K, V> {
+        self.subscribe_range(..).await
+    }
+
+    /// Same as [`subscribe_all`](Self::subscribe_all) but only for keys within the range.
+    ///
+    /// Keys which are created later on are observed as soon as they fall within the range, e.g.
+    /// all sensors of a numbered bank.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, f32>::default();
+    /// let mut bank = map.subscribe_range(100..200).await;
+    ///
+    /// let _outside = map.get_or_insert(200, 0.0).await.unwrap();
+    /// let _sensor = map.get_or_insert(142, 21.5).await.unwrap();
+    /// assert_eq!(bank.next().await, Some((142, 21.5)));
+    /// # };
+    /// ```
+    pub async fn subscribe_range<R>(&self, range: R) -> Firehose<K, V>
+    where
+        R: RangeBounds<K>,
+    {
+        self.subscribe_filtered(range, None).await
+    }
+
+    /// Same as [`subscribe_all`](Self::subscribe_all) but only for keys selected by the matcher.
+    ///
+    /// ```
+    /// # use async_subscription_map::{Glob, SubscriptionMap};
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, &'static str>::default();
+    /// let mut status = map.subscribe_matching(Glob::new("orders/*/status")).await;
+    ///
+    /// let _items = map.get_or_insert("orders/1/items", "").await.unwrap();
+    /// let _status = map.get_or_insert("orders/1/status", "placed").await.unwrap();
+    /// assert_eq!(status.next().await, Some(("orders/1/status", "placed")));
+    /// # };
+    /// ```
+    pub async fn subscribe_matching<M>(&self, matcher: M) -> Firehose<K, V>
+    where
+        M: KeyMatcher<K> + Send + Sync + 'static,
+    {
+        self.subscribe_filtered(.., Some(Box::new(matcher))).await
+    }
+
+    async fn subscribe_filtered<R>(&self, range: R, matcher: Option<Matcher<K>>) -> Firehose<K, V>
+    where
+        R: RangeBounds<K>,
+    {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+
+        // subscribe before looking at the entries so an insertion in between isn't missed
+        let events = self.lifecycle_events();
+        let map = self.read_entries().await;
+
+        let observables: BTreeMap<K, Observable<V>> = map
+            .range(range.clone())
+            .filter(|(key, _)| matcher.as_ref().is_none_or(|m| m.matches(key)))
+            .map(|(key, entry)| (key.clone(), entry.observable.clone()))
+            .collect();
+
+        Firehose {
+            owner: self.internal(),
+            events,
+            pending: observables.keys().cloned().collect(),
+            observables,
+            range,
+            matcher,
+            closed: false,
+        }
+    }
+}
+
+type Matcher<K> = Box<dyn KeyMatcher<K> + Send + Sync>;
+
+/// Yields the publishes of all or a selection of keys of a map, see
+/// [`SubscriptionMap::subscribe_all`].
+#[must_use = "the firehose does nothing unless polled"]
+pub struct Firehose<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    owner: SubscriptionMap<K, V>,
+    events: LifecycleEvents<K>,
+    /// The observed entries, without subscribing to them
+    observables: BTreeMap<K, Observable<V>>,
+    /// The keys whose current value still has to be yielded
+    pending: BTreeSet<K>,
+    /// Only keys within the range are observed
+    range: (Bound<K>, Bound<K>),
+    matcher: Option<Matcher<K>>,
+    closed: bool,
+}
+
+impl<K, V> Firehose<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Wait until any key is published or created and return it together with its value.
+    ///
+    /// Returns `None` once the map is closed, see [`MapEvent::Closed`]. Every call waits on all
+    /// entries, so its cost grows with their amount.
+    pub async fn next(&mut self) -> Option<(K, V)> {
+        loop {
+            while let Some(key) = self.pending.pop_first() {
+                if let Some(observable) = self.observables.get_mut(&key) {
+                    return Some((key, observable.synchronize()));
+                }
+            }
+
+            if self.closed {
+                return None;
+            }
+
+            let changed = {
+                let batch = self.events.next();
+
+                if self.observables.is_empty() {
+                    Either::Left(batch.await)
+                } else {
+                    let published =
+                        future::select_all(self.observables.iter_mut().map(|(key, observable)| {
+                            Box::pin(async move { (key.clone(), observable.next().await) })
+                        }));
+
+                    match future::select(batch, published).await {
+                        Either::Left((batch, _)) => Either::Left(batch),
+                        Either::Right(((item, _, _), _)) => Either::Right(item),
+                    }
+                }
+            };
+
+            match changed {
+                Either::Left(batch) => self.apply(batch.unwrap_or_default()).await,
+                Either::Right(item) => return Some(item),
+            }
+        }
+    }
+
+    /// The amount of observed entries
+    pub fn len(&self) -> usize {
+        self.observables.len()
+    }
+
+    /// Check if no entries are observed
+    pub fn is_empty(&self) -> bool {
+        self.observables.is_empty()
+    }
+
+    fn selects(&self, key: &K) -> bool {
+        self.range.contains(key) && self.matcher.as_ref().is_none_or(|m| m.matches(key))
+    }
+
+    /// Follow the created and removed entries
+    async fn apply(&mut self, batch: Vec<MapEvent<K>>) {
+        let map = self.owner.read_entries().await;
+
+        for event in batch {
+            match event {
+                MapEvent::Inserted(key) | MapEvent::Removed(key) if !self.selects(&key) => {}
+                MapEvent::Inserted(key) => match map.get(&key) {
+                    Some(entry) => {
+                        self.observables
+                            .insert(key.clone(), entry.observable.clone());
+                        self.pending.insert(key);
+                    }
+                    // removed again, its removal is part of a later batch
+                    None => {
+                        self.observables.remove(&key);
+                    }
+                },
+                MapEvent::Removed(key) => {
+                    self.observables.remove(&key);
+                    self.pending.remove(&key);
+                }
+                MapEvent::Closed => self.closed = true,
+                MapEvent::StateChanged(..) => {}
+            }
+        }
+    }
+}
+
+impl<K, V> Debug for Firehose<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Firehose")
+            .field("entries", &self.observables.len())
+            .field("pending", &self.pending.len())
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Exact, Glob, SubscriptionMap};
+
+    #[async_std::test]
+    async fn should_yield_publishes_of_all_keys() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        map.pin(2, 20).await.unwrap();
+        map.pin(1, 10).await.unwrap();
+
+        let mut firehose = map.subscribe_all().await;
+        assert_eq!(firehose.next().await, Some((1, 10)));
+        assert_eq!(firehose.next().await, Some((2, 20)));
+
+        map.publish_if_changed(&2, 21).await.unwrap();
+        assert_eq!(firehose.next().await, Some((2, 21)));
+
+        let three = map.get_or_insert(3, 30).await.unwrap();
+        assert_eq!(firehose.next().await, Some((3, 30)));
+        assert_eq!(firehose.len(), 3);
+
+        drop(three);
+        map.unpin(&1).await;
+        map.publish_if_changed(&2, 22).await.unwrap();
+        assert_eq!(firehose.next().await, Some((2, 22)));
+        assert_eq!(firehose.len(), 1);
+        assert_eq!(map.entries_snapshot().await.len(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_only_observe_keys_within_range() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        map.pin(1, 0).await.unwrap();
+        map.pin(5, 0).await.unwrap();
+
+        let mut bank = map.subscribe_range(2..=5).await;
+        assert_eq!(bank.next().await, Some((5, 0)));
+
+        map.pin(6, 0).await.unwrap();
+        map.publish_if_changed(&1, 1).await.unwrap();
+        map.pin(2, 0).await.unwrap();
+        assert_eq!(bank.next().await, Some((2, 0)));
+
+        map.publish_if_changed(&6, 1).await.unwrap();
+        map.publish_if_changed(&5, 1).await.unwrap();
+        assert_eq!(bank.next().await, Some((5, 1)));
+        assert_eq!(bank.len(), 2);
+    }
+
+    #[async_std::test]
+    async fn should_observe_matching_keys() {
+        let map: SubscriptionMap<&'static str, usize> = SubscriptionMap::new();
+        map.pin("orders/1/status", 0).await.unwrap();
+        map.pin("orders/1/items", 0).await.unwrap();
+
+        let mut status = map.subscribe_matching(Glob::new("orders/*/status")).await;
+        assert_eq!(status.next().await, Some(("orders/1/status", 0)));
+
+        map.publish_if_changed(&"orders/1/items", 1).await.unwrap();
+        map.pin("orders/2/status", 0).await.unwrap();
+        assert_eq!(status.next().await, Some(("orders/2/status", 0)));
+
+        let mut exact = map.subscribe_matching(Exact("orders/1/items")).await;
+        assert_eq!(exact.next().await, Some(("orders/1/items", 1)));
+        assert_eq!(exact.len(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_end_once_map_is_closed() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut firehose = map.subscribe_all().await;
+        assert!(firehose.is_empty());
+
+        drop(map);
+        assert_eq!(firehose.next().await, None);
+    }
+}
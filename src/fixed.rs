@@ -0,0 +1,276 @@
+use crate::{diag, Error};
+use anyhow::Context;
+use async_lock::Mutex;
+use async_observable::Observable;
+use std::fmt::{self, Debug};
+use std::sync::Arc;
+
+/// A slot which either holds an entry or is free for reuse
+struct Slot<K, V>
+where
+    V: Clone + Debug,
+{
+    key: Option<K>,
+    observable: Observable<V>,
+    rc: usize,
+}
+
+/// A subscription map with a statically bounded amount of entries.
+///
+/// All slots and their observables are allocated when the map is created, slots of removed
+/// entries are reused by later insertions. Hence `get_or_insert` doesn't allocate on the heap
+/// as long as cloning keys doesn't, which makes this variant suitable for latency critical paths
+/// where the self cleaning [`SubscriptionMap`](crate::SubscriptionMap) is unacceptable.
+///
+/// Lookups scan all slots, so this is meant for small capacities.
+///
+/// ```
+/// # use async_subscription_map::{Error, FixedSubscriptionMap};
+/// # async {
+/// let map = FixedSubscriptionMap::<usize, usize, 1>::new(0);
+/// let subscription = map.get_or_insert(1, 0).await.unwrap();
+///
+/// let err = map.get_or_insert(2, 0).await.unwrap_err();
+/// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CapacityExceeded));
+///
+/// drop(subscription);
+/// assert!(map.get_or_insert(2, 0).await.is_ok());
+/// # };
+/// ```
+pub struct FixedSubscriptionMap<K, V, const N: usize>(Arc<Mutex<[Slot<K, V>; N]>>)
+where
+    K: Clone + Debug + Eq,
+    V: Clone + Debug;
+
+impl<K, V, const N: usize> FixedSubscriptionMap<K, V, N>
+where
+    K: Clone + Debug + Eq,
+    V: Clone + Debug,
+{
+    /// Allocate all slots up front, the placeholder value is never observed by subscribers
+    pub fn new(placeholder: V) -> Self {
+        let slots = std::array::from_fn(|_| Slot {
+            key: None,
+            observable: Observable::new(placeholder.clone()),
+            rc: 0,
+        });
+
+        Self(Arc::new(Mutex::new(slots)))
+    }
+
+    /// The maximum amount of entries
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Either creates a ref to a existing subscription or initializes a new one in a free slot.
+    ///
+    /// Fails with [`Error::CapacityExceeded`] if all slots are occupied.
+    pub async fn get_or_insert(
+        &self,
+        key: K,
+        value: V,
+    ) -> anyhow::Result<FixedSubscriptionRef<K, V, N>> {
+        let mut slots = self.0.lock().await;
+
+        let index = match slots
+            .iter()
+            .position(|slot| slot.key.as_ref() == Some(&key))
+        {
+            Some(index) => index,
+            None => {
+                let index = slots
+                    .iter()
+                    .position(|slot| slot.key.is_none())
+                    .ok_or(Error::CapacityExceeded)
+                    .with_context(|| format!("unable to insert {:?}", key))?;
+
+                let slot = &mut slots[index];
+                slot.key = Some(key.clone());
+                slot.observable.publish(value);
+                index
+            }
+        };
+
+        let slot = &mut slots[index];
+        slot.rc += 1;
+
+        Ok(FixedSubscriptionRef {
+            key,
+            index,
+            owner: self.clone(),
+            observable: slot.observable.clone(),
+        })
+    }
+
+    /// Amount of occupied slots
+    pub async fn len(&self) -> usize {
+        let slots = self.0.lock().await;
+        slots.iter().filter(|slot| slot.key.is_some()).count()
+    }
+
+    /// Check if all slots are free
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+}
+
+impl<K, V, const N: usize> Clone for FixedSubscriptionMap<K, V, N>
+where
+    K: Clone + Debug + Eq,
+    V: Clone + Debug,
+{
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<K, V, const N: usize> Debug for FixedSubscriptionMap<K, V, N>
+where
+    K: Clone + Debug + Eq,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("FixedSubscriptionMap");
+        debug.field("capacity", &N);
+
+        match self.0.try_lock() {
+            Some(slots) => debug.field(
+                "keys",
+                &slots
+                    .iter()
+                    .filter_map(|slot| slot.key.as_ref())
+                    .collect::<Vec<_>>(),
+            ),
+            None => debug.field("keys", &format_args!("<locked>")),
+        };
+
+        debug.finish()
+    }
+}
+
+/// A subscription to a slot of a [`FixedSubscriptionMap`] which frees the slot once no one
+/// subscribes to it anymore.
+#[must_use = "slots are freed as soon as no one subscribes to them"]
+pub struct FixedSubscriptionRef<K, V, const N: usize>
+where
+    K: Clone + Debug + Eq,
+    V: Clone + Debug,
+{
+    key: K,
+    index: usize,
+    owner: FixedSubscriptionMap<K, V, N>,
+    observable: Observable<V>,
+}
+
+impl<K, V, const N: usize> FixedSubscriptionRef<K, V, N>
+where
+    K: Clone + Debug + Eq,
+    V: Clone + Debug,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// A clone of the latest value, without consuming the change.
+    pub fn latest(&self) -> V {
+        self.observable.latest()
+    }
+
+    /// Wait until a new version is published and return a clone of it.
+    pub async fn next(&mut self) -> V {
+        self.observable.next().await
+    }
+
+    /// Skip any pending updates and return the latest value.
+    pub fn synchronize(&mut self) -> V {
+        self.observable.synchronize()
+    }
+
+    /// Store the provided value and notify all subscribers.
+    pub fn publish(&mut self, value: V) {
+        self.observable.publish(value);
+    }
+
+    /// Modify the value through a mutable reference and notify all subscribers.
+    pub fn modify<F>(&mut self, modify: F)
+    where
+        F: FnOnce(&mut V),
+    {
+        self.observable.modify(modify);
+    }
+}
+
+impl<K, V, const N: usize> Debug for FixedSubscriptionRef<K, V, N>
+where
+    K: Clone + Debug + Eq,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixedSubscriptionRef")
+            .field("key", &self.key)
+            .field("value", &self.observable.latest())
+            .finish()
+    }
+}
+
+impl<K, V, const N: usize> Drop for FixedSubscriptionRef<K, V, N>
+where
+    K: Clone + Debug + Eq,
+    V: Clone + Debug,
+{
+    fn drop(&mut self) {
+        diag::trace!("drop for fixed subscription ref for key {:?}", self.key);
+
+        // the slots are never locked across an await, so this only blocks for a moment
+        let mut slots = self.owner.0.lock_blocking();
+        let slot = &mut slots[self.index];
+
+        slot.rc -= 1;
+
+        if slot.rc == 0 {
+            slot.key = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FixedSubscriptionMap;
+    use crate::Error;
+
+    #[async_std::test]
+    async fn should_reuse_freed_slots() {
+        let map: FixedSubscriptionMap<usize, usize, 2> = FixedSubscriptionMap::new(0);
+        let one = map.get_or_insert(1, 1).await.unwrap();
+        let _two = map.get_or_insert(2, 2).await.unwrap();
+        let also_one = map.get_or_insert(1, 5).await.unwrap();
+        assert_eq!(map.len().await, 2);
+
+        let err = map.get_or_insert(3, 3).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CapacityExceeded));
+
+        drop(one);
+        assert_eq!(map.len().await, 2);
+        drop(also_one);
+        assert_eq!(map.len().await, 1);
+
+        let mut three = map.get_or_insert(3, 3).await.unwrap();
+        assert_eq!(three.latest(), 3);
+        assert_eq!(three.synchronize(), 3);
+    }
+
+    #[async_std::test]
+    async fn should_notify_subscribers_of_a_slot() {
+        let map: FixedSubscriptionMap<usize, usize, 1> = FixedSubscriptionMap::new(0);
+        let mut publisher = map.get_or_insert(1, 0).await.unwrap();
+        let mut subscriber = map.get_or_insert(1, 0).await.unwrap();
+
+        publisher.publish(1);
+        assert_eq!(subscriber.next().await, 1);
+
+        publisher.modify(|v| *v += 1);
+        assert_eq!(subscriber.next().await, 2);
+    }
+}
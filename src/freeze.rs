@@ -0,0 +1,82 @@
+use crate::SubscriptionMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Reject all publish operations with [`Error::Frozen`](crate::Error::Frozen) until
+    /// [`thaw`](Self::thaw) is called.
+    ///
+    /// Subscriptions keep working while the map is frozen. Publishes which are in flight while
+    /// freezing are completed before this returns, so the values of a frozen map are quiescent
+    /// which is useful for snapshot or persistence operations.
+    ///
+    /// ```
+    /// # use async_subscription_map::{Error, SubscriptionMap};
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    ///
+    /// map.freeze();
+    /// let err = subscription.publish(1).unwrap_err();
+    /// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Frozen));
+    ///
+    /// map.thaw();
+    /// assert!(subscription.publish(1).is_ok());
+    /// # };
+    /// ```
+    pub fn freeze(&self) {
+        self.publish_state_mut().frozen = true;
+    }
+
+    /// Accept publish operations again after [`freeze`](Self::freeze).
+    pub fn thaw(&self) {
+        self.publish_state_mut().frozen = false;
+    }
+
+    /// Check if the map is currently frozen
+    pub fn is_frozen(&self) -> bool {
+        self.publish_state().frozen
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+
+    #[async_std::test]
+    async fn should_reject_publishes_while_frozen() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.freeze();
+        assert!(map.is_frozen());
+
+        let err = map.publish_if_changed(&1, 1).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Frozen));
+        assert!(map.modify_and_publish(&1, |v| *v = 1).await.is_err());
+        assert!(subscription.publish(1).is_err());
+        assert!(subscription.modify(|v| *v = 1).is_err());
+        assert_eq!(subscription.latest(), 0);
+
+        map.thaw();
+        assert!(!map.is_frozen());
+        assert!(map.publish_if_changed(&1, 1).await.unwrap());
+        assert_eq!(subscription.next().await, 1);
+    }
+
+    #[async_std::test]
+    async fn should_keep_subscriptions_working_while_frozen() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        map.freeze();
+
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(subscription.latest(), 0);
+
+        drop(subscription);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+}
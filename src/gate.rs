@@ -0,0 +1,438 @@
+use crate::coalesce::Coalescing;
+use crate::{EntryMeta, Error, SubscriptionMap};
+use anyhow::Context;
+use async_observable::Observable;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{RwLockReadGuard, RwLockWriteGuard};
+
+/// The entry a publish goes to
+pub(crate) struct PublishTarget<'a, V>
+where
+    V: Clone,
+{
+    pub observable: Observable<V>,
+    pub meta: &'a EntryMeta<V>,
+    /// The entry is no longer part of the map, its publishes only reach the entry itself and
+    /// bypass the bookkeeping and buffering of its key, which may belong to another entry by now
+    pub detached: bool,
+}
+
+/// Decides whether publishes are applied, rejected or buffered
+pub(crate) struct PublishState<K, V> {
+    /// Reject all publishes
+    pub frozen: bool,
+    /// Buffer publishes per key instead of applying them
+    pub paused: Option<BTreeMap<K, V>>,
+    /// Buffer publishes which happen within the coalescing window of their key
+    pub coalescing: Coalescing<K, V>,
+}
+
+impl<K, V> Default for PublishState<K, V> {
+    fn default() -> Self {
+        Self {
+            frozen: false,
+            paused: None,
+            coalescing: Coalescing::default(),
+        }
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    pub(crate) fn publish_state(&self) -> RwLockReadGuard<'_, PublishState<K, V>> {
+        self.0
+            .publish_state
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn publish_state_mut(&self) -> RwLockWriteGuard<'_, PublishState<K, V>> {
+        self.0
+            .publish_state
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Modify the value of the entry if the condition is met, returns if a change was made.
+    ///
+    /// Every publish operation of the map and its refs goes through here, so this is the place
+    /// which enforces the publish state of the map.
+    ///
+    /// Values exceeding the [maximum value size](crate::SubscriptionMapBuilder::max_value_size)
+    /// are rejected before they reach the publish state.
+    ///
+    /// This must never lock the entries of the map: publishes through refs are the most frequent
+    /// operation and may only contend with changes of the publish state, not with insertions or
+    /// cleanup.
+    pub(crate) fn publish_gate<C, M>(
+        &self,
+        key: &K,
+        target: PublishTarget<'_, V>,
+        condition: C,
+        modify: M,
+    ) -> anyhow::Result<bool>
+    where
+        C: FnOnce(&V) -> bool,
+        M: FnOnce(&mut V),
+    {
+        self.publish_gate_as(key, None, target, condition, modify)
+    }
+
+    /// Same as [`publish_gate`](Self::publish_gate) for publishes of a tagged producer
+    pub(crate) fn publish_gate_as<C, M>(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: PublishTarget<'_, V>,
+        condition: C,
+        modify: M,
+    ) -> anyhow::Result<bool>
+    where
+        C: FnOnce(&V) -> bool,
+        M: FnOnce(&mut V),
+    {
+        self.account_gate(key, target, |target| {
+            self.apply_gate(key, producer, target, condition, modify)
+        })
+    }
+
+    /// Replace the value of the entry if the condition is met, returns if a change was made.
+    ///
+    /// The condition gets the current and the new value. Unlike with
+    /// [`publish_gate`](Self::publish_gate) the new value is known upfront, so its size is checked
+    /// without cloning the current value.
+    pub(crate) fn publish_value_gate<C>(
+        &self,
+        key: &K,
+        target: PublishTarget<'_, V>,
+        condition: C,
+        value: V,
+    ) -> anyhow::Result<bool>
+    where
+        C: FnOnce(&V, &V) -> bool,
+    {
+        self.publish_value_gate_as(key, None, target, condition, value)
+    }
+
+    /// Same as [`publish_value_gate`](Self::publish_value_gate) for publishes of a tagged producer
+    pub(crate) fn publish_value_gate_as<C>(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: PublishTarget<'_, V>,
+        condition: C,
+        value: V,
+    ) -> anyhow::Result<bool>
+    where
+        C: FnOnce(&V, &V) -> bool,
+    {
+        self.account_gate(key, target, |target| {
+            self.apply_value_publish(key, producer, target, condition, value)
+        })
+    }
+
+    /// Run the publish with the breaker and bookkeeping of its key
+    fn account_gate<P>(
+        &self,
+        key: &K,
+        mut target: PublishTarget<'_, V>,
+        apply: P,
+    ) -> anyhow::Result<bool>
+    where
+        P: FnOnce(&mut PublishTarget<'_, V>) -> anyhow::Result<bool>,
+    {
+        self.check_breaker(key, target.meta)?;
+
+        let published = apply(&mut target);
+        self.record_outcome(key, target.meta, &published);
+
+        let changed = published?;
+        self.account_publish(key, &target, changed);
+
+        Ok(changed)
+    }
+
+    /// Apply the publish without the bookkeeping of [`publish_gate_as`](Self::publish_gate_as),
+    /// callers have to account for its outcome
+    pub(crate) fn apply_gate<C, M>(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: &mut PublishTarget<'_, V>,
+        condition: C,
+        modify: M,
+    ) -> anyhow::Result<bool>
+    where
+        C: FnOnce(&V) -> bool,
+        M: FnOnce(&mut V),
+    {
+        match self.0.config.max_value_size {
+            Some(_) => self.apply_sized_publish(key, producer, target, condition, modify),
+            None => self.apply_publish(key, producer, target, condition, modify),
+        }
+    }
+
+    /// Same as [`apply_publish`](Self::apply_publish) for a replacement of the value, an oversized
+    /// value only fails the publish if the condition is met
+    fn apply_value_publish<C>(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: &mut PublishTarget<'_, V>,
+        condition: C,
+        value: V,
+    ) -> anyhow::Result<bool>
+    where
+        C: FnOnce(&V, &V) -> bool,
+    {
+        let oversized = Cell::new(self.check_value_size(key, &value).err());
+        let rejected = Cell::new(None);
+
+        // both closures need the value, the condition only borrows it while the modification
+        // moves it out afterwards
+        let value = Cell::new(Some(value));
+
+        let changed = self.apply_publish(
+            key,
+            producer,
+            target,
+            |current| {
+                let new = value.take();
+                let accepted = new.as_ref().is_some_and(|new| condition(current, new));
+                value.set(new);
+
+                if !accepted {
+                    return false;
+                }
+
+                match oversized.take() {
+                    Some(e) => {
+                        rejected.set(Some(e));
+                        false
+                    }
+                    None => true,
+                }
+            },
+            |current| {
+                if let Some(new) = value.take() {
+                    *current = new;
+                }
+            },
+        )?;
+
+        match rejected.into_inner() {
+            Some(e) => Err(e),
+            None => Ok(changed),
+        }
+    }
+
+    /// Account for a publish which passed the gate
+    pub(crate) fn account_publish(&self, key: &K, target: &PublishTarget<'_, V>, changed: bool) {
+        if changed && !target.detached {
+            self.count_publish(key);
+            #[cfg(feature = "latency")]
+            self.record_publish(key);
+        }
+    }
+
+    /// Same as [`apply_publish`](Self::apply_publish) but the modification is applied to a clone
+    /// first, so its size can be checked before anything is published or buffered.
+    ///
+    /// Replacements of the value go through
+    /// [`publish_value_gate`](Self::publish_value_gate), which doesn't need the clone.
+    fn apply_sized_publish<C, M>(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: &mut PublishTarget<'_, V>,
+        condition: C,
+        modify: M,
+    ) -> anyhow::Result<bool>
+    where
+        C: FnOnce(&V) -> bool,
+        M: FnOnce(&mut V),
+    {
+        let candidate = Cell::new(None);
+        let rejected = Cell::new(None);
+
+        let changed = self.apply_publish(
+            key,
+            producer,
+            target,
+            |current| {
+                if !condition(current) {
+                    return false;
+                }
+
+                let mut new = current.clone();
+                modify(&mut new);
+
+                match self.check_value_size(key, &new) {
+                    Ok(()) => {
+                        candidate.set(Some(new));
+                        true
+                    }
+                    Err(e) => {
+                        rejected.set(Some(e));
+                        false
+                    }
+                }
+            },
+            |current| {
+                if let Some(new) = candidate.take() {
+                    *current = new;
+                }
+            },
+        )?;
+
+        match rejected.into_inner() {
+            Some(e) => Err(e),
+            None => Ok(changed),
+        }
+    }
+
+    /// Account for a value which reaches the subscribers of the key, this runs under the lock of
+    /// its observable
+    pub(crate) fn record_published(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        meta: &EntryMeta<V>,
+        detached: bool,
+        value: &V,
+    ) {
+        // streams receive the changes in the order of their versions as long as their queues are
+        // locked while the version is assigned
+        let capture = self.capturing_changes().filter(|_| !detached);
+        let update = self.record_update(meta, producer);
+
+        if let Some(mut queues) = capture {
+            self.capture_change(&mut queues, key, update, value);
+        }
+
+        self.record_weight(key, meta, value);
+        self.record_history(meta, update.version, value);
+    }
+
+    /// Publish a value which was buffered while the map was paused or coalescing
+    pub(crate) fn publish_buffered(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: &mut PublishTarget<'_, V>,
+        value: V,
+    ) {
+        target.observable.modify(|current| {
+            *current = value;
+            self.record_published(key, producer, target.meta, target.detached, current);
+        });
+    }
+
+    /// Apply the publish right away
+    pub(crate) fn publish_now<C, M>(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: &mut PublishTarget<'_, V>,
+        condition: C,
+        modify: M,
+    ) -> bool
+    where
+        C: FnOnce(&V) -> bool,
+        M: FnOnce(&mut V),
+    {
+        target.observable.modify_conditional(condition, |value| {
+            modify(value);
+            self.record_published(key, producer, target.meta, target.detached, value);
+        })
+    }
+
+    fn apply_publish<C, M>(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: &mut PublishTarget<'_, V>,
+        condition: C,
+        modify: M,
+    ) -> anyhow::Result<bool>
+    where
+        C: FnOnce(&V) -> bool,
+        M: FnOnce(&mut V),
+    {
+        {
+            let state = self.publish_state();
+            let buffering = state.paused.is_some() || !state.coalescing.is_idle();
+
+            if !state.frozen && (!buffering || target.detached) {
+                return Ok(self.publish_now(key, producer, target, condition, modify));
+            }
+        }
+
+        let mut state = self.publish_state_mut();
+
+        if state.frozen {
+            return Err(Error::Frozen)
+                .with_context(|| format!("unable to publish to {:?}", self.redacted_key(key)));
+        }
+
+        // buffered values are published to whichever entry the key has later on
+        if target.detached {
+            return Ok(self.publish_now(key, producer, target, condition, modify));
+        }
+
+        let state = &mut *state;
+        let buffer = match state.paused.as_mut() {
+            Some(buffer) => buffer,
+            None => {
+                return Ok(self.coalesce_publish(
+                    &mut state.coalescing,
+                    key,
+                    producer,
+                    target,
+                    condition,
+                    modify,
+                ))
+            }
+        };
+
+        let buffered = buffer.contains_key(key);
+        let mut pending = buffer
+            .remove(key)
+            .unwrap_or_else(|| target.observable.latest());
+
+        let changed = condition(&pending);
+
+        if changed {
+            modify(&mut pending);
+        }
+
+        if changed || buffered {
+            buffer.insert(key.clone(), pending);
+        }
+
+        Ok(changed)
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Eq,
+{
+    /// Publish the value through the gate if it differs from the current one
+    pub(crate) fn publish_gate_if_changed(
+        &self,
+        key: &K,
+        producer: Option<&'static str>,
+        target: PublishTarget<'_, V>,
+        value: V,
+    ) -> anyhow::Result<bool> {
+        self.publish_value_gate_as(key, producer, target, |current, new| current != new, value)
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{KeyPrefix, SubscriptionMap};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::time::Duration;
+
+/// The part of the configuration which can be changed while the map is in use
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Tunables {
+    pub capacity: Option<usize>,
+    pub keep_alive: Option<Duration>,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    pub(crate) fn tunables(&self) -> Tunables {
+        *self.0.tunables.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn tune<F>(&self, tune: F)
+    where
+        F: FnOnce(&mut Tunables),
+    {
+        tune(&mut self.0.tunables.write().unwrap_or_else(|e| e.into_inner()));
+
+        // the maintenance future re-evaluates its deadlines under the new configuration
+        self.0.deadlines_changed.clone().publish(());
+    }
+
+    /// A handle to tune the configuration of the map at runtime
+    pub fn config_handle(&self) -> MapConfigHandle<K, V> {
+        MapConfigHandle { map: self.clone() }
+    }
+}
+
+/// Changes the configuration of a running map, see [`SubscriptionMap::config_handle`].
+///
+/// Changes take effect immediately for all clones of the map and are observed by
+/// [`maintain`](SubscriptionMap::maintain). This allows services to be tuned under load without
+/// restarts, e.g. through an admin endpoint.
+///
+/// ```
+/// # use async_subscription_map::SubscriptionMap;
+/// # async {
+/// let map = SubscriptionMap::<usize, usize>::builder().capacity(2).build();
+/// map.pin(1, 0).await.unwrap();
+/// map.pin(2, 0).await.unwrap();
+///
+/// let handle = map.config_handle();
+/// assert_eq!(handle.capacity(), Some(2));
+///
+/// // unreferenced entries above the new capacity are evicted right away
+/// assert_eq!(handle.set_capacity(Some(1)).await, 1);
+/// # };
+/// ```
+#[derive(Clone)]
+pub struct MapConfigHandle<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    map: SubscriptionMap<K, V>,
+}
+
+impl<K, V> MapConfigHandle<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The current entry limit, see [`capacity`](crate::SubscriptionMapBuilder::capacity)
+    pub fn capacity(&self) -> Option<usize> {
+        self.map.tunables().capacity
+    }
+
+    /// The current grace period of unreferenced entries, see
+    /// [`keep_alive`](crate::SubscriptionMapBuilder::keep_alive)
+    pub fn keep_alive(&self) -> Option<Duration> {
+        self.map.tunables().keep_alive
+    }
+
+    /// Change the grace period of entries whose last ref is dropped from now on, entries which
+    /// are already unreferenced keep their current deadline.
+    pub fn set_keep_alive(&self, keep_alive: Option<Duration>) {
+        self.map.tune(|tunables| tunables.keep_alive = keep_alive);
+    }
+
+    /// Change the entry limit and evict unreferenced entries above it, returns the amount of
+    /// evicted entries.
+    ///
+    /// Referenced entries are never evicted, so the map may stay above a lowered limit until
+    /// they are dropped. New entries are rejected in the meantime.
+    pub async fn set_capacity(&self, capacity: Option<usize>) -> usize {
+        let mut map = self.map.lock_entries(&self.map.0.entries()).await;
+        self.map.tune(|tunables| tunables.capacity = capacity);
+
+        self.map.shrink_to_capacity(&mut map)
+    }
+}
+
+impl<K, V> MapConfigHandle<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord + KeyPrefix,
+    V: Clone + Debug,
+{
+    /// Same as [`SubscriptionMap::coalesce`]
+    pub fn coalesce(&self, prefix: K, window: Duration) {
+        self.map.coalesce(prefix, window);
+    }
+
+    /// Same as [`SubscriptionMap::stop_coalescing`]
+    pub fn stop_coalescing(&self, prefix: &K) -> bool {
+        self.map.stop_coalescing(prefix)
+    }
+
+    /// The configured coalescing windows in the order they are matched
+    pub fn coalescing(&self) -> Vec<(K, Duration)> {
+        self.map.publish_state().coalescing.prefixes().to_vec()
+    }
+
+    /// Same as [`SubscriptionMap::exclude_from_cleanup`]
+    pub fn exclude_from_cleanup(&self, prefix: K) {
+        self.map.exclude_from_cleanup(prefix);
+    }
+
+    /// Same as [`SubscriptionMap::include`]
+    pub async fn include(&self, prefix: &K) -> usize {
+        self.map.include(prefix).await
+    }
+
+    /// The prefixes which are currently protected from cleanup
+    pub fn excluded_from_cleanup(&self) -> Vec<K> {
+        self.map.excluded_from_cleanup()
+    }
+}
+
+impl<K, V> Debug for MapConfigHandle<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapConfigHandle")
+            .field("capacity", &self.capacity())
+            .field("keep_alive", &self.keep_alive())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_apply_capacity_at_runtime() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let handle = map.config_handle();
+        assert_eq!(handle.capacity(), None);
+
+        let _one = map.get_or_insert(1, 0).await.unwrap();
+        map.pin(2, 0).await.unwrap();
+        map.pin(3, 0).await.unwrap();
+
+        assert_eq!(handle.set_capacity(Some(1)).await, 2);
+        assert_eq!(map.entries_snapshot().await.len(), 1);
+
+        let err = map.get_or_insert(4, 0).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CapacityExceeded));
+
+        assert_eq!(handle.set_capacity(None).await, 0);
+        assert!(map.get_or_insert(4, 0).await.is_ok());
+    }
+
+    #[async_std::test]
+    async fn should_tune_coalescing_through_handle() {
+        let map: SubscriptionMap<&'static str, usize> = SubscriptionMap::new();
+        let handle = map.config_handle();
+
+        handle.coalesce("a/", Duration::from_secs(1));
+        handle.coalesce("b/", Duration::from_secs(2));
+        handle.coalesce("a/", Duration::from_secs(3));
+        assert_eq!(
+            handle.coalescing(),
+            vec![
+                ("a/", Duration::from_secs(3)),
+                ("b/", Duration::from_secs(2))
+            ]
+        );
+
+        assert!(handle.stop_coalescing(&"a/"));
+        assert_eq!(handle.coalescing().len(), 1);
+    }
+}
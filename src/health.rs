@@ -0,0 +1,114 @@
+use crate::SubscriptionMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// A point in time report of the state of a map, see [`SubscriptionMap::health`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapHealth {
+    /// The time it took to acquire a shared lock of the entries for this report
+    pub lock_latency: Duration,
+    /// Releases of dropped refs which are waiting for the entries to be unlocked or for the
+    /// next [`sweep`](SubscriptionMap::sweep)
+    pub pending_releases: usize,
+    /// The largest amount of lifecycle events a single subscriber didn't receive yet, event
+    /// queues are unbounded and grow as long as their stream isn't polled
+    pub event_backlog: usize,
+    /// The amount of entries in the map
+    pub entries: usize,
+    /// The current entry limit, see [`capacity`](crate::SubscriptionMapBuilder::capacity)
+    pub capacity: Option<usize>,
+    /// If the map rejects new subscriptions, see [`drain`](SubscriptionMap::drain)
+    pub draining: bool,
+}
+
+impl MapHealth {
+    /// The share of the capacity which is in use, between 0 and 1 unless the limit was lowered
+    /// below the amount of referenced entries
+    pub fn capacity_usage(&self) -> Option<f64> {
+        self.capacity
+            .map(|capacity| self.entries as f64 / capacity.max(1) as f64)
+    }
+
+    /// Check if new entries can be created right now, e.g. for readiness probes
+    pub fn is_accepting(&self) -> bool {
+        !self.draining && self.capacity.is_none_or(|capacity| self.entries < capacity)
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Report the state of the map, suitable for readiness probes.
+    ///
+    /// The report shares the lock of the entries once and measures how long that took, so probes
+    /// wait for structural changes but neither block publishes nor finish pending releases. The
+    /// latency is measured in real time, independent of the configured [`Clock`](crate::Clock).
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::builder().capacity(2).build();
+    /// let _subscription = map.get_or_insert(1, 0).await.unwrap();
+    ///
+    /// let health = map.health().await;
+    /// assert_eq!(health.capacity_usage(), Some(0.5));
+    /// assert!(health.is_accepting());
+    /// # };
+    /// ```
+    pub async fn health(&self) -> MapHealth {
+        let started = Instant::now();
+        let map = self.read_entries().await;
+        let lock_latency = started.elapsed();
+
+        MapHealth {
+            lock_latency,
+            pending_releases: self.0.released().len(),
+            event_backlog: self.event_backlog(),
+            entries: map.len(),
+            capacity: self.tunables().capacity,
+            draining: self.is_draining(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_report_map_health() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::builder().capacity(1).build();
+        let _events = map.lifecycle_events();
+
+        let health = map.health().await;
+        assert_eq!((health.entries, health.event_backlog), (0, 0));
+        assert!(health.is_accepting());
+
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let locked = map.0.entries().write_arc().await;
+        drop(subscription);
+
+        // the release waits for the lock held above, and the report doesn't finish it
+        assert_eq!(map.0.released().len(), 1);
+        drop(locked);
+
+        let health = map.health().await;
+        assert_eq!(health.pending_releases, 1);
+        assert_eq!(health.entries, 1);
+
+        map.sweep().await;
+        let health = map.health().await;
+        assert_eq!(health.pending_releases, 0);
+        assert_eq!(health.entries, 0);
+
+        let _subscription = map.get_or_insert(2, 0).await.unwrap();
+        map.drain();
+        let health = map.health().await;
+        assert_eq!(health.event_backlog, 1);
+        assert_eq!(health.capacity_usage(), Some(1.0));
+        assert!(!health.is_accepting());
+    }
+}
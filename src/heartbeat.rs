@@ -0,0 +1,166 @@
+use crate::{Error, SubscriptionRef};
+use anyhow::Context;
+use futures::future::{self, Either};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Duration;
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Wait for the next value, but fail with [`Error::StalePublisher`] if none is published
+    /// within the interval after the latest one.
+    ///
+    /// Staleness is measured from the latest publish to the entry, not from the call, so a
+    /// producer which went quiet before the call is reported right away. The value stays
+    /// unchanged and the ref remains usable, so consumers can fail over to another data source
+    /// and keep waiting, calls fail immediately until the producer publishes again. Calling this
+    /// in a loop turns the interval into a heartbeat of the producer. Timing is driven by the
+    /// [`Clock`](crate::Clock) of the map.
+    ///
+    /// ```
+    /// # use async_subscription_map::{Error, SubscriptionMap};
+    /// # use std::time::Duration;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    ///
+    /// let err = subscription
+    ///     .next_within(Duration::from_millis(10))
+    ///     .await
+    ///     .unwrap_err();
+    /// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::StalePublisher));
+    /// # };
+    /// ```
+    pub async fn next_within(&mut self, interval: Duration) -> anyhow::Result<V> {
+        // deadlines which can't be represented are never reached
+        let deadline = match self.meta.updated_at().checked_add(interval) {
+            Some(deadline) => deadline,
+            None => return Ok(self.next().await),
+        };
+
+        let timeout = self.owner.0.config.clock.sleep_until(deadline);
+        let value = {
+            let next = self.next();
+            futures::pin_mut!(next);
+
+            match future::select(next, timeout).await {
+                Either::Left((value, _)) => Some(value),
+                Either::Right(_) => None,
+            }
+        };
+
+        value.ok_or(Error::StalePublisher).with_context(|| {
+            format!(
+                "no publish to {:?} within {:?}",
+                self.owner.redacted_key(&self.key),
+                interval
+            )
+        })
+    }
+
+    /// Same as [`next_within`](Self::next_within) with the
+    /// [`heartbeat`](crate::SubscriptionMapBuilder::heartbeat) of the map, waits indefinitely if
+    /// none is configured.
+    pub async fn next_or_stale(&mut self) -> anyhow::Result<V> {
+        match self.owner.0.config.heartbeat {
+            Some(interval) => self.next_within(interval).await,
+            None => Ok(self.next().await),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, ManualClock, SubscriptionMap};
+    use futures::FutureExt;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_signal_stale_publisher() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .heartbeat(Duration::from_secs(5))
+            .build();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(subscription.next_or_stale().await.unwrap(), 1);
+
+        {
+            let next = subscription.next_or_stale();
+            futures::pin_mut!(next);
+            assert!(next.as_mut().now_or_never().is_none());
+
+            clock.advance(Duration::from_secs(5));
+            let err = next.await.unwrap_err();
+            assert_eq!(err.downcast_ref::<Error>(), Some(&Error::StalePublisher));
+        }
+
+        // the producer stays stale until it publishes again
+        let err = subscription.next_or_stale().await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::StalePublisher));
+
+        // the value is untouched and later publishes are received again
+        assert_eq!(subscription.latest(), 1);
+        map.publish_if_changed(&1, 2).await.unwrap();
+        assert_eq!(subscription.next_or_stale().await.unwrap(), 2);
+    }
+
+    #[cfg(feature = "latency")]
+    #[async_std::test]
+    async fn should_record_wakeup_latencies() {
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .publish_latency_budget(Duration::from_secs(1))
+            .build();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(
+            subscription
+                .next_within(Duration::from_secs(5))
+                .await
+                .unwrap(),
+            1
+        );
+        assert_eq!(map.publish_latency().total.count(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_measure_staleness_from_the_latest_publish() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .build();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(subscription.next().await, 1);
+        clock.advance(Duration::from_secs(3));
+
+        let next = subscription.next_within(Duration::from_secs(5));
+        futures::pin_mut!(next);
+        assert!(next.as_mut().now_or_never().is_none());
+
+        // five seconds after the publish, only two after the call
+        clock.advance(Duration::from_secs(2));
+        let err = next.await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::StalePublisher));
+    }
+
+    #[async_std::test]
+    async fn should_never_be_stale_if_the_deadline_overflows() {
+        let map = SubscriptionMap::<usize, usize>::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        let next = subscription.next_within(Duration::MAX);
+        futures::pin_mut!(next);
+        assert!(next.as_mut().now_or_never().is_none());
+
+        map.publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(next.await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{EntryMeta, SubscriptionMap, SubscriptionRef, Versioned};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::MutexGuard;
+
+/// The latest published values of a key with their versions, oldest first
+#[derive(Debug)]
+pub(crate) struct History<V> {
+    len: usize,
+    pub values: VecDeque<Versioned<V>>,
+}
+
+impl<V> History<V> {
+    fn push(&mut self, value: Versioned<V>) {
+        while self.values.len() >= self.len {
+            self.values.pop_front();
+        }
+
+        self.values.push_back(value);
+    }
+}
+
+pub(crate) fn lock<V>(meta: &EntryMeta<V>) -> MutexGuard<'_, Option<History<V>>> {
+    meta.history.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Keep the last `len` published values of the entry, so subscribers which join late or lag
+    /// behind can replay them through [`SubscriptionRef::history`].
+    ///
+    /// The history starts out with the current value and is dropped together with the entry, a
+    /// length of zero stops keeping it. Returns `false` if the entry doesn't exist.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let mut producer = map.get_or_insert("progress", 0).await.unwrap();
+    /// assert!(map.keep_history(&"progress", 3).await);
+    ///
+    /// for progress in 1..=4 {
+    ///     producer.publish(progress).unwrap();
+    /// }
+    ///
+    /// let mut late = map.get_or_insert("progress", 0).await.unwrap();
+    /// assert_eq!(late.history(), vec![2, 3, 4]);
+    /// # };
+    /// ```
+    pub async fn keep_history(&self, key: &K, len: usize) -> bool {
+        // publishes record their history while holding the lock of the observable, so it must
+        // not be locked while the history is
+        let entries = self.read_entries().await;
+        let entry = match entries.get(key) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        // the history starts out with the current value and its version, both are only
+        // consistent under the lock of the observable
+        entry.observable.clone().modify_conditional(
+            |latest| {
+                let mut history = lock(&entry.meta);
+
+                if len == 0 {
+                    *history = None;
+                    return false;
+                }
+
+                let history = history.get_or_insert_with(|| History {
+                    len,
+                    values: VecDeque::from([Versioned {
+                        value: latest.clone(),
+                        version: entry.meta.version(),
+                    }]),
+                });
+                history.len = len;
+
+                while history.values.len() > len {
+                    history.values.pop_front();
+                }
+
+                false
+            },
+            |_| {},
+        );
+
+        true
+    }
+
+    /// Append a value which is published to the entry to its history, if one is kept
+    pub(crate) fn record_history(&self, meta: &EntryMeta<V>, version: u64, value: &V) {
+        if let Some(history) = lock(meta).as_mut() {
+            history.push(Versioned {
+                value: value.clone(),
+                version,
+            });
+        }
+    }
+}
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The recent values of the entry oldest first, ending with the latest one, see
+    /// [`SubscriptionMap::keep_history`]. Without a kept history this is just the latest value.
+    ///
+    /// Pending updates are skipped, so [`next`](Self::next) continues with the publishes after
+    /// the replayed ones. A publish racing with this call may be yielded by both.
+    pub fn history(&mut self) -> Vec<V> {
+        let latest = self.synchronize();
+
+        match lock(&self.meta).as_ref() {
+            Some(history) => history.values.iter().map(|v| v.value.clone()).collect(),
+            None => vec![latest],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+    use futures::FutureExt;
+
+    #[async_std::test]
+    async fn should_replay_bounded_history() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(producer.history(), vec![0]);
+        assert!(!map.keep_history(&2, 2).await);
+
+        assert!(map.keep_history(&1, 2).await);
+        producer.publish(1).unwrap();
+        assert!(!producer.publish_if_changed(1).unwrap());
+        producer.publish(2).unwrap();
+
+        let mut late = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(late.history(), vec![1, 2]);
+        assert!(late.next().now_or_never().is_none());
+
+        producer.publish(3).unwrap();
+        assert_eq!(late.next().await, 3);
+
+        assert!(map.keep_history(&1, 0).await);
+        assert_eq!(late.history(), vec![3]);
+    }
+
+    #[async_std::test]
+    async fn should_record_paused_publishes_once_resumed() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        map.keep_history(&1, 4).await;
+
+        map.pause();
+        producer.publish(1).unwrap();
+        producer.publish(2).unwrap();
+        map.resume().await;
+        assert_eq!(producer.history(), vec![0, 2]);
+
+        drop(producer);
+        let mut subscription = map.get_or_insert(1, 5).await.unwrap();
+        assert_eq!(subscription.history(), vec![5]);
+    }
+
+    #[async_std::test]
+    async fn should_keep_histories_of_swapped_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let other: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let swapped_out = map.get_or_insert(1, 0).await.unwrap();
+        let mut swapped_in = other.get_or_insert(1, 0).await.unwrap();
+        other.keep_history(&1, 2).await;
+        swapped_in.publish(1).unwrap();
+
+        map.swap_contents(&other);
+        // removing the entry which moved to the other map leaves the one of this map alone
+        drop(swapped_out);
+
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(subscription.history(), vec![0, 1]);
+    }
+}
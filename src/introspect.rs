@@ -0,0 +1,75 @@
+use crate::SubscriptionMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The amount of entries, including pinned and retained ones without subscribers.
+    pub async fn len(&self) -> usize {
+        self.read_entries().await.len()
+    }
+
+    /// Check if the map has no entries
+    pub async fn is_empty(&self) -> bool {
+        self.read_entries().await.is_empty()
+    }
+
+    /// Check if an entry exists at the key
+    pub async fn contains_key(&self, key: &K) -> bool {
+        self.read_entries().await.contains_key(key)
+    }
+
+    /// The keys of all entries in ascending order
+    pub async fn keys(&self) -> Vec<K> {
+        self.read_entries().await.keys().cloned().collect()
+    }
+
+    /// The amount of refs subscribed to the key, zero if no entry exists.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let _subscription = map.get_or_insert(1, 0).await.unwrap();
+    /// map.pin(2, 0).await.unwrap();
+    ///
+    /// assert_eq!(map.keys().await, vec![1, 2]);
+    /// assert_eq!(map.subscribers(&1).await, 1);
+    /// assert_eq!(map.subscribers(&2).await, 0);
+    /// # };
+    /// ```
+    pub async fn subscribers(&self, key: &K) -> usize {
+        self.read_entries()
+            .await
+            .get(key)
+            .map_or(0, |entry| entry.rc())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_introspect_live_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        assert!(map.is_empty().await);
+
+        let one = map.get_or_insert(1, 0).await.unwrap();
+        let _also_one = map.get_or_insert(1, 0).await.unwrap();
+        map.pin(3, 0).await.unwrap();
+
+        assert_eq!(map.len().await, 2);
+        assert!(map.contains_key(&1).await);
+        assert!(!map.contains_key(&2).await);
+        assert_eq!(map.keys().await, vec![1, 3]);
+        assert_eq!(map.subscribers(&1).await, 2);
+
+        drop(one);
+        assert_eq!(map.subscribers(&1).await, 1);
+        assert_eq!(map.subscribers(&2).await, 0);
+    }
+}
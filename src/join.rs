@@ -0,0 +1,141 @@
+use crate::{ReadOnlyRef, SubscriptionMap};
+use futures::future::{self, Either};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+impl<K, A> SubscriptionMap<K, A>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    A: Clone + Debug,
+{
+    /// Observe the same key in this and another map as pairs of their values.
+    ///
+    /// Both entries are created with the given values if they don't exist yet and are kept
+    /// alive by the returned subscription, which yields whenever either of them is published.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let config = SubscriptionMap::<&'static str, usize>::default();
+    /// let status = SubscriptionMap::<&'static str, bool>::default();
+    ///
+    /// let mut joined = config.join(&status, "worker", 4, false).await.unwrap();
+    /// status.publish_if_changed(&"worker", true).await.unwrap();
+    /// assert_eq!(joined.next().await, (4, true));
+    /// # };
+    /// ```
+    pub async fn join<B>(
+        &self,
+        other: &SubscriptionMap<K, B>,
+        key: K,
+        value: A,
+        other_value: B,
+    ) -> anyhow::Result<JoinRef<K, A, B>>
+    where
+        B: Clone + Debug,
+    {
+        let left = self.get_or_insert_read_only(key.clone(), value).await?;
+        let right = other.get_or_insert_read_only(key, other_value).await?;
+
+        Ok(JoinRef { left, right })
+    }
+}
+
+/// A read-only subscription to the same key in two maps, see [`SubscriptionMap::join`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct JoinRef<K, A, B>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    A: Clone + Debug,
+    B: Clone + Debug,
+{
+    left: ReadOnlyRef<K, A>,
+    right: ReadOnlyRef<K, B>,
+}
+
+impl<K, A, B> JoinRef<K, A, B>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    A: Clone + Debug,
+    B: Clone + Debug,
+{
+    /// The key of the observed entries
+    pub fn key(&self) -> &K {
+        self.left.key()
+    }
+
+    /// Clones of the latest values, without consuming the changes.
+    pub fn latest(&self) -> (A, B) {
+        (self.left.latest(), self.right.latest())
+    }
+
+    /// Wait until either entry is published and return clones of both latest values.
+    ///
+    /// Changes of both entries which happen at the same time are yielded as a single pair.
+    pub async fn next(&mut self) -> (A, B) {
+        let changed = {
+            let left = self.left.next();
+            let right = self.right.next();
+            futures::pin_mut!(left, right);
+
+            match future::select(left, right).await {
+                Either::Left((left, _)) => Either::Left(left),
+                Either::Right((right, _)) => Either::Right(right),
+            }
+        };
+
+        match changed {
+            Either::Left(left) => (left, self.right.synchronize()),
+            Either::Right(right) => (self.left.synchronize(), right),
+        }
+    }
+
+    /// Skip any pending updates and return the latest values.
+    pub fn synchronize(&mut self) -> (A, B) {
+        (self.left.synchronize(), self.right.synchronize())
+    }
+}
+
+impl<K, A, B> Debug for JoinRef<K, A, B>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    A: Clone + Debug,
+    B: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JoinRef")
+            .field("left", &self.left)
+            .field("right", &self.right)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_yield_pairs_on_either_publish() {
+        let left: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let right: SubscriptionMap<usize, &'static str> = SubscriptionMap::new();
+
+        let mut joined = left.join(&right, 1, 0, "idle").await.unwrap();
+        assert_eq!(joined.latest(), (0, "idle"));
+
+        left.publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(joined.next().await, (1, "idle"));
+
+        right.publish_if_changed(&1, "busy").await.unwrap();
+        assert_eq!(joined.next().await, (1, "busy"));
+
+        // simultaneous changes are yielded once
+        left.publish_if_changed(&1, 2).await.unwrap();
+        right.publish_if_changed(&1, "idle").await.unwrap();
+        assert_eq!(joined.next().await, (2, "idle"));
+        assert_eq!(joined.synchronize(), (2, "idle"));
+
+        drop(joined);
+        assert_eq!(left.entries_snapshot().await.len(), 0);
+        assert_eq!(right.entries_snapshot().await.len(), 0);
+    }
+}
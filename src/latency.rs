@@ -0,0 +1,256 @@
+use crate::{Error, SubscriptionMap};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::MutexGuard;
+use std::time::{Duration, Instant};
+
+const BUCKETS: usize = 32;
+
+/// A distribution of publish latencies in power of two microsecond buckets.
+///
+/// Bucket `i` counts latencies below `2^i` microseconds, the last bucket also contains everything
+/// exceeding its bound.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    buckets: [u64; BUCKETS],
+}
+
+impl LatencyHistogram {
+    fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros();
+        let bucket = (u128::BITS - micros.leading_zeros()) as usize;
+        self.buckets[bucket.min(BUCKETS - 1)] += 1;
+    }
+
+    /// The total amount of recorded latencies
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// The upper bounds of all buckets together with their counts
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (Duration::from_micros(1 << i), *count))
+    }
+
+    /// The upper bound of the bucket containing the quantile `q` (between 0 and 1) of all
+    /// recorded latencies.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+
+        self.buckets().find_map(|(bound, bucket)| {
+            seen += bucket;
+            (seen >= rank).then_some(bound)
+        })
+    }
+}
+
+/// Publish to wakeup latencies of a map, in total and per configured key prefix, see
+/// [`SubscriptionMap::publish_latency`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublishLatency<K> {
+    /// Latencies over all keys
+    pub total: LatencyHistogram,
+    /// Latencies over the keys of every prefix configured via
+    /// [`stats_prefixes`](crate::SubscriptionMapBuilder::stats_prefixes)
+    pub prefixes: Vec<(K, LatencyHistogram)>,
+}
+
+/// Records the publish to wakeup latencies of a map
+#[derive(Debug)]
+pub(crate) struct LatencyRecorder<K> {
+    /// When the latest value of every key was published
+    published: BTreeMap<K, Instant>,
+    total: LatencyHistogram,
+    prefixes: Vec<LatencyHistogram>,
+}
+
+impl<K> LatencyRecorder<K> {
+    pub fn new(prefixes: usize) -> Self {
+        Self {
+            published: BTreeMap::new(),
+            total: LatencyHistogram::default(),
+            prefixes: vec![LatencyHistogram::default(); prefixes],
+        }
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The recorder of the map, if latencies are measured at all
+    fn latency(&self) -> Option<MutexGuard<'_, LatencyRecorder<K>>> {
+        self.0.config.latency_budget?;
+        Some(self.0.latency.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Remember when the latest value of the key was published
+    pub(crate) fn record_publish(&self, key: &K) {
+        if let Some(mut latency) = self.latency() {
+            latency.published.insert(key.clone(), Instant::now());
+        }
+    }
+
+    /// Record the latency of a subscriber of the key which was just woken
+    pub(crate) fn record_wakeup(&self, key: &K) {
+        let mut latency = match self.latency() {
+            Some(latency) => latency,
+            None => return,
+        };
+
+        let elapsed = match latency.published.get(key) {
+            Some(published) => published.elapsed(),
+            None => return,
+        };
+
+        latency.total.record(elapsed);
+
+        if let Some(prefix) = self.0.config.prefix_of(key) {
+            latency.prefixes[prefix].record(elapsed);
+        }
+    }
+
+    /// Drop the publish time of a removed entry
+    pub(crate) fn forget_publish(&self, key: &K) {
+        if let Some(mut latency) = self.latency() {
+            latency.published.remove(key);
+        }
+    }
+
+    /// The latencies between publishes and the wakeups of their subscribers, measured while a
+    /// [`publish_latency_budget`](crate::SubscriptionMapBuilder::publish_latency_budget) is
+    /// configured.
+    pub fn publish_latency(&self) -> PublishLatency<K> {
+        let (total, prefixes) = match self.latency() {
+            Some(latency) => (latency.total.clone(), latency.prefixes.clone()),
+            None => Default::default(),
+        };
+
+        PublishLatency {
+            total,
+            prefixes: self
+                .0
+                .config
+                .stats_prefixes
+                .iter()
+                .cloned()
+                .zip(prefixes)
+                .collect(),
+        }
+    }
+
+    /// Fail with [`Error::LatencyBudgetExceeded`] if the p99 publish latency in total or of any
+    /// prefix exceeds the configured budget.
+    ///
+    /// Latencies are bucketed, the upper bound of the bucket containing the p99 is compared to
+    /// the budget. Succeeds if no budget is configured.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # use std::time::Duration;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::builder()
+    ///     .publish_latency_budget(Duration::from_millis(100))
+    ///     .build();
+    ///
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    /// map.publish_if_changed(&1, 1).await.unwrap();
+    /// subscription.next().await;
+    ///
+    /// assert_eq!(map.publish_latency().total.count(), 1);
+    /// map.check_publish_latency().unwrap();
+    /// # };
+    /// ```
+    pub fn check_publish_latency(&self) -> anyhow::Result<()> {
+        let budget = match self.0.config.latency_budget {
+            Some(budget) => budget,
+            None => return Ok(()),
+        };
+
+        let latency = self.publish_latency();
+        let prefixes = latency
+            .prefixes
+            .iter()
+            .map(|(prefix, histogram)| (Some(prefix), histogram));
+
+        for (prefix, histogram) in std::iter::once((None, &latency.total)).chain(prefixes) {
+            match histogram.quantile(0.99) {
+                Some(p99) if p99 > budget => {
+                    return Err(Error::LatencyBudgetExceeded).with_context(|| {
+                        format!(
+                            "p99 publish latency of {} is below {:?}, budget is {:?}",
+                            prefix.map_or("all keys".to_string(), |prefix| format!(
+                                "{:?}",
+                                self.redacted_key(prefix)
+                            )),
+                            p99,
+                            budget
+                        )
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LatencyHistogram;
+    use crate::{Error, SubscriptionMap};
+    use std::time::Duration;
+
+    #[test]
+    fn should_compute_latency_quantiles() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.quantile(0.99), None);
+
+        for _ in 0..99 {
+            histogram.record(Duration::from_micros(3));
+        }
+        histogram.record(Duration::from_millis(1));
+
+        assert_eq!(histogram.quantile(0.99), Some(Duration::from_micros(4)));
+        assert_eq!(histogram.quantile(1.0), Some(Duration::from_micros(1024)));
+    }
+
+    #[async_std::test]
+    async fn should_fail_once_budget_is_exceeded() {
+        let map = SubscriptionMap::<&'static str, usize>::builder()
+            .stats_prefixes(["slow/"])
+            .publish_latency_budget(Duration::from_millis(1))
+            .build();
+
+        let mut subscription = map.get_or_insert("slow/1", 0).await.unwrap();
+        map.publish_if_changed(&"slow/1", 1).await.unwrap();
+        async_std::task::sleep(Duration::from_millis(5)).await;
+        subscription.next().await;
+
+        let latency = map.publish_latency();
+        assert_eq!(latency.prefixes[0].1.count(), 1);
+
+        let err = map.check_publish_latency().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::LatencyBudgetExceeded)
+        );
+
+        drop(subscription);
+        assert!(map.0.latency.lock().unwrap().published.is_empty());
+    }
+}
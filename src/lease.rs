@@ -0,0 +1,419 @@
+use crate::gate::PublishTarget;
+use crate::{diag, Entries, EntryMeta, Error, SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use async_observable::Observable;
+use futures::future::{self, Either};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant};
+
+/// The subscription held by a lease until it expires
+pub(crate) struct Lease<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Leases without a ttl are only released explicitly
+    ttl: Option<Duration>,
+    state: Mutex<LeaseState<K, V>>,
+}
+
+struct LeaseState<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    deadline: Option<Instant>,
+    subscription: Option<SubscriptionRef<K, V>>,
+}
+
+impl<K, V> Lease<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn state(&self) -> MutexGuard<'_, LeaseState<K, V>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take the subscription if the lease expired, it has to be dropped after the state is unlocked
+    fn expire(&self, now: Instant) -> Option<SubscriptionRef<K, V>> {
+        let mut state = self.state();
+
+        if state.deadline.is_some_and(|deadline| deadline <= now) {
+            state.subscription.take()
+        } else {
+            None
+        }
+    }
+
+    /// Take the subscription regardless of the deadline
+    pub(crate) fn release(&self) -> Option<SubscriptionRef<K, V>> {
+        self.state().subscription.take()
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn leases(&self) -> MutexGuard<'_, Vec<Weak<Lease<K, V>>>> {
+        self.0.leases.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Same as [`get_or_insert`](Self::get_or_insert) but the subscription is released
+    /// automatically once the ttl elapses without a [`renew`](LeasedRef::renew).
+    ///
+    /// This protects the self cleaning nature of the map against tasks which hang while holding a
+    /// ref. Expired leases are released by [`sweep`](Self::sweep) and
+    /// [`maintain`](Self::maintain), or as soon as the lease is used again.
+    ///
+    /// ```
+    /// # use async_subscription_map::{Error, ManualClock, SubscriptionMap};
+    /// # use std::time::Duration;
+    /// # async {
+    /// let clock = ManualClock::new();
+    /// let map = SubscriptionMap::<usize, usize>::builder()
+    ///     .clock(clock.clone())
+    ///     .build();
+    ///
+    /// let mut lease = map
+    ///     .get_or_insert_leased(1, 0, Duration::from_secs(10))
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// clock.advance(Duration::from_secs(5));
+    /// lease.renew().unwrap();
+    ///
+    /// clock.advance(Duration::from_secs(10));
+    /// let err = lease.publish(1).unwrap_err();
+    /// assert_eq!(err.downcast_ref::<Error>(), Some(&Error::LeaseExpired));
+    /// # };
+    /// ```
+    pub async fn get_or_insert_leased(
+        &self,
+        key: K,
+        value: V,
+        ttl: Duration,
+    ) -> anyhow::Result<LeasedRef<K, V>> {
+        let subscription = self.get_or_insert(key, value).await?;
+        let (lease, leased) = self.lease(subscription, Some(ttl));
+
+        self.leases().push(Arc::downgrade(&lease));
+        self.0.deadlines_changed.clone().publish(());
+
+        Ok(leased)
+    }
+
+    /// Move the subscription into a lease which expires after the ttl, if any
+    pub(crate) fn lease(
+        &self,
+        subscription: SubscriptionRef<K, V>,
+        ttl: Option<Duration>,
+    ) -> (Arc<Lease<K, V>>, LeasedRef<K, V>) {
+        let key = subscription.key.clone();
+        let observable = subscription.observable.clone();
+        let entries = subscription.entries.clone();
+        let meta = subscription.meta.clone();
+
+        let lease = Arc::new(Lease {
+            ttl,
+            state: Mutex::new(LeaseState {
+                // deadlines which can't be represented are never reached
+                deadline: ttl.and_then(|ttl| self.0.config.clock.now().checked_add(ttl)),
+                subscription: Some(subscription),
+            }),
+        });
+
+        let leased = LeasedRef {
+            key,
+            owner: self.internal(),
+            lease: lease.clone(),
+            entries,
+            observable,
+            meta,
+        };
+
+        (lease, leased)
+    }
+
+    /// Release all expired leases and return the deadline of the next one
+    pub(crate) fn release_expired_leases(&self) -> Option<Instant> {
+        let now = self.0.config.clock.now();
+        let mut expired = Vec::new();
+        let mut next = None;
+
+        self.leases().retain(|lease| {
+            let lease = match lease.upgrade() {
+                Some(lease) => lease,
+                None => return false,
+            };
+
+            match lease.expire(now) {
+                Some(subscription) => {
+                    expired.push(subscription);
+                    false
+                }
+                None => {
+                    let state = lease.state();
+
+                    if let (Some(deadline), true) = (state.deadline, state.subscription.is_some()) {
+                        next = Some(next.map_or(deadline, |next: Instant| next.min(deadline)));
+                    }
+
+                    state.subscription.is_some()
+                }
+            }
+        });
+
+        for subscription in expired {
+            diag::warning!(
+                "releasing expired lease of key {:?}",
+                self.redacted_key(&subscription.key)
+            );
+        }
+
+        next
+    }
+}
+
+/// A subscription which is released automatically if it isn't renewed in time.
+///
+/// All operations fail with [`Error::LeaseExpired`] once the lease expired.
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct LeasedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    key: K,
+    owner: SubscriptionMap<K, V>,
+    lease: Arc<Lease<K, V>>,
+    entries: Arc<Entries<K, V>>,
+    observable: Observable<V>,
+    meta: Arc<EntryMeta<V>>,
+}
+
+impl<K, V> LeasedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Fail if the lease expired, releases the subscription if that happened just now
+    fn check(&self) -> anyhow::Result<()> {
+        let now = self.owner.0.config.clock.now();
+        let expired = self.lease.expire(now);
+        let held = self.lease.state().subscription.is_some();
+
+        drop(expired);
+
+        if held {
+            Ok(())
+        } else {
+            Err(Error::LeaseExpired)
+                .with_context(|| format!("lease of key {:?}", self.owner.redacted_key(&self.key)))
+        }
+    }
+
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    fn target(&self) -> PublishTarget<'_, V> {
+        PublishTarget {
+            observable: self.observable.clone(),
+            meta: &self.meta,
+            detached: self.meta.is_detached() || !self.owner.0.is_current(&self.entries),
+        }
+    }
+
+    /// Check if the lease expired
+    pub fn is_expired(&self) -> bool {
+        let state = self.lease.state();
+        let now = self.owner.0.config.clock.now();
+
+        state.subscription.is_none() || state.deadline.is_some_and(|deadline| deadline <= now)
+    }
+
+    /// Extend the lease by its ttl starting now.
+    pub fn renew(&mut self) -> anyhow::Result<()> {
+        self.check()?;
+
+        if let Some(ttl) = self.lease.ttl {
+            self.lease.state().deadline = self.owner.0.config.clock.now().checked_add(ttl);
+            self.owner.0.deadlines_changed.clone().publish(());
+        }
+
+        Ok(())
+    }
+
+    /// A clone of the latest value, without consuming the change.
+    pub fn latest(&self) -> anyhow::Result<V> {
+        self.check()?;
+        Ok(self.observable.latest())
+    }
+
+    /// Wait until a new version is published and return a clone of it.
+    ///
+    /// Fails with [`Error::LeaseExpired`] as soon as the lease expires while waiting.
+    pub async fn next(&mut self) -> anyhow::Result<V> {
+        loop {
+            self.check()?;
+
+            let deadline = self.lease.state().deadline;
+            let next = self.observable.next();
+
+            let value = match deadline {
+                Some(deadline) => {
+                    let sleep = self.owner.0.config.clock.sleep_until(deadline);
+                    futures::pin_mut!(next);
+
+                    match future::select(next, sleep).await {
+                        Either::Left((value, _)) => value,
+                        // the next check fails unless the sleep woke up early
+                        Either::Right(_) => continue,
+                    }
+                }
+                None => next.await,
+            };
+
+            self.check()?;
+            return Ok(value);
+        }
+    }
+
+    /// Store the provided value and notify all subscribers.
+    pub fn publish(&mut self, value: V) -> anyhow::Result<()> {
+        self.check()?;
+        self.owner
+            .publish_value_gate(&self.key, self.target(), |_, _| true, value)?;
+
+        Ok(())
+    }
+
+    /// Modify the value through a mutable reference and notify all subscribers.
+    pub fn modify<F>(&mut self, modify: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut V),
+    {
+        self.check()?;
+        self.owner
+            .publish_gate(&self.key, self.target(), |_| true, modify)?;
+
+        Ok(())
+    }
+}
+
+impl<K, V> Debug for LeasedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.observable.latest();
+
+        f.debug_struct("LeasedRef")
+            .field("key", &self.owner.redacted_key(&self.key))
+            .field("value", &self.owner.redacted(&value))
+            .field("expired", &self.is_expired())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    fn map() -> (ManualClock, SubscriptionMap<usize, usize>) {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::builder().clock(clock.clone()).build();
+        (clock, map)
+    }
+
+    #[async_std::test]
+    async fn should_release_expired_leases_on_sweep() {
+        let (clock, map) = map();
+        let lease = map
+            .get_or_insert_leased(1, 0, Duration::from_secs(10))
+            .await
+            .unwrap();
+        let _other = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 2);
+
+        clock.advance(Duration::from_secs(10));
+        map.sweep().await;
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 1);
+
+        let err = lease.latest().unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::LeaseExpired));
+        assert!(lease.is_expired());
+    }
+
+    #[async_std::test]
+    async fn should_extend_renewed_leases() {
+        let (clock, map) = map();
+        let mut lease = map
+            .get_or_insert_leased(1, 0, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        clock.advance(Duration::from_secs(8));
+        lease.renew().unwrap();
+        clock.advance(Duration::from_secs(8));
+        map.sweep().await;
+
+        lease.publish(1).unwrap();
+        assert_eq!(lease.latest().unwrap(), 1);
+
+        // expiry is detected on use even without a sweep
+        clock.advance(Duration::from_secs(2));
+        assert!(lease.renew().is_err());
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_release_lease_on_drop() {
+        let (_, map) = map();
+        let lease = map
+            .get_or_insert_leased(1, 0, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        drop(lease);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+        assert_eq!(map.release_expired_leases(), None);
+        assert!(map.leases().is_empty());
+    }
+
+    #[async_std::test]
+    async fn should_expire_while_waiting_for_the_next_value() {
+        let (clock, map) = map();
+        let mut lease = map
+            .get_or_insert_leased(1, 0, Duration::from_secs(10))
+            .await
+            .unwrap();
+
+        let mut next = Box::pin(lease.next());
+        assert!(futures::poll!(&mut next).is_pending());
+
+        clock.advance(Duration::from_secs(10));
+        let err = next.await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::LeaseExpired));
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_never_expire_leases_with_unrepresentable_deadlines() {
+        let (clock, map) = map();
+        let mut lease = map.get_or_insert_leased(1, 0, Duration::MAX).await.unwrap();
+
+        clock.advance(Duration::from_secs(3600));
+        lease.renew().unwrap();
+        assert!(!lease.is_expired());
+        assert_eq!(map.release_expired_leases(), None);
+    }
+}
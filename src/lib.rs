@@ -26,15 +26,184 @@
 //! The subscription map is selfcleaing in the sense that it removes every
 //! subscription entry and its data as soon as no one subscribes to it and thus
 //! actively preventing memory leaks!
+//!
+//! ## Minimal Builds
+//!
+//! All diagnostics of the map are default features which compile out entirely once disabled,
+//! for builds which are sensitive to binary size or latency:
+//!
+//! - `log` logs through the `log` crate
+//! - `stats` collects the churn [statistics](SubscriptionMap::stats)
+//! - `latency` measures the [publish latency](SubscriptionMap::publish_latency)
+//!
+//! Builds with `default-features = false` only keep the bare map semantics. The builder options
+//! configuring a diagnostic are only available together with its feature.
+
+mod batch;
+mod breaker;
+mod builder;
+mod bulk;
+mod capacity;
+mod cas;
+mod cdc;
+mod claim;
+mod clock;
+mod close;
+mod coalesce;
+mod compact;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+mod dedup;
+#[cfg(feature = "demo")]
+pub mod demo;
+mod dependency;
+mod diag;
+mod drain;
+mod entry;
+mod error;
+mod events;
+mod exclude;
+mod failover;
+mod fallible;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
+mod filter;
+mod firehose;
+mod fixed;
+mod freeze;
+mod gate;
+mod handle;
+mod health;
+mod heartbeat;
+mod history;
+mod introspect;
+mod join;
+#[cfg(feature = "latency")]
+mod latency;
+mod lease;
+mod lifecycle;
+mod mapped;
+mod matcher;
+mod modify;
+mod mux;
+mod negotiate;
+mod overlay;
+mod pause;
+mod pin;
+mod prefix;
+mod priority;
+mod publisher;
+mod rate;
+mod read_only;
+mod redact;
+mod remove;
+mod resume;
+mod retention;
+mod scope;
+#[cfg(any(feature = "json", feature = "toml"))]
+mod seed;
+mod set;
+mod sharded;
+#[cfg(feature = "sim")]
+pub mod sim;
+mod size;
+mod snapshot;
+mod spin;
+mod split;
+#[cfg(feature = "stats")]
+mod stats;
+mod stream;
+mod swap;
+mod tag;
+mod throttle;
+#[doc(hidden)]
+pub mod topic;
+mod transaction;
+mod update;
+mod version;
+mod view;
+mod weak;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+pub use breaker::FeedStatus;
+pub use builder::SubscriptionMapBuilder;
+pub use cdc::{CdcChange, CdcStream};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use compact::Compaction;
+pub use dedup::DedupRef;
+pub use dependency::Invalidation;
+pub use entry::Entry;
+pub use error::Error;
+pub use events::{LifecycleEvents, MapEvent, MapEvents};
+pub use failover::{Failover, FailoverRef};
+pub use filter::FilteredRef;
+pub use firehose::Firehose;
+pub use fixed::{FixedSubscriptionMap, FixedSubscriptionRef};
+pub use handle::MapConfigHandle;
+pub use health::MapHealth;
+pub use join::JoinRef;
+#[cfg(feature = "latency")]
+pub use latency::{LatencyHistogram, PublishLatency};
+pub use lease::LeasedRef;
+pub use lifecycle::EntryState;
+pub use mapped::MappedRef;
+pub use matcher::{AllKeys, Exact, Glob, KeyMatcher, Prefix};
+pub use mux::Mux;
+pub use negotiate::{Negotiated, SubscribeMode};
+pub use overlay::Overlay;
+pub use prefix::KeyPrefix;
+pub use priority::Priority;
+pub use publisher::PublisherRef;
+pub use rate::Rate;
+pub use read_only::ReadOnlyRef;
+pub use redact::{FullRedaction, HashedKeys, Redactor, TruncatedKeys};
+pub use resume::{Resumed, ResumedRef};
+pub use scope::{Bound, TaskScope};
+#[cfg(any(feature = "json", feature = "toml"))]
+pub use seed::SeedFormat;
+pub use set::SubscriptionSet;
+pub use sharded::ShardedSubscriptionMap;
+pub use snapshot::{EntrySnapshot, MapSnapshot, SnapshotChange, SnapshotDiff};
+pub use split::{KeepAlive, ReadHalf};
+#[cfg(feature = "stats")]
+pub use stats::{ChurnStats, LifetimeHistogram, MapStats};
+pub use throttle::{DebouncedRef, ThrottledRef};
+pub use topic::Topic;
+pub use update::{Update, UpdateRef};
+pub use version::Versioned;
+pub use view::{Access, RestrictedView};
+pub use weak::WeakSubscriptionRef;
+
+use breaker::Breaker;
+use builder::Config;
+use cdc::ChangeQueues;
+use close::Handle;
+use dependency::Dependencies;
+use events::EventQueue;
+use exclude::Exclusions;
+use gate::{PublishState, PublishTarget};
+use handle::Tunables;
+use history::History;
+#[cfg(feature = "latency")]
+use latency::LatencyRecorder;
+use lease::Lease;
+use priority::EntryLock;
+use redact::Redacted;
+use retention::Retention;
+#[cfg(feature = "stats")]
+use stats::{ChurnOrigin, Stats};
+use update::UpdateMeta;
+
 use anyhow::Context;
+use async_lock::{RwLock as AsyncRwLock, RwLockReadGuardArc, RwLockWriteGuardArc};
 use async_observable::Observable;
-use async_std::sync::Mutex;
-use async_std::task::block_on;
 use std::collections::BTreeMap;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::hash::Hash;
-use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, MutexGuard, RwLock, Weak};
+use std::time::Instant;
 
 /// A concurrent and self cleaning map of observable values to easily
 /// communicate dynamically across tasks.
@@ -44,46 +213,250 @@ use std::sync::Arc;
 /// # use async_std::task;
 /// # async {
 /// let map = SubscriptionMap::<usize, usize>::default();
-/// let mut subscription = map.get_or_insert(1, 0).await;
+/// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
 ///
 /// task::spawn(async move {
 ///     // somewhere else in your program
-///     let mut subscription = map.get_or_insert(1, 0).await;
+///     let mut subscription = map.get_or_insert(1, 0).await.unwrap();
 ///     log::info!("received update throguh map: {}", subscription.next().await);
 /// });
 ///
 /// // wait for some event and publish the state
-/// subscription.publish(1);
+/// subscription.publish(1)?;
 /// // just drop the ref as soon as you are done with it to trigger the cleanup
 /// drop(subscription);
+/// # Ok::<(), anyhow::Error>(())
 /// # };
 /// ```
-#[derive(Clone, Debug)]
-pub struct SubscriptionMap<K, V>(Arc<Mutex<BTreeMap<K, SubscriptionEntry<V>>>>)
+///
+/// Refs outlive the map they were created in: once the last clone of the map is dropped, the
+/// map is closed and emits [`MapEvent::Closed`]. Remaining refs keep working on their own and no
+/// longer clean up on drop, the entries are freed together with the last of them.
+pub struct SubscriptionMap<K, V>(Arc<Shared<K, V>>, Handle)
 where
     K: Clone + Debug + Eq + Hash + Ord,
     V: Clone + Debug;
 
+/// The entries of a map, refs keep the one they were created in even if the map swaps it.
+///
+/// Operations which only locate an entry, such as map level publishes, share the lock. Only
+/// structural changes like insertions and removals need exclusive access.
+type Entries<K, V> = AsyncRwLock<BTreeMap<K, SubscriptionEntry<V>>>;
+
+/// The state shared by all clones of a map
+struct Shared<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    entries: RwLock<Arc<Entries<K, V>>>,
+    config: Config<K, V>,
+    /// Shared with the entries created in the map, see [`ChurnOrigin`]
+    #[cfg(feature = "stats")]
+    stats: Arc<std::sync::Mutex<Stats>>,
+    publish_state: RwLock<PublishState<K, V>>,
+    draining: AtomicBool,
+    /// Notified whenever draining ends, see [`SubscriptionMap::subscribe_created`]
+    undrained: Observable<()>,
+    /// Notified whenever retention or lease deadlines are added, see [`SubscriptionMap::maintain`]
+    deadlines_changed: Observable<()>,
+    leases: std::sync::Mutex<Vec<Weak<Lease<K, V>>>>,
+    dependencies: std::sync::Mutex<Dependencies<K>>,
+    tunables: RwLock<Tunables>,
+    /// Key prefixes which are protected from cleanup at runtime
+    exclusions: RwLock<Exclusions<K>>,
+    events: std::sync::Mutex<Vec<Weak<EventQueue<K>>>>,
+    /// Releases of dropped refs which could not lock the entries at the time
+    released: std::sync::Mutex<Vec<Released<K, V>>>,
+    /// Publish counters of the live [`Rate`]s per key
+    rates: RwLock<BTreeMap<K, Vec<Arc<AtomicU64>>>>,
+    /// Publish to wakeup latencies, only recorded with a configured budget
+    #[cfg(feature = "latency")]
+    latency: std::sync::Mutex<LatencyRecorder<K>>,
+    /// The last version handed out by the map
+    sequence: AtomicU64,
+    /// The queues of the live [`CdcStream`]s
+    changes: std::sync::Mutex<ChangeQueues<K, V>>,
+    /// The amount of live [`CdcStream`]s, publishes only lock their queues if there are any
+    capturing: AtomicUsize,
+    /// The amount of clones which keep the map open, see [`Handle`]
+    handles: AtomicUsize,
+    closed: AtomicBool,
+}
+
+impl<K, V> Shared<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    #[cfg(feature = "stats")]
+    fn stats(&self) -> MutexGuard<'_, Stats> {
+        stats::lock(&self.stats)
+    }
+
+    /// The entries new lookups operate on
+    fn entries(&self) -> Arc<Entries<K, V>> {
+        match self.entries.read() {
+            Ok(guard) => guard.clone(),
+            Err(e) => e.into_inner().clone(),
+        }
+    }
+
+    /// Check if the entries are the ones new lookups operate on, refs to others were
+    /// [swapped](SubscriptionMap::swap_contents) out
+    fn is_current(&self, entries: &Arc<Entries<K, V>>) -> bool {
+        match self.entries.read() {
+            Ok(guard) => Arc::ptr_eq(&guard, entries),
+            Err(e) => Arc::ptr_eq(&e.into_inner(), entries),
+        }
+    }
+
+    fn released(&self) -> MutexGuard<'_, Vec<Released<K, V>>> {
+        self.released.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A dropped ref whose release still has to look at its entry
+struct Released<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    entries: Arc<Entries<K, V>>,
+    key: K,
+    rc: Arc<AtomicUsize>,
+    tag: Option<&'static str>,
+    /// If the subscription count wasn't decremented yet
+    decrement: bool,
+    /// Injected delays defer the release until this point in time
+    not_before: Option<Instant>,
+}
+
+impl<K, V> Released<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Decrement the subscription count if needed, returns if the entry has to be looked at
+    fn decrement(&mut self) -> bool {
+        if !std::mem::take(&mut self.decrement) {
+            return true;
+        }
+
+        let last = self.rc.fetch_sub(1, Ordering::SeqCst) == 1;
+        last || self.tag.is_some()
+    }
+}
+
 /// A single observable entry and its subscription count
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 struct SubscriptionEntry<V>
 where
     V: Clone + Debug,
 {
     observable: Observable<V>,
-    rc: usize,
+    /// Shared with the refs, so only the last one to drop has to lock the entries
+    rc: Arc<AtomicUsize>,
+    // only read by the churn statistics
+    #[cfg_attr(not(feature = "stats"), allow(dead_code))]
+    created_at: Instant,
+    /// Pinned entries are kept even if no one subscribes to them
+    pinned: bool,
+    /// Unreferenced entries are kept until this deadline passes
+    retain_until: Option<Retention>,
+    /// When a ref was last created or the last ref was dropped
+    last_used: Instant,
+    /// Published whenever a source of the entry is removed
+    invalidation: Observable<()>,
+    /// Shared with the refs, set once the entry is forcibly removed
+    removed: Arc<AtomicBool>,
+    /// The tags of all tagged refs, see [`SubscriptionMap::get_or_insert_tagged`]
+    tags: Vec<&'static str>,
+    /// Serializes async read-modify-writes, see [`SubscriptionMap::modify_async`]
+    writer: Arc<EntryLock>,
+    /// If a ref was ever created, see [`EntryState::Creating`]
+    attached: bool,
+    /// Shared with the refs, so publishes through them account to the entry they were created
+    /// for even after it was removed or swapped into another map
+    meta: Arc<EntryMeta<V>>,
+    /// The statistics of the map the entry was created in, set once it is inserted
+    #[cfg(feature = "stats")]
+    churn: Option<ChurnOrigin>,
+}
+
+/// The bookkeeping of the publishes to a single entry
+#[derive(Debug)]
+struct EntryMeta<V> {
+    /// The version and origin of the latest publish, see [`Update`]
+    update: std::sync::Mutex<UpdateMeta>,
+    /// The recently published values, see [`SubscriptionMap::keep_history`]
+    history: std::sync::Mutex<Option<History<V>>>,
+    /// Failures of the producers, only tracked with a configured circuit breaker
+    breaker: std::sync::Mutex<Option<Breaker>>,
+    /// Set once the entry is removed from its map, see [`PublishTarget::detached`]
+    detached: AtomicBool,
+    /// The eviction weight of the latest value, see
+    /// [`eviction_weight`](SubscriptionMapBuilder::eviction_weight)
+    weight: AtomicU64,
+}
+
+impl<V> EntryMeta<V> {
+    fn is_detached(&self) -> bool {
+        self.detached.load(Ordering::SeqCst)
+    }
 }
 
 impl<V> SubscriptionEntry<V>
 where
     V: Clone + Debug,
 {
-    pub fn new(value: V) -> Self {
+    pub fn new(value: V, created_at: Instant, version: u64, weight: u64) -> Self {
         Self {
             observable: Observable::new(value),
-            rc: 0,
+            rc: Arc::new(AtomicUsize::new(0)),
+            created_at,
+            pinned: false,
+            retain_until: None,
+            last_used: created_at,
+            invalidation: Observable::new(()),
+            removed: Arc::new(AtomicBool::new(false)),
+            tags: Vec::new(),
+            writer: Arc::default(),
+            attached: false,
+            meta: Arc::new(EntryMeta {
+                update: std::sync::Mutex::new(UpdateMeta::new(version, created_at)),
+                history: std::sync::Mutex::new(None),
+                breaker: std::sync::Mutex::new(None),
+                detached: AtomicBool::new(false),
+                weight: AtomicU64::new(weight),
+            }),
+            #[cfg(feature = "stats")]
+            churn: None,
+        }
+    }
+
+    /// The amount of refs to the entry
+    fn rc(&self) -> usize {
+        self.rc.load(Ordering::SeqCst)
+    }
+
+    /// Publish to the entry itself
+    fn target(&self) -> PublishTarget<'_, V> {
+        PublishTarget {
+            observable: self.observable.clone(),
+            meta: &self.meta,
+            detached: false,
         }
     }
+
+    /// Check if the entry may be cleaned up at this point in time
+    fn removable(&self, now: Instant) -> bool {
+        self.rc() == 0
+            && !self.pinned
+            && self
+                .retain_until
+                .is_none_or(|retention| retention.has_passed(now))
+    }
 }
 
 impl<K, V> SubscriptionMap<K, V>
@@ -93,39 +466,488 @@ where
 {
     /// Create an empty SubscriptionMap
     pub fn new() -> Self {
-        Self(Arc::new(Mutex::new(BTreeMap::new())))
+        Self::builder().build()
     }
 
-    /// Either creates a ref to a existing subscription or initializes a new one.
-    pub async fn get_or_insert(&self, key: K, value: V) -> SubscriptionRef<K, V> {
-        let mut map = self.0.lock().await;
-        let entry = {
-            let entry = SubscriptionEntry::new(value);
-            map.entry(key.clone()).or_insert(entry)
+    /// Configure a new SubscriptionMap
+    pub fn builder() -> SubscriptionMapBuilder<K, V> {
+        SubscriptionMapBuilder::new()
+    }
+
+    fn with_config(config: Config<K, V>) -> Self {
+        #[cfg(feature = "stats")]
+        let stats = Stats::new(config.clock.now(), config.stats_prefixes.len());
+        #[cfg(feature = "latency")]
+        let latency = LatencyRecorder::new(config.stats_prefixes.len());
+        let tunables = Tunables {
+            capacity: config.capacity,
+            keep_alive: config.keep_alive,
         };
 
-        SubscriptionRef::new(key, self.clone(), entry)
+        Self(
+            Arc::new(Shared {
+                entries: RwLock::new(Arc::new(AsyncRwLock::new(BTreeMap::new()))),
+                config,
+                #[cfg(feature = "stats")]
+                stats: Arc::new(std::sync::Mutex::new(stats)),
+                publish_state: RwLock::new(PublishState::default()),
+                draining: AtomicBool::new(false),
+                undrained: Observable::new(()),
+                deadlines_changed: Observable::new(()),
+                leases: std::sync::Mutex::new(Vec::new()),
+                dependencies: std::sync::Mutex::new(Dependencies::default()),
+                tunables: RwLock::new(tunables),
+                exclusions: RwLock::new(Exclusions::default()),
+                events: std::sync::Mutex::new(Vec::new()),
+                released: std::sync::Mutex::new(Vec::new()),
+                rates: RwLock::new(BTreeMap::new()),
+                #[cfg(feature = "latency")]
+                latency: std::sync::Mutex::new(latency),
+                sequence: AtomicU64::new(0),
+                changes: std::sync::Mutex::new(Vec::new()),
+                capturing: AtomicUsize::new(0),
+                handles: AtomicUsize::new(1),
+                closed: AtomicBool::new(false),
+            }),
+            Handle::Open,
+        )
     }
 
-    #[cfg(test)]
-    async fn snapshot(&self) -> BTreeMap<K, SubscriptionEntry<V>> {
-        self.0.lock().await.deref().clone()
+    /// Either creates a ref to a existing subscription or initializes a new one.
+    ///
+    /// Concurrent calls for the same key are serialized, the value of the first one initializes
+    /// the entry and the values of all others are discarded. Use
+    /// [`negotiate`](Self::negotiate) to find out which value was used.
+    ///
+    /// Fails with [`Error::Draining`] once the map is [draining](Self::drain).
+    pub async fn get_or_insert(&self, key: K, value: V) -> anyhow::Result<SubscriptionRef<K, V>> {
+        let entries = self.0.entries();
+        let mut map = self.lock_entries(&entries).await;
+
+        if self.0.draining.load(Ordering::SeqCst) {
+            return Err(Error::Draining)
+                .with_context(|| format!("unable to subscribe to {:?}", self.redacted_key(&key)));
+        }
+
+        self.attach(&entries, &mut map, key, value)
     }
 
-    async fn remove(&self, key: &K) -> anyhow::Result<()> {
-        let mut map = self.0.lock().await;
+    /// Lock the entries and finish the releases which were deferred while they were locked
+    async fn lock_entries(
+        &self,
+        entries: &Arc<Entries<K, V>>,
+    ) -> RwLockWriteGuardArc<BTreeMap<K, SubscriptionEntry<V>>> {
+        let mut map = entries.write_arc().await;
+        self.collect_released(entries, &mut map);
+        map
+    }
+
+    /// Share the lock of the entries new lookups operate on, deferred releases are left to the
+    /// next exclusive lock
+    async fn read_entries(&self) -> RwLockReadGuardArc<BTreeMap<K, SubscriptionEntry<V>>> {
+        self.0.entries().read_arc().await
+    }
+
+    /// A clone of the latest value of the key without subscribing to it.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let _subscription = map.get_or_insert(1, 5).await.unwrap();
+    ///
+    /// assert_eq!(map.peek(&1).await, Some(5));
+    /// assert_eq!(map.peek(&2).await, None);
+    /// # };
+    /// ```
+    pub async fn peek(&self, key: &K) -> Option<V> {
+        let map = self.read_entries().await;
+        map.get(key).map(|entry| entry.observable.latest())
+    }
+
+    /// Subscribe to the key only if the entry already exists.
+    ///
+    /// This avoids inventing an initial value just to observe a key which a producer may or may
+    /// not have registered. Like [`get_or_insert`](Self::get_or_insert) it returns `None` once
+    /// the map is [draining](Self::drain).
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// assert!(map.get(&1).await.is_none());
+    ///
+    /// let _producer = map.get_or_insert(1, 5).await.unwrap();
+    /// assert_eq!(map.get(&1).await.unwrap().latest(), 5);
+    /// # };
+    /// ```
+    pub async fn get(&self, key: &K) -> Option<SubscriptionRef<K, V>> {
+        let entries = self.0.entries();
+        let mut map = self.lock_entries(&entries).await;
+
+        if self.0.draining.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        let entry = map.get_mut(key)?;
+        entry.last_used = self.0.config.clock.now();
+
+        Some(SubscriptionRef::new(
+            key.clone(),
+            self.internal(),
+            entries.clone(),
+            entry,
+        ))
+    }
+
+    /// Create a ref to the entry at the key in the locked entries, initializes it if not present
+    fn attach(
+        &self,
+        entries: &Arc<Entries<K, V>>,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+        key: K,
+        value: V,
+    ) -> anyhow::Result<SubscriptionRef<K, V>> {
+        self.attach_with(entries, map, key, || value)
+    }
+
+    /// Same as [`attach`](Self::attach) but the initial value is only computed if needed
+    fn attach_with<F>(
+        &self,
+        entries: &Arc<Entries<K, V>>,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+        key: K,
+        value: F,
+    ) -> anyhow::Result<SubscriptionRef<K, V>>
+    where
+        F: FnOnce() -> V,
+    {
+        let now = self.0.config.clock.now();
+
+        if !map.contains_key(&key) {
+            // rejected values must not evict anything
+            let value = value();
+            self.check_value_size(&key, &value)?;
+            self.make_room(map, &key)?;
+
+            let entry = self.new_entry(&key, value, now);
+            map.insert(key.clone(), entry);
+        }
 
         let entry = map
-            .get(key)
-            .with_context(|| format!("unable remove not present key {:?} in {:#?}", key, self))?;
+            .get_mut(&key)
+            .expect("the entry exists or was just inserted");
+        entry.last_used = now;
+
+        Ok(SubscriptionRef::new(
+            key,
+            self.internal(),
+            entries.clone(),
+            entry,
+        ))
+    }
+
+    #[cfg(test)]
+    async fn entries_snapshot(&self) -> BTreeMap<K, SubscriptionEntry<V>> {
+        self.lock_entries(&self.0.entries()).await.clone()
+    }
+
+    /// The time source of this map
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.0.config.clock.clone()
+    }
+
+    fn redacted<'a>(&'a self, value: &'a V) -> Redacted<'a, V> {
+        Redacted {
+            value,
+            redactor: self.0.config.redactor.as_deref(),
+        }
+    }
+
+    /// Format the key through the key redactor, see
+    /// [`key_redactor`](SubscriptionMapBuilder::key_redactor)
+    fn redacted_key<'a>(&'a self, key: &'a K) -> Redacted<'a, K> {
+        Redacted {
+            value: key,
+            redactor: self.0.config.key_redactor.as_deref(),
+        }
+    }
+
+    fn debug_entries<'a>(
+        &'a self,
+        entries: &'a BTreeMap<K, SubscriptionEntry<V>>,
+    ) -> DebugEntries<'a, K, V> {
+        DebugEntries { map: self, entries }
+    }
+
+    /// Account for a removed entry and invalidate the entries derived from it
+    fn record_removal(
+        &self,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+        key: &K,
+        entry: &SubscriptionEntry<V>,
+    ) {
+        entry.meta.detached.store(true, Ordering::SeqCst);
+        self.record_lifetime(key, entry);
+        #[cfg(feature = "latency")]
+        self.forget_publish(key);
+        self.invalidate_dependents(map, key);
+    }
+
+    /// Create the entry of a key which is inserted into the map
+    fn new_entry(&self, key: &K, value: V, now: Instant) -> SubscriptionEntry<V> {
+        self.emit(MapEvent::Inserted(key.clone()));
+
+        let weight = self.weigh(key, &value);
+        #[allow(unused_mut)]
+        let mut entry = SubscriptionEntry::new(value, now, self.next_sequence(), weight);
+        #[cfg(feature = "stats")]
+        {
+            let prefix = self.0.config.prefix_of(key);
+            entry.churn = Some(ChurnOrigin::created(&self.0.stats, prefix));
+        }
+
+        entry
+    }
+
+    fn record_lifetime(
+        &self,
+        key: &K,
+        #[cfg_attr(not(feature = "stats"), allow(unused_variables))] entry: &SubscriptionEntry<V>,
+    ) {
+        // accounted to the map the entry was created in, it may have been swapped in since
+        #[cfg(feature = "stats")]
+        if let Some(churn) = &entry.churn {
+            let lifetime = self
+                .0
+                .config
+                .clock
+                .now()
+                .saturating_duration_since(entry.created_at);
+
+            churn.removed(lifetime);
+        }
+
+        self.emit(MapEvent::Removed(key.clone()));
+    }
+
+    /// Give up a reference to the entry and clean it up if it is no longer needed.
+    ///
+    /// Only the last ref and tagged refs have to look at the entry, all others just decrement the
+    /// count. If the entries are locked at the time, the release is deferred to whoever locks
+    /// them next or to [`maintain`](Self::maintain), so dropping a ref never blocks.
+    fn release(&self, mut released: Released<K, V>) {
+        // the entries are freed together with the last ref of a closed map
+        if self.is_closed() {
+            return;
+        }
+
+        #[cfg(feature = "fault-injection")]
+        match self.inject_fault_now(fault::FaultPoint::BeforeRcDecrement, &released.key) {
+            Ok(None) => {}
+            Ok(Some(delay)) => return self.defer(released, delay),
+            Err(e) => {
+                diag::error!("error occurred while cleanup subscription ref {}", e);
+                return;
+            }
+        }
+
+        if !released.decrement() {
+            return;
+        }
+
+        let entries = released.entries.clone();
+        self.0.released().push(released);
+
+        match entries.try_write_arc() {
+            Some(mut map) => self.collect_released(&entries, &mut map),
+            // the maintenance future finishes the release in case no one else locks the entries
+            None => self.0.deadlines_changed.clone().publish(()),
+        }
+    }
+
+    /// Finish the release once the injected delay passed
+    #[cfg(feature = "fault-injection")]
+    fn defer(&self, mut released: Released<K, V>, delay: std::time::Duration) {
+        released.not_before = Some(self.0.config.clock.now() + delay);
+        self.0.released().push(released);
+        self.0.deadlines_changed.clone().publish(());
+    }
+
+    /// Finish the deferred releases of refs to the locked entries
+    fn collect_released(
+        &self,
+        entries: &Arc<Entries<K, V>>,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+    ) {
+        let now = self.0.config.clock.now();
+
+        let pending: Vec<_> = {
+            let mut released = self.0.released();
+            if released.is_empty() {
+                return;
+            }
+
+            let (pending, others) = std::mem::take(&mut *released).into_iter().partition(
+                |released: &Released<K, V>| {
+                    Arc::ptr_eq(&released.entries, entries)
+                        && released.not_before.is_none_or(|until| until <= now)
+                },
+            );
+            *released = others;
+            pending
+        };
+
+        for released in pending {
+            self.finish_release(map, released);
+        }
+    }
+
+    /// Finish the deferred releases of all entries, including the ones swapped out of the map,
+    /// returns when the next delayed release is due
+    async fn collect_deferred(&self) -> Option<Instant> {
+        let mut entries: Vec<Arc<Entries<K, V>>> = Vec::new();
+
+        for released in self.0.released().iter() {
+            if !entries.iter().any(|e| Arc::ptr_eq(e, &released.entries)) {
+                entries.push(released.entries.clone());
+            }
+        }
+
+        for entries in entries.iter() {
+            drop(self.lock_entries(entries).await);
+        }
+
+        self.0
+            .released()
+            .iter()
+            .filter_map(|released| released.not_before)
+            .min()
+    }
+
+    fn finish_release(
+        &self,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+        mut released: Released<K, V>,
+    ) {
+        if !released.decrement() {
+            return;
+        }
+
+        let entry = match map.get_mut(&released.key) {
+            Some(entry) if Arc::ptr_eq(&entry.rc, &released.rc) => entry,
+            // the unreferenced entry was already cleaned up by someone else
+            _ => {
+                diag::trace!(
+                    "released entry at {:?} is already removed",
+                    self.redacted_key(&released.key)
+                );
+                return;
+            }
+        };
+
+        let now = self.0.config.clock.now();
+
+        if let Some(tag) = released.tag.take() {
+            entry.untag(tag);
+        }
+
+        if entry.rc() == 0 {
+            entry.last_used = now;
+
+            if let Some(keep_alive) = self.tunables().keep_alive {
+                let retention = Retention::after(now, keep_alive);
+                entry.retain_until = entry.retain_until.max(Some(retention));
+
+                // the maintenance future removes the entry once the grace period passes
+                self.0.deadlines_changed.clone().publish(());
+            }
+        }
+
+        if !entry.removable(now) || self.is_excluded_from_cleanup(&released.key) {
+            if entry.rc() == 0 {
+                self.emit(MapEvent::StateChanged(
+                    released.key.clone(),
+                    EntryState::Draining,
+                ));
+            }
+
+            return;
+        }
+
+        // releases are delayed at most once
+        #[cfg(feature = "fault-injection")]
+        if released.not_before.is_none() {
+            match self.inject_fault_now(fault::FaultPoint::BeforeRemoval, &released.key) {
+                Ok(None) => {}
+                Ok(Some(delay)) => return self.defer(released, delay),
+                Err(e) => {
+                    diag::error!("error occurred while cleanup subscription ref {}", e);
+                    return;
+                }
+            }
+        }
+
+        if let Err(e) = self.remove(map, &released.key) {
+            diag::error!("error occurred while cleanup subscription ref {}", e);
+        }
+    }
+
+    fn remove(&self, map: &mut BTreeMap<K, SubscriptionEntry<V>>, key: &K) -> anyhow::Result<()> {
+        let entry = map.get(key).with_context(|| {
+            format!(
+                "unable remove not present key {:?} in {:#?}",
+                self.redacted_key(key),
+                self.debug_entries(map)
+            )
+        })?;
 
         assert!(
-            entry.rc == 0,
+            entry.rc() == 0,
             "invalid removal of referenced subscription at {:?}",
-            key
+            self.redacted_key(key)
         );
 
-        map.remove(key);
+        if !entry.removable(self.0.config.clock.now()) || self.is_excluded_from_cleanup(key) {
+            return Ok(());
+        }
+
+        if let Some(entry) = map.remove(key) {
+            self.record_removal(map, key, &entry);
+        }
+
+        Ok(())
+    }
+
+    /// Store the value and notify all subscribers, even if it equals the current one.
+    ///
+    /// Unlike [`publish_if_changed`](Self::publish_if_changed) this doesn't require comparable
+    /// values and wakes subscribers with every call, e.g. for heartbeat-style signals.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, ()>::default();
+    /// let mut heartbeat = map.get_or_insert("worker", ()).await.unwrap();
+    ///
+    /// map.publish(&"worker", ()).await.unwrap();
+    /// heartbeat.next().await;
+    /// # };
+    /// ```
+    pub async fn publish(&self, key: &K, value: V) -> anyhow::Result<()> {
+        #[cfg(feature = "fault-injection")]
+        self.inject_fault(fault::FaultPoint::BeforePublish, key)
+            .await?;
+
+        let map = self.read_entries().await;
+        let entry = map.get(key).with_context(|| {
+            format!(
+                "unable publish new version of not present key {:?}",
+                self.redacted_key(key)
+            )
+        })?;
+
+        self.publish_value_gate(key, entry.target(), |_, _| true, value)?;
 
         Ok(())
     }
@@ -143,7 +965,7 @@ where
     /// # use async_subscription_map::SubscriptionMap;
     /// # async {
     /// let map = SubscriptionMap::<usize, usize>::default();
-    /// let mut subscription = map.get_or_insert(1, 0).await;
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
     ///
     /// assert_eq!(subscription.latest(), 0);
     /// map.publish_if_changed(&1, 1);
@@ -155,12 +977,19 @@ where
     /// # };
     /// ```
     pub async fn publish_if_changed(&self, key: &K, value: V) -> anyhow::Result<bool> {
-        let mut map = self.0.lock().await;
-        let entry = map
-            .get_mut(key)
-            .with_context(|| format!("unable publish new version of not present key {:?}", key))?;
+        #[cfg(feature = "fault-injection")]
+        self.inject_fault(fault::FaultPoint::BeforePublish, key)
+            .await?;
+
+        let map = self.read_entries().await;
+        let entry = map.get(key).with_context(|| {
+            format!(
+                "unable publish new version of not present key {:?}",
+                self.redacted_key(key)
+            )
+        })?;
 
-        Ok(entry.observable.publish_if_changed(value))
+        self.publish_gate_if_changed(key, None, entry.target(), value)
     }
 
     /// Modify the value contained in the subscription through a mutable reference and notify
@@ -173,7 +1002,7 @@ where
     /// # use async_subscription_map::SubscriptionMap;
     /// # async {
     /// let map = SubscriptionMap::<usize, usize>::default();
-    /// let mut subscription = map.get_or_insert(1, 0).await;
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
     ///
     /// assert_eq!(subscription.latest(), 0);
     /// map.modify_and_publish(&1, |mut v| *v = 1);
@@ -184,14 +1013,23 @@ where
     where
         F: FnOnce(&mut V) -> R,
     {
-        let mut map = self.0.lock().await;
-        let entry = map
-            .get_mut(key)
-            .with_context(|| format!("unable modify not present key {:?}", key))?;
+        #[cfg(feature = "fault-injection")]
+        self.inject_fault(fault::FaultPoint::BeforePublish, key)
+            .await?;
 
-        entry.observable.modify(|v| {
-            modify(v);
-        });
+        let map = self.read_entries().await;
+        let entry = map.get(key).with_context(|| {
+            format!("unable modify not present key {:?}", self.redacted_key(key))
+        })?;
+
+        self.publish_gate(
+            key,
+            entry.target(),
+            |_| true,
+            |v| {
+                modify(v);
+            },
+        )?;
 
         Ok(())
     }
@@ -207,10 +1045,84 @@ where
     }
 }
 
-/// A transparent wrapper for the underlying subscription in the map
-/// which manages the subscription count and removes the observable if no one
-/// holds a subscription to it.
-#[derive(Debug)]
+impl<K, V> Debug for SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_tuple("SubscriptionMap");
+
+        match self.0.entries().try_read_arc() {
+            Some(entries) => debug.field(&self.debug_entries(&entries)),
+            None => debug.field(&format_args!("<locked>")),
+        };
+
+        debug.finish()
+    }
+}
+
+/// Formats the entries of a map through its redactor
+struct DebugEntries<'a, K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    map: &'a SubscriptionMap<K, V>,
+    entries: &'a BTreeMap<K, SubscriptionEntry<V>>,
+}
+
+impl<K, V> Debug for DebugEntries<'_, K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.entries.iter().map(|(key, entry)| {
+                let entry = DebugEntry {
+                    map: self.map,
+                    entry,
+                };
+                (self.map.redacted_key(key), entry)
+            }))
+            .finish()
+    }
+}
+
+struct DebugEntry<'a, K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    map: &'a SubscriptionMap<K, V>,
+    entry: &'a SubscriptionEntry<V>,
+}
+
+impl<K, V> Debug for DebugEntry<'_, K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.entry.observable.latest();
+
+        f.debug_struct("SubscriptionEntry")
+            .field("value", &self.map.redacted(&value))
+            .field("rc", &self.entry.rc())
+            .field("pinned", &self.entry.pinned)
+            .field("tags", &self.entry.tags)
+            .finish()
+    }
+}
+
+/// A subscription to an entry of the map which manages the subscription count
+/// and removes the observable if no one holds a subscription to it.
+///
+/// It mirrors the api of the underlying `Observable`, but publishes go through
+/// the map so they respect its state (e.g. [`SubscriptionMap::freeze`]). They
+/// only touch the subscribed entry and never wait for structural operations of
+/// the map such as insertions or cleanup.
 #[must_use = "entries are removed as soon as no one subscribes to them"]
 pub struct SubscriptionRef<K, V>
 where
@@ -219,7 +1131,13 @@ where
 {
     key: K,
     owner: SubscriptionMap<K, V>,
+    entries: Arc<Entries<K, V>>,
     observable: Observable<V>,
+    invalidation: Observable<()>,
+    removed: Arc<AtomicBool>,
+    rc: Arc<AtomicUsize>,
+    meta: Arc<EntryMeta<V>>,
+    tag: Option<&'static str>,
 }
 
 impl<K, V> SubscriptionRef<K, V>
@@ -227,36 +1145,149 @@ where
     K: Clone + Debug + Eq + Hash + Ord,
     V: Clone + Debug,
 {
-    fn new(key: K, owner: SubscriptionMap<K, V>, entry: &mut SubscriptionEntry<V>) -> Self {
-        entry.rc += 1;
+    fn new(
+        key: K,
+        owner: SubscriptionMap<K, V>,
+        entries: Arc<Entries<K, V>>,
+        entry: &mut SubscriptionEntry<V>,
+    ) -> Self {
+        entry.attached = true;
+
+        if entry.rc.fetch_add(1, Ordering::SeqCst) == 0 {
+            owner.emit(MapEvent::StateChanged(key.clone(), EntryState::Live));
+        }
 
         Self {
             key,
             owner,
+            entries,
             observable: entry.observable.clone(),
+            invalidation: entry.invalidation.clone(),
+            removed: entry.removed.clone(),
+            rc: entry.rc.clone(),
+            meta: entry.meta.clone(),
+            tag: None,
+        }
+    }
+
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// A clone of the latest value, without consuming the change.
+    pub fn latest(&self) -> V {
+        self.observable.latest()
+    }
+
+    /// Wait until a new version is published and return a clone of it.
+    pub async fn next(&mut self) -> V {
+        let value = self.observable.next().await;
+        #[cfg(feature = "latency")]
+        self.owner.record_wakeup(&self.key);
+        value
+    }
+
+    /// Skip any pending updates and return the latest value.
+    pub fn synchronize(&mut self) -> V {
+        self.observable.synchronize()
+    }
+
+    /// Wait until a source of the entry is removed, see [`SubscriptionMap::depends_on`].
+    pub async fn invalidated(&mut self) {
+        self.invalidation.next().await
+    }
+
+    /// Store the provided value and notify all subscribers.
+    pub fn publish(&mut self, value: V) -> anyhow::Result<()> {
+        self.owner
+            .publish_value_gate_as(&self.key, self.tag, self.target(), |_, _| true, value)?;
+
+        Ok(())
+    }
+
+    /// Modify the value through a mutable reference and notify all subscribers.
+    pub fn modify<F>(&mut self, modify: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut V),
+    {
+        self.owner
+            .publish_gate_as(&self.key, self.tag, self.target(), |_| true, modify)?;
+
+        Ok(())
+    }
+
+    /// Publish to the entry of the ref
+    pub(crate) fn target(&self) -> PublishTarget<'_, V> {
+        PublishTarget {
+            observable: self.observable.clone(),
+            meta: &self.meta,
+            detached: self.meta.is_detached() || !self.owner.0.is_current(&self.entries),
+        }
+    }
+
+    /// Create another ref to the same entry which continues at the same version and carries the
+    /// same tag, unless the entry was forcibly removed
+    async fn fork(&self) -> Self {
+        // this ref keeps the entry alive, so only the tags require the entries to be locked
+        self.rc.fetch_add(1, Ordering::SeqCst);
+
+        let tag = match self.tag {
+            Some(tag) => {
+                let mut map = self.entries.write().await;
+
+                match map.get_mut(&self.key) {
+                    Some(entry) if Arc::ptr_eq(&entry.rc, &self.rc) => {
+                        entry.tags.push(tag);
+                        Some(tag)
+                    }
+                    // the entry was forcibly removed, possibly replaced by an unrelated one, and
+                    // no longer tracks the tags of its refs
+                    _ => None,
+                }
+            }
+            None => None,
+        };
+
+        Self {
+            key: self.key.clone(),
+            owner: self.owner.internal(),
+            entries: self.entries.clone(),
+            observable: self.observable.clone(),
+            invalidation: self.invalidation.clone(),
+            removed: self.removed.clone(),
+            rc: self.rc.clone(),
+            meta: self.meta.clone(),
+            tag,
         }
     }
 }
 
-impl<K, V> Deref for SubscriptionRef<K, V>
+impl<K, V> SubscriptionRef<K, V>
 where
     K: Clone + Debug + Eq + Hash + Ord,
-    V: Clone + Debug,
+    V: Clone + Debug + Eq,
 {
-    type Target = Observable<V>;
-
-    fn deref(&self) -> &Self::Target {
-        &self.observable
+    /// Publish the value if it differs from the current one, returns if a publish was made.
+    pub fn publish_if_changed(&mut self, value: V) -> anyhow::Result<bool> {
+        self.owner
+            .publish_gate_if_changed(&self.key, self.tag, self.target(), value)
     }
 }
 
-impl<K, V> DerefMut for SubscriptionRef<K, V>
+impl<K, V> Debug for SubscriptionRef<K, V>
 where
     K: Clone + Debug + Eq + Hash + Ord,
     V: Clone + Debug,
 {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.observable
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.observable.latest();
+
+        f.debug_struct("SubscriptionRef")
+            .field("key", &self.owner.redacted_key(&self.key))
+            .field("value", &self.owner.redacted(&value))
+            .field("tag", &self.tag)
+            .finish()
     }
 }
 
@@ -266,27 +1297,19 @@ where
     V: Clone + Debug,
 {
     fn drop(&mut self) {
-        log::trace!("drop for subscription ref for key {:?}", self.key);
-
-        let mut map = block_on(self.owner.0.lock());
-        let mut entry = match map.get_mut(&self.key) {
-            Some(entry) => entry,
-            None => {
-                log::error!("could not obtain rc in subscription map {:#?}", map.deref());
-                return;
-            }
-        };
-
-        entry.rc -= 1;
-
-        if entry.rc == 0 {
-            drop(map);
-            let res = block_on(self.owner.remove(&self.key));
+        diag::trace!(
+            "drop for subscription ref for key {:?}",
+            self.owner.redacted_key(&self.key)
+        );
 
-            if let Err(e) = res {
-                log::error!("error occurred while cleanup subscription ref {}", e);
-            }
-        }
+        self.owner.release(Released {
+            entries: self.entries.clone(),
+            key: self.key.clone(),
+            rc: self.rc.clone(),
+            tag: self.tag,
+            decrement: true,
+            not_before: None,
+        });
     }
 }
 
@@ -296,13 +1319,13 @@ mod test {
 
     macro_rules! assert_map_len {
         ($map:ident, $len:expr) => {
-            assert_eq!($map.snapshot().await.len(), $len);
+            assert_eq!($map.entries_snapshot().await.len(), $len);
         };
     }
 
     macro_rules! assert_ref_count {
         ($map:ident, $key:expr, $rc:expr) => {
-            assert_eq!($map.snapshot().await.get($key).unwrap().rc, $rc);
+            assert_eq!($map.entries_snapshot().await.get($key).unwrap().rc(), $rc);
         };
     }
 
@@ -311,10 +1334,10 @@ mod test {
         let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
         assert_map_len!(map, 0);
 
-        let _ = map.get_or_insert(1, 1).await;
+        let _ = map.get_or_insert(1, 1).await.unwrap();
         assert_map_len!(map, 0);
 
-        let _ = map.get_or_insert(2, 2).await;
+        let _ = map.get_or_insert(2, 2).await.unwrap();
         assert_map_len!(map, 0);
     }
 
@@ -323,21 +1346,21 @@ mod test {
         let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
         assert_map_len!(map, 0);
 
-        let ref_one = map.get_or_insert(1, 1).await;
+        let ref_one = map.get_or_insert(1, 1).await.unwrap();
         assert_map_len!(map, 1);
 
-        let ref_two = map.get_or_insert(2, 2).await;
+        let ref_two = map.get_or_insert(2, 2).await.unwrap();
         assert_map_len!(map, 2);
 
         drop(ref_one);
         assert_map_len!(map, 1);
-        assert!(map.snapshot().await.get(&1).is_none());
-        assert!(map.snapshot().await.get(&2).is_some());
+        assert!(!map.entries_snapshot().await.contains_key(&1));
+        assert!(map.entries_snapshot().await.contains_key(&2));
 
         drop(ref_two);
         assert_map_len!(map, 0);
-        assert!(map.snapshot().await.get(&1).is_none());
-        assert!(map.snapshot().await.get(&2).is_none());
+        assert!(!map.entries_snapshot().await.contains_key(&1));
+        assert!(!map.entries_snapshot().await.contains_key(&2));
     }
 
     #[async_std::test]
@@ -345,10 +1368,10 @@ mod test {
         let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
         assert_map_len!(map, 0);
 
-        let ref_one = map.get_or_insert(1, 1).await;
+        let ref_one = map.get_or_insert(1, 1).await.unwrap();
         assert_ref_count!(map, &1, 1);
 
-        let ref_two = map.get_or_insert(1, 1).await;
+        let ref_two = map.get_or_insert(1, 1).await.unwrap();
         assert_ref_count!(map, &1, 2);
 
         drop(ref_one);
@@ -358,15 +1381,114 @@ mod test {
         assert_map_len!(map, 0);
     }
 
+    #[async_std::test]
+    async fn should_only_get_present_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        assert!(map.get(&1).await.is_none());
+        assert_map_len!(map, 0);
+
+        let producer = map.get_or_insert(1, 1).await.unwrap();
+        let consumer = map.get(&1).await.unwrap();
+        assert_ref_count!(map, &1, 2);
+
+        drop(producer);
+        assert_eq!(consumer.latest(), 1);
+        drop(consumer);
+        assert_map_len!(map, 0);
+    }
+
+    #[async_std::test]
+    async fn should_publish_through_ref_without_locking_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        let entries = map.0.entries().write_arc().await;
+        subscription.publish(1).unwrap();
+        assert!(subscription.publish_if_changed(2).unwrap());
+        subscription.modify(|v| *v += 1).unwrap();
+        drop(entries);
+
+        assert_eq!(subscription.synchronize(), 3);
+    }
+
+    #[async_std::test]
+    async fn should_publish_through_map_with_shared_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        let entries = map.0.entries().read_arc().await;
+        assert!(map.publish_if_changed(&1, 1).await.unwrap());
+        map.modify_and_publish(&1, |v| *v += 1).await.unwrap();
+        assert_eq!(map.peek(&1).await, Some(2));
+        drop(entries);
+
+        assert_eq!(subscription.synchronize(), 2);
+    }
+
+    #[async_std::test]
+    async fn should_notify_on_every_unconditional_publish() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.publish(&1, 0).await.unwrap();
+        assert_eq!(subscription.next().await, 0);
+        map.publish(&1, 0).await.unwrap();
+        assert_eq!(subscription.next().await, 0);
+
+        assert!(map.publish(&2, 0).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn should_drop_shared_ref_without_locking_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let first = map.get_or_insert(1, 0).await.unwrap();
+        let second = map.get_or_insert(1, 0).await.unwrap();
+
+        let entries = map.0.entries().write_arc().await;
+        drop(first);
+        drop(entries);
+        assert_ref_count!(map, &1, 1);
+
+        drop(second);
+        assert_map_len!(map, 0);
+    }
+
+    #[async_std::test]
+    async fn should_defer_release_while_entries_are_locked() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        let entries = map.0.entries().write_arc().await;
+        drop(subscription);
+        assert!(entries.contains_key(&1));
+        drop(entries);
+
+        assert_map_len!(map, 0);
+    }
+
+    #[test]
+    fn should_run_on_any_executor() {
+        futures::executor::block_on(async {
+            let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+            let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+            subscription.publish(1).unwrap();
+            assert_eq!(subscription.synchronize(), 1);
+            drop(subscription);
+            assert_map_len!(map, 0);
+        });
+    }
+
     #[async_std::test]
     #[should_panic]
     async fn shouldnt_remove_if_rc_is_not_zero() {
         let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
         assert_map_len!(map, 0);
 
-        let _ref = map.get_or_insert(1, 1).await;
+        let _ref = map.get_or_insert(1, 1).await.unwrap();
         assert_ref_count!(map, &1, 1);
 
-        map.remove(&1).await.unwrap();
+        let mut entries = map.0.entries().write_arc().await;
+        map.remove(&mut entries, &1).unwrap();
     }
 }
@@ -0,0 +1,122 @@
+use crate::{MapEvents, SubscriptionMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// The stage of an entry in its lifecycle, see [`SubscriptionMap::entry_state`].
+///
+/// Entries start out as [`Creating`](Self::Creating) or, if they are inserted by a subscriber,
+/// become [`Live`](Self::Live) right away. They alternate between live and
+/// [`Draining`](Self::Draining) as refs come and go until they are [`Removed`](Self::Removed).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntryState {
+    /// The entry exists but was never subscribed to, e.g. after [`pin`](SubscriptionMap::pin),
+    /// [`seed`](SubscriptionMap::seed) or [`warm`](SubscriptionMap::warm)
+    Creating,
+    /// At least one ref subscribes to the entry
+    Live,
+    /// All refs were dropped, the entry is kept until it is cleaned up because it is pinned,
+    /// retained, excluded from cleanup or its release is deferred
+    Draining,
+    /// There is no entry at the key
+    Removed,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The lifecycle stage of the entry at the key.
+    ///
+    /// Orchestration on top of the map, e.g. backfilling entries before they are subscribed to,
+    /// can coordinate on these states instead of inferring them from subscription counts.
+    ///
+    /// ```
+    /// # use async_subscription_map::{EntryState, SubscriptionMap};
+    /// # use std::time::Duration;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::builder()
+    ///     .keep_alive(Duration::from_secs(60))
+    ///     .build();
+    /// map.warm([(1, 0)], Duration::from_secs(60)).await.unwrap();
+    /// assert_eq!(map.entry_state(&1).await, EntryState::Creating);
+    ///
+    /// let subscription = map.get_or_insert(1, 0).await.unwrap();
+    /// assert_eq!(map.entry_state(&1).await, EntryState::Live);
+    ///
+    /// drop(subscription);
+    /// assert_eq!(map.entry_state(&1).await, EntryState::Draining);
+    /// assert_eq!(map.entry_state(&2).await, EntryState::Removed);
+    /// # };
+    /// ```
+    pub async fn entry_state(&self, key: &K) -> EntryState {
+        match self.read_entries().await.get(key) {
+            None => EntryState::Removed,
+            Some(entry) if entry.rc() > 0 => EntryState::Live,
+            Some(entry) if !entry.attached => EntryState::Creating,
+            Some(_) => EntryState::Draining,
+        }
+    }
+
+    /// Same as [`events`](Self::events), additionally reporting the lifecycle transitions of
+    /// entries through [`MapEvent::StateChanged`](crate::MapEvent::StateChanged).
+    ///
+    /// Insertions imply [`EntryState::Creating`] and removals [`EntryState::Removed`], so only
+    /// the transitions in between are reported as state changes. A transition to live may be
+    /// reported twice in a row if a new subscriber races with a deferred release.
+    ///
+    /// ```
+    /// # use async_subscription_map::{EntryState, MapEvent, SubscriptionMap};
+    /// # use futures::StreamExt;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut events = map.state_events();
+    ///
+    /// map.pin(1, 0).await.unwrap();
+    /// drop(map.get_or_insert(1, 0).await.unwrap());
+    ///
+    /// assert_eq!(events.next().await, Some(MapEvent::Inserted(1)));
+    /// assert_eq!(events.next().await, Some(MapEvent::StateChanged(1, EntryState::Live)));
+    /// assert_eq!(events.next().await, Some(MapEvent::StateChanged(1, EntryState::Draining)));
+    /// # };
+    /// ```
+    pub fn state_events(&self) -> MapEvents<K> {
+        MapEvents::new(self.subscribe_events(None, true))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::EntryState;
+    use crate::{MapEvent, SubscriptionMap};
+    use futures::StreamExt;
+
+    #[async_std::test]
+    async fn should_report_state_transitions() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut events = map.state_events();
+        let mut plain = map.events();
+
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let other = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(map.entry_state(&1).await, EntryState::Live);
+        drop((subscription, other));
+        assert_eq!(map.entry_state(&1).await, EntryState::Removed);
+
+        map.pin(2, 0).await.unwrap();
+        drop(map.get_or_insert(2, 0).await.unwrap());
+        assert_eq!(map.entry_state(&2).await, EntryState::Draining);
+
+        let expected = vec![
+            MapEvent::Inserted(2),
+            MapEvent::StateChanged(2, EntryState::Live),
+            MapEvent::StateChanged(2, EntryState::Draining),
+        ];
+        let received: Vec<_> = (&mut events).take(3).collect().await;
+        assert_eq!(received, expected);
+
+        // plain event streams don't receive state changes
+        assert_eq!(plain.next().await, Some(MapEvent::Inserted(2)));
+        assert!(futures::FutureExt::now_or_never(plain.next()).is_none());
+    }
+}
@@ -0,0 +1,128 @@
+use crate::SubscriptionRef;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+type Projection<V, T> = Box<dyn Fn(&V) -> T + Send + Sync>;
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Derive a subscription which yields the projection of every value instead of the value.
+    ///
+    /// This exposes only a part of a large state to consumers. The projection is applied on
+    /// every delivery, the returned ref keeps the entry alive like this one.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, (String, u32)>::default();
+    /// let mut producer = map.get_or_insert("user", ("ada".into(), 36)).await.unwrap();
+    ///
+    /// let subscription = map.get_or_insert("user", Default::default()).await.unwrap();
+    /// let mut age = subscription.map(|(_, age)| *age);
+    ///
+    /// producer.publish(("ada".into(), 37)).unwrap();
+    /// assert_eq!(age.next().await, 37);
+    /// # };
+    /// ```
+    pub fn map<T, F>(self, project: F) -> MappedRef<K, V, T>
+    where
+        F: Fn(&V) -> T + Send + Sync + 'static,
+    {
+        MappedRef {
+            subscription: self,
+            project: Box::new(project),
+        }
+    }
+}
+
+/// A subscription which yields a projection of the values, see [`SubscriptionRef::map`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct MappedRef<K, V, T>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscription: SubscriptionRef<K, V>,
+    project: Projection<V, T>,
+}
+
+impl<K, V, T> MappedRef<K, V, T>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// The projection of the latest value, without consuming the change.
+    pub fn latest(&self) -> T {
+        (self.project)(&self.subscription.latest())
+    }
+
+    /// Wait until a new version is published and return its projection.
+    pub async fn next(&mut self) -> T {
+        let value = self.subscription.next().await;
+        (self.project)(&value)
+    }
+
+    /// Skip any pending updates and return the projection of the latest value.
+    pub fn synchronize(&mut self) -> T {
+        (self.project)(&self.subscription.synchronize())
+    }
+
+    /// Project the projected values once more
+    pub fn map<U, F>(self, project: F) -> MappedRef<K, V, U>
+    where
+        V: 'static,
+        T: 'static,
+        F: Fn(&T) -> U + Send + Sync + 'static,
+    {
+        let inner = self.project;
+
+        MappedRef {
+            subscription: self.subscription,
+            project: Box::new(move |value| project(&inner(value))),
+        }
+    }
+}
+
+impl<K, V, T> Debug for MappedRef<K, V, T>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedRef")
+            .field("subscription", &self.subscription)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_yield_projections() {
+        let map: SubscriptionMap<usize, Vec<usize>> = SubscriptionMap::new();
+        let mut producer = map.get_or_insert(1, vec![1, 2]).await.unwrap();
+        let subscription = map.get_or_insert(1, Vec::new()).await.unwrap();
+
+        let mut len = subscription.map(Vec::len).map(|len| len * 10);
+        assert_eq!(len.latest(), 20);
+
+        producer.publish(vec![1, 2, 3]).unwrap();
+        assert_eq!(len.next().await, 30);
+
+        producer.publish(Vec::new()).unwrap();
+        assert_eq!(len.synchronize(), 0);
+
+        drop((producer, len));
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+}
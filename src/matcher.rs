@@ -0,0 +1,136 @@
+use crate::KeyPrefix;
+use std::fmt::{self, Debug};
+
+/// Selects the keys a [matching subscription](crate::SubscriptionMap::subscribe_matching)
+/// observes.
+pub trait KeyMatcher<K> {
+    /// Check if the key is selected
+    fn matches(&self, key: &K) -> bool;
+}
+
+/// Selects every key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllKeys;
+
+impl<K> KeyMatcher<K> for AllKeys {
+    fn matches(&self, _: &K) -> bool {
+        true
+    }
+}
+
+/// Selects a single key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exact<K>(pub K);
+
+impl<K> KeyMatcher<K> for Exact<K>
+where
+    K: PartialEq,
+{
+    fn matches(&self, key: &K) -> bool {
+        *key == self.0
+    }
+}
+
+/// Selects all keys which start with the prefix, see [`KeyPrefix`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Prefix<K>(pub K);
+
+impl<K> KeyMatcher<K> for Prefix<K>
+where
+    K: KeyPrefix,
+{
+    fn matches(&self, key: &K) -> bool {
+        key.starts_with(&self.0)
+    }
+}
+
+/// Selects string keys through a glob pattern such as `"orders/*/status"`.
+///
+/// `*` matches any characters within a segment, `**` also matches across segments and `?`
+/// matches a single character, segments are separated by `/`. All other characters match
+/// themselves.
+///
+/// ```
+/// # use async_subscription_map::{Glob, KeyMatcher};
+/// let status = Glob::new("orders/*/status");
+///
+/// assert!(status.matches(&"orders/42/status"));
+/// assert!(!status.matches(&"orders/42/items/1/status"));
+/// assert!(Glob::new("orders/**/status").matches(&"orders/42/items/1/status"));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Glob {
+    pattern: Vec<char>,
+}
+
+impl Glob {
+    /// Parse the pattern, every string is a valid pattern
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.chars().collect(),
+        }
+    }
+}
+
+impl<K> KeyMatcher<K> for Glob
+where
+    K: AsRef<str>,
+{
+    fn matches(&self, key: &K) -> bool {
+        let key: Vec<char> = key.as_ref().chars().collect();
+        glob(&self.pattern, &key)
+    }
+}
+
+impl Debug for Glob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Glob")
+            .field(&self.pattern.iter().collect::<String>())
+            .finish()
+    }
+}
+
+fn glob(pattern: &[char], key: &[char]) -> bool {
+    match pattern {
+        [] => key.is_empty(),
+        ['*', '*', rest @ ..] => (0..=key.len()).any(|i| glob(rest, &key[i..])),
+        ['*', rest @ ..] => (0..=key.len())
+            .take_while(|&i| i == 0 || key[i - 1] != '/')
+            .any(|i| glob(rest, &key[i..])),
+        ['?', rest @ ..] => matches!(key.first(), Some(c) if *c != '/') && glob(rest, &key[1..]),
+        [c, rest @ ..] => key.first() == Some(c) && glob(rest, &key[1..]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Exact, Glob, KeyMatcher, Prefix};
+
+    #[test]
+    fn should_match_glob_patterns() {
+        let cases = [
+            ("orders/*/status", "orders/1/status", true),
+            ("orders/*/status", "orders//status", true),
+            ("orders/*/status", "orders/1/2/status", false),
+            ("orders/**", "orders/1/2/status", true),
+            ("orders/**/status", "orders/status", false),
+            ("orders/?", "orders/1", true),
+            ("orders/?", "orders/12", false),
+            ("*", "", true),
+            ("a*b*c", "aXbYc", true),
+            ("a*b*c", "aXbY", false),
+        ];
+
+        for (pattern, key, expected) in cases {
+            assert_eq!(
+                Glob::new(pattern).matches(&key),
+                expected,
+                "{pattern} {key}"
+            );
+        }
+
+        assert!(Exact(1).matches(&1));
+        assert!(Prefix("a/").matches(&"a/1"));
+        assert!(!Prefix("a/").matches(&"b/1"));
+    }
+}
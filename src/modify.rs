@@ -0,0 +1,177 @@
+#[cfg(feature = "fault-injection")]
+use crate::fault;
+use crate::gate::PublishTarget;
+use crate::{Priority, SubscriptionMap};
+use anyhow::Context;
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Compute the new value of the entry from its latest one through an async closure and
+    /// publish it, e.g. if computing it requires I/O.
+    ///
+    /// The closure runs under a lock of the entry alone, the map stays unlocked while it is
+    /// awaited. Concurrent calls for the same key wait for each other, so every closure sees the
+    /// value published by the previous one. Other publishes to the key don't take the lock and
+    /// are overwritten if they happen while the closure runs, use
+    /// [`compare_and_publish`](Self::compare_and_publish) for optimistic updates instead.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, u64>::default();
+    /// let mut subscription = map.get_or_insert("balance", 100).await.unwrap();
+    ///
+    /// map.modify_async(&"balance", |balance| async move {
+    ///     // e.g. ask a remote ledger for the pending transactions
+    ///     balance - 25
+    /// })
+    /// .await
+    /// .unwrap();
+    ///
+    /// assert_eq!(subscription.next().await, 75);
+    /// # };
+    /// ```
+    pub async fn modify_async<F, Fut>(&self, key: &K, modify: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(V) -> Fut,
+        Fut: Future<Output = V>,
+    {
+        self.modify_async_with_priority(key, Priority::Normal, modify)
+            .await
+    }
+
+    /// Like [`modify_async`](Self::modify_async), but waiting for the lock of the entry with a
+    /// priority.
+    ///
+    /// Once the closure of the current holder finished the lock is handed to the waiting call
+    /// with the highest priority, so e.g. an urgent correction isn't starved behind a queue of
+    /// background refreshes. Calls of the same priority are served in order.
+    ///
+    /// ```
+    /// # use async_subscription_map::{Priority, SubscriptionMap};
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, u64>::default();
+    /// let mut subscription = map.get_or_insert("price", 100).await.unwrap();
+    ///
+    /// map.modify_async_with_priority(&"price", Priority::High, |_| async move { 95 })
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// assert_eq!(subscription.next().await, 95);
+    /// # };
+    /// ```
+    pub async fn modify_async_with_priority<F, Fut>(
+        &self,
+        key: &K,
+        priority: Priority,
+        modify: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnOnce(V) -> Fut,
+        Fut: Future<Output = V>,
+    {
+        #[cfg(feature = "fault-injection")]
+        self.inject_fault(fault::FaultPoint::BeforePublish, key)
+            .await?;
+
+        let entries = self.0.entries();
+        let (observable, meta, writer) = {
+            let map = entries.read_arc().await;
+            let entry = map.get(key).with_context(|| {
+                format!("unable modify not present key {:?}", self.redacted_key(key))
+            })?;
+
+            (
+                entry.observable.clone(),
+                entry.meta.clone(),
+                entry.writer.clone(),
+            )
+        };
+
+        let _writer = writer.lock(priority).await;
+        let value = modify(observable.latest()).await;
+        // the entry may have been removed or swapped out while the closure ran
+        let target = PublishTarget {
+            observable,
+            meta: &meta,
+            detached: meta.is_detached() || !self.0.is_current(&entries),
+        };
+        self.publish_value_gate(key, target, |_, _| true, value)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Priority, SubscriptionMap};
+    use futures::channel::oneshot;
+    use futures::FutureExt;
+
+    #[async_std::test]
+    async fn should_serialize_async_modifications() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let (release, released) = oneshot::channel::<()>();
+
+        let first = map.modify_async(&1, |v| async move {
+            released.await.ok();
+            v + 1
+        });
+        let second = map.modify_async(&1, |v| async move { v * 10 });
+        futures::pin_mut!(first, second);
+
+        assert!(first.as_mut().now_or_never().is_none());
+        assert!(second.as_mut().now_or_never().is_none());
+
+        // the map itself stays usable while the first closure is awaited
+        assert!(map.publish_if_changed(&1, 5).await.unwrap());
+        assert_eq!(map.peek(&1).await, Some(5));
+
+        release.send(()).unwrap();
+        first.await.unwrap();
+        second.await.unwrap();
+        assert_eq!(subscription.latest(), 10);
+    }
+
+    #[async_std::test]
+    async fn should_serve_high_priority_modifications_first() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let (release, released) = oneshot::channel::<()>();
+
+        let first = map.modify_async(&1, |v| async move {
+            released.await.ok();
+            v + 1
+        });
+        let low = map.modify_async_with_priority(&1, Priority::Low, |v| async move { v + 10 });
+        let high = map.modify_async_with_priority(&1, Priority::High, |v| async move { v * 100 });
+        futures::pin_mut!(first, low, high);
+
+        assert!(first.as_mut().now_or_never().is_none());
+        assert!(low.as_mut().now_or_never().is_none());
+        assert!(high.as_mut().now_or_never().is_none());
+
+        release.send(()).unwrap();
+        first.await.unwrap();
+        assert!(low.as_mut().now_or_never().is_none());
+
+        high.await.unwrap();
+        assert_eq!(subscription.latest(), 100);
+
+        low.await.unwrap();
+        assert_eq!(subscription.latest(), 110);
+    }
+
+    #[async_std::test]
+    async fn should_fail_for_missing_keys() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        assert!(map.modify_async(&1, |v| async move { v }).await.is_err());
+    }
+}
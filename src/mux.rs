@@ -0,0 +1,205 @@
+use crate::{SubscriptionMap, SubscriptionRef};
+use futures::future;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+/// Fans a single consumer across many subscriptions, each identified by a correlation id.
+///
+/// Subscriptions are added and completed while the mux is in use, completing a correlation
+/// releases its ref. This is the building block for protocol gateways which serve many
+/// subscriptions of their clients from a single task.
+///
+/// ```
+/// # use async_subscription_map::{Mux, SubscriptionMap};
+/// # async {
+/// let map = SubscriptionMap::<&'static str, usize>::default();
+/// let mut mux = Mux::new();
+///
+/// mux.insert(1, map.get_or_insert("temperature", 20).await.unwrap());
+/// mux.insert(2, map.get_or_insert("humidity", 50).await.unwrap());
+///
+/// map.publish_if_changed(&"humidity", 55).await.unwrap();
+/// assert_eq!(mux.next().await, Some((2, 55)));
+///
+/// // the client unsubscribed, which releases the entry
+/// mux.complete(&2);
+/// assert_eq!(map.peek(&"humidity").await, None);
+/// # };
+/// ```
+pub struct Mux<C, K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscriptions: BTreeMap<C, SubscriptionRef<K, V>>,
+}
+
+impl<C, K, V> Mux<C, K, V>
+where
+    C: Clone + Ord,
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Create a mux without subscriptions
+    pub fn new() -> Self {
+        Self {
+            subscriptions: BTreeMap::new(),
+        }
+    }
+
+    /// Add the subscription under the correlation id, returns the subscription it replaces.
+    pub fn insert(
+        &mut self,
+        correlation: C,
+        subscription: SubscriptionRef<K, V>,
+    ) -> Option<SubscriptionRef<K, V>> {
+        self.subscriptions.insert(correlation, subscription)
+    }
+
+    /// Complete the correlation and release its subscription, returns if it existed.
+    pub fn complete(&mut self, correlation: &C) -> bool {
+        self.subscriptions.remove(correlation).is_some()
+    }
+
+    /// Check if the correlation is in progress
+    pub fn contains(&self, correlation: &C) -> bool {
+        self.subscriptions.contains_key(correlation)
+    }
+
+    /// The amount of correlations in progress
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    /// Check if no correlation is in progress
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Wait until any of the subscriptions is published and return its correlation id together
+    /// with the new value, `None` if there are no subscriptions.
+    ///
+    /// Every call waits on all subscriptions, so its cost grows with their amount.
+    pub async fn next(&mut self) -> Option<(C, V)> {
+        if self.subscriptions.is_empty() {
+            return None;
+        }
+
+        let pending = self
+            .subscriptions
+            .iter_mut()
+            .map(|(correlation, subscription)| {
+                Box::pin(async move { (correlation.clone(), subscription.next().await) })
+            });
+
+        let (item, _, _) = future::select_all(pending).await;
+        Some(item)
+    }
+}
+
+impl<C, K, V> Default for Mux<C, K, V>
+where
+    C: Clone + Ord,
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, K, V> Debug for Mux<C, K, V>
+where
+    C: Debug,
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.subscriptions.iter()).finish()
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Default,
+{
+    /// Subscribe to all keys through a single mux which yields a key together with its value as
+    /// soon as it is published.
+    ///
+    /// The keys serve as correlation ids, entries which don't exist yet are created with the
+    /// default value. Completing a key releases its subscription, see [`Mux::complete`].
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let mut selected = map.select(["a", "b", "c"]).await.unwrap();
+    ///
+    /// map.publish_if_changed(&"b", 1).await.unwrap();
+    /// assert_eq!(selected.next().await, Some(("b", 1)));
+    /// # };
+    /// ```
+    pub async fn select<I>(&self, keys: I) -> anyhow::Result<Mux<K, K, V>>
+    where
+        I: IntoIterator<Item = K>,
+    {
+        let mut mux = Mux::new();
+
+        for key in keys {
+            let subscription = self.get_or_insert(key.clone(), V::default()).await?;
+            mux.insert(key, subscription);
+        }
+
+        Ok(mux)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Mux;
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_yield_values_by_correlation() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut mux = Mux::new();
+        assert_eq!(mux.next().await, None);
+
+        mux.insert("a", map.get_or_insert(1, 0).await.unwrap());
+        mux.insert("b", map.get_or_insert(1, 0).await.unwrap());
+        mux.insert("c", map.get_or_insert(2, 0).await.unwrap());
+
+        map.publish_if_changed(&2, 1).await.unwrap();
+        assert_eq!(mux.next().await, Some(("c", 1)));
+
+        map.publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(mux.next().await, Some(("a", 1)));
+        assert_eq!(mux.next().await, Some(("b", 1)));
+
+        assert!(mux.complete(&"a"));
+        assert!(!mux.complete(&"a"));
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 1);
+
+        mux.complete(&"b");
+        assert!(!map.entries_snapshot().await.contains_key(&1));
+        assert_eq!(mux.len(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_select_keys() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        map.pin(2, 5).await.unwrap();
+
+        let mut selected = map.select([1, 2, 2]).await.unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(map.entries_snapshot().await[&2].rc(), 1);
+
+        map.publish_if_changed(&2, 6).await.unwrap();
+        assert_eq!(selected.next().await, Some((2, 6)));
+
+        drop(selected);
+        assert_eq!(map.keys().await, vec![2]);
+    }
+}
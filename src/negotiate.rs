@@ -0,0 +1,143 @@
+use crate::{Error, SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+
+/// How a subscriber wants to initialize an entry, see [`SubscriptionMap::negotiate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SubscribeMode<V> {
+    /// Only subscribe to an existing entry
+    UseExisting,
+    /// Initialize the entry with the value unless it already exists
+    ProposeInitial(V),
+}
+
+/// The outcome of a [`negotiate`](SubscriptionMap::negotiate) call.
+#[derive(Debug)]
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub enum Negotiated<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The proposed value initialized the entry
+    Inserted(SubscriptionRef<K, V>),
+    /// The entry already existed, a proposed value was discarded
+    Existing(SubscriptionRef<K, V>),
+    /// The entry doesn't exist and no value was proposed
+    Absent,
+}
+
+impl<K, V> Negotiated<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The subscription, unless the entry is absent
+    pub fn into_subscription(self) -> Option<SubscriptionRef<K, V>> {
+        match self {
+            Negotiated::Inserted(subscription) | Negotiated::Existing(subscription) => {
+                Some(subscription)
+            }
+            Negotiated::Absent => None,
+        }
+    }
+
+    /// Check if the proposed value initialized the entry
+    pub fn is_inserted(&self) -> bool {
+        matches!(self, Negotiated::Inserted(_))
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Subscribe to the key and report which value the entry was initialized with.
+    ///
+    /// The first subscriber to insert the key wins, everyone racing with it observes its value.
+    /// Callers which proposed an initial value learn that they lost the race through
+    /// [`Negotiated::Existing`], e.g. to reconcile their value with the one in the map.
+    ///
+    /// Fails with [`Error::Draining`] once the map is [draining](Self::drain).
+    ///
+    /// ```
+    /// # use async_subscription_map::{Negotiated, SubscribeMode, SubscriptionMap};
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    ///
+    /// let first = map.negotiate(1, SubscribeMode::ProposeInitial(5)).await.unwrap();
+    /// assert!(first.is_inserted());
+    ///
+    /// match map.negotiate(1, SubscribeMode::ProposeInitial(7)).await.unwrap() {
+    ///     Negotiated::Existing(subscription) => assert_eq!(subscription.latest(), 5),
+    ///     _ => unreachable!(),
+    /// }
+    /// # };
+    /// ```
+    pub async fn negotiate(
+        &self,
+        key: K,
+        mode: SubscribeMode<V>,
+    ) -> anyhow::Result<Negotiated<K, V>> {
+        let entries = self.0.entries();
+        let mut map = self.lock_entries(&entries).await;
+
+        if self.0.draining.load(Ordering::SeqCst) {
+            return Err(Error::Draining)
+                .with_context(|| format!("unable to subscribe to {:?}", self.redacted_key(&key)));
+        }
+
+        let existing = map.contains_key(&key);
+
+        match mode {
+            SubscribeMode::UseExisting if !existing => Ok(Negotiated::Absent),
+            SubscribeMode::UseExisting => Ok(Negotiated::Existing(self.attach_with(
+                &entries,
+                &mut map,
+                key,
+                || unreachable!("the entry exists"),
+            )?)),
+            SubscribeMode::ProposeInitial(value) => {
+                let subscription = self.attach(&entries, &mut map, key, value)?;
+
+                if existing {
+                    Ok(Negotiated::Existing(subscription))
+                } else {
+                    Ok(Negotiated::Inserted(subscription))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Negotiated, SubscribeMode};
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_report_who_initialized_the_entry() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let absent = map.negotiate(1, SubscribeMode::UseExisting).await.unwrap();
+        assert!(matches!(absent, Negotiated::Absent));
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+
+        let (first, second) = futures::join!(
+            map.negotiate(1, SubscribeMode::ProposeInitial(1)),
+            map.negotiate(1, SubscribeMode::ProposeInitial(2)),
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+        assert!(first.is_inserted() != second.is_inserted());
+
+        let first = first.into_subscription().unwrap();
+        let second = second.into_subscription().unwrap();
+        assert_eq!(first.latest(), second.latest());
+
+        let existing = map.negotiate(1, SubscribeMode::UseExisting).await.unwrap();
+        assert!(matches!(existing, Negotiated::Existing(_)));
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 3);
+    }
+}
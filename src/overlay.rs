@@ -0,0 +1,112 @@
+use crate::{SubscriptionMap, SubscriptionRef};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+/// A map layered on top of a base map, whose values serve as the initial state of new entries.
+///
+/// The overlay only reads from the base, subscriptions and publishes always go to the overlay
+/// map. This allows layering per-request overrides on top of shared global state, an entry of
+/// the overlay starts out with the value of the base once and diverges from there.
+///
+/// ```
+/// # use async_subscription_map::SubscriptionMap;
+/// # async {
+/// let global = SubscriptionMap::<&'static str, usize>::default();
+/// global.pin("timeout", 30).await.unwrap();
+///
+/// let request = SubscriptionMap::<&'static str, usize>::default();
+/// let overlay = request.overlay(&global);
+///
+/// let mut timeout = overlay.get_or_insert("timeout", 10).await.unwrap();
+/// assert_eq!(timeout.latest(), 30);
+///
+/// timeout.publish(5).unwrap();
+/// assert_eq!(overlay.get_or_insert("retries", 3).await.unwrap().latest(), 3);
+/// # };
+/// ```
+#[derive(Clone)]
+pub struct Overlay<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    map: SubscriptionMap<K, V>,
+    base: SubscriptionMap<K, V>,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Layer this map on top of the base map, see [`Overlay`].
+    pub fn overlay(&self, base: &SubscriptionMap<K, V>) -> Overlay<K, V> {
+        Overlay {
+            map: self.clone(),
+            base: base.clone(),
+        }
+    }
+}
+
+impl<K, V> Overlay<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The map holding the overrides
+    pub fn map(&self) -> &SubscriptionMap<K, V> {
+        &self.map
+    }
+
+    /// The map the initial values are taken from
+    pub fn base(&self) -> &SubscriptionMap<K, V> {
+        &self.base
+    }
+
+    /// Subscribe to the key in the overlay map, new entries are initialized with the value of the
+    /// base map and only fall back to the provided value if the base doesn't contain the key.
+    pub async fn get_or_insert(&self, key: K, value: V) -> anyhow::Result<SubscriptionRef<K, V>> {
+        // the base is read before the overlay is locked, so the maps are never locked together
+        let value = self.base.peek(&key).await.unwrap_or(value);
+
+        self.map.entry(key).await.or_insert(value)
+    }
+}
+
+impl<K, V> Debug for Overlay<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Overlay")
+            .field("map", &self.map)
+            .field("base", &self.base)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_initialize_entries_from_base() {
+        let base: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let overlay = map.overlay(&base);
+
+        let mut global = base.get_or_insert(1, 10).await.unwrap();
+        let mut local = overlay.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(local.latest(), 10);
+
+        // once created the entries are independent of each other
+        global.publish(11).unwrap();
+        local.publish(12).unwrap();
+        assert_eq!(overlay.get_or_insert(1, 0).await.unwrap().latest(), 12);
+        assert_eq!(global.synchronize(), 11);
+
+        assert_eq!(overlay.get_or_insert(2, 2).await.unwrap().latest(), 2);
+        assert_eq!(base.entries_snapshot().await.len(), 1);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{diag, SubscriptionMap};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Buffer all publish operations until [`resume`](Self::resume) is called.
+    ///
+    /// Unlike [`freeze`](Self::freeze) publishes succeed while paused, they are coalesced per key
+    /// and subscribers only observe the latest buffered value once the map is resumed. Hence
+    /// producers don't need to handle errors during brief maintenance operations.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    ///
+    /// map.pause();
+    /// map.publish_if_changed(&1, 1).await.unwrap();
+    /// map.publish_if_changed(&1, 2).await.unwrap();
+    /// assert_eq!(subscription.latest(), 0);
+    ///
+    /// map.resume().await;
+    /// assert_eq!(subscription.next().await, 2);
+    /// # };
+    /// ```
+    pub fn pause(&self) {
+        self.publish_state_mut()
+            .paused
+            .get_or_insert_with(BTreeMap::new);
+    }
+
+    /// Publish all buffered values and apply publishes immediately again.
+    ///
+    /// Buffered values of keys which were removed in the meantime are discarded.
+    pub async fn resume(&self) {
+        let mut entries = self.lock_entries(&self.0.entries()).await;
+        let mut state = self.publish_state_mut();
+
+        for (key, value) in state.paused.take().into_iter().flatten() {
+            if let Some(entry) = entries.get_mut(&key) {
+                self.publish_buffered(&key, None, &mut entry.target(), value);
+            } else {
+                diag::debug!(
+                    "discarding paused publish of removed key {:?}",
+                    self.redacted_key(&key)
+                );
+            }
+        }
+    }
+
+    /// Check if the map is currently paused
+    pub fn is_paused(&self) -> bool {
+        self.publish_state().paused.is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_coalesce_publishes_while_paused() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut one = map.get_or_insert(1, 0).await.unwrap();
+        let mut two = map.get_or_insert(2, 0).await.unwrap();
+
+        map.pause();
+        assert!(map.is_paused());
+
+        one.publish(1).unwrap();
+        one.modify(|v| *v += 1).unwrap();
+        assert!(map.publish_if_changed(&2, 5).await.unwrap());
+        assert!(!two.publish_if_changed(5).unwrap());
+        assert_eq!(one.latest(), 0);
+        assert_eq!(two.latest(), 0);
+
+        map.resume().await;
+        assert!(!map.is_paused());
+        assert_eq!(one.next().await, 2);
+        assert_eq!(two.next().await, 5);
+    }
+
+    #[async_std::test]
+    async fn should_not_buffer_unchanged_values() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.pause();
+        assert!(!subscription.publish_if_changed(0).unwrap());
+        map.resume().await;
+
+        subscription.publish(1).unwrap();
+        assert_eq!(subscription.next().await, 1);
+    }
+
+    #[async_std::test]
+    async fn should_reject_paused_publishes_when_frozen() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.pause();
+        map.freeze();
+        assert!(subscription.publish(1).is_err());
+
+        map.thaw();
+        map.resume().await;
+        assert_eq!(subscription.synchronize(), 0);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::SubscriptionMap;
+use std::collections::{btree_map, BTreeSet};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Keep the entry in the map even if no one subscribes to it, initializes it if not present.
+    ///
+    /// Pinned entries are only cleaned up once they are [unpinned](Self::unpin).
+    pub async fn pin(&self, key: K, value: V) -> anyhow::Result<()> {
+        self.seed([(key, value)]).await
+    }
+
+    /// [Pin](Self::pin) all pairs under a single lock acquisition, e.g. to preload feature flags
+    /// and config keys at startup.
+    ///
+    /// The pairs can come from any source, such as a configuration file deserialized by the
+    /// service. Existing entries keep their values and are only pinned. Seeding is all or
+    /// nothing: if a value is too large or the pairs don't fit into the capacity, no entry is
+    /// pinned, inserted or evicted. Entries of seeded keys are never evicted to make room for
+    /// other seeded keys.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, bool>::default();
+    /// map.seed([("flags/dark-mode", true), ("flags/beta", false)])
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// assert_eq!(map.peek(&"flags/dark-mode").await, Some(true));
+    /// # };
+    /// ```
+    pub async fn seed<I>(&self, pairs: I) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let pairs: Vec<_> = pairs.into_iter().collect();
+        let mut map = self.lock_entries(&self.0.entries()).await;
+        let now = self.0.config.clock.now();
+
+        // validate everything up front, so a failing seed leaves the map untouched
+        let mut keys = BTreeSet::new();
+
+        for (key, value) in &pairs {
+            if !map.contains_key(key) && !keys.contains(key) {
+                self.check_value_size(key, value)?;
+            }
+
+            keys.insert(key.clone());
+        }
+
+        self.make_room_for(&mut map, &keys)?;
+
+        for (key, value) in pairs {
+            match map.entry(key) {
+                btree_map::Entry::Occupied(entry) => entry.into_mut().pinned = true,
+                btree_map::Entry::Vacant(entry) => {
+                    let mut new = self.new_entry(entry.key(), value, now);
+                    new.pinned = true;
+                    entry.insert(new);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Release a pinned entry, it is removed immediately if no one subscribes to it.
+    ///
+    /// Returns if the entry was pinned.
+    pub async fn unpin(&self, key: &K) -> bool {
+        let mut map = self.lock_entries(&self.0.entries()).await;
+
+        let entry = match map.get_mut(key) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        let pinned = std::mem::replace(&mut entry.pinned, false);
+
+        if entry.removable(self.0.config.clock.now()) && !self.is_excluded_from_cleanup(key) {
+            if let Some(entry) = map.remove(key) {
+                self.record_removal(&mut map, key, &entry);
+            }
+        }
+
+        pinned
+    }
+
+    /// Create an independent map which contains the current values of this one but none of its
+    /// subscribers.
+    ///
+    /// All entries of the clone are pinned, so they survive until they are unpinned. The clone
+    /// shares the configuration of this map but starts with fresh statistics and publish state,
+    /// which makes it possible to fork a simulation or test scenario from live state.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    /// subscription.publish(1).unwrap();
+    ///
+    /// let fork = map.deep_clone().await;
+    /// drop(subscription);
+    ///
+    /// let subscription = fork.get_or_insert(1, 0).await.unwrap();
+    /// assert_eq!(subscription.latest(), 1);
+    /// # };
+    /// ```
+    pub async fn deep_clone(&self) -> Self {
+        let clone = Self::with_config(self.0.config.clone());
+        clone.tune(|tunables| *tunables = self.tunables());
+
+        {
+            let source = self.read_entries().await;
+            let mut target = clone.0.entries().write_arc().await;
+            let now = clone.0.config.clock.now();
+
+            for (key, entry) in source.iter() {
+                let mut new = clone.new_entry(key, entry.observable.latest(), now);
+                new.pinned = true;
+                target.insert(key.clone(), new);
+            }
+        }
+
+        clone
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+
+    #[async_std::test]
+    async fn should_keep_pinned_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        map.pin(1, 0).await.unwrap();
+
+        let mut subscription = map.get_or_insert(1, 5).await.unwrap();
+        subscription.publish(1).unwrap();
+        drop(subscription);
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 0);
+
+        let subscription = map.get_or_insert(1, 5).await.unwrap();
+        assert_eq!(subscription.latest(), 1);
+
+        assert!(map.unpin(&1).await);
+        assert!(!map.unpin(&1).await);
+        assert_eq!(map.entries_snapshot().await.len(), 1);
+
+        drop(subscription);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_seed_pinned_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        map.pin(1, 5).await.unwrap();
+        map.seed([(1, 0), (2, 2)]).await.unwrap();
+
+        let snapshot = map.entries_snapshot().await;
+        assert!(snapshot.values().all(|entry| entry.pinned));
+        assert_eq!(snapshot[&1].observable.latest(), 5);
+        assert_eq!(snapshot[&2].observable.latest(), 2);
+    }
+
+    #[async_std::test]
+    async fn should_not_seed_anything_if_a_pair_fails() {
+        let map = SubscriptionMap::<usize, Vec<u8>>::builder()
+            .capacity(3)
+            .max_value_size(2, |blob: &Vec<u8>| blob.len())
+            .build();
+        map.pin(1, vec![]).await.unwrap();
+        let _two = map.get_or_insert(2, vec![]).await.unwrap();
+
+        let err = map
+            .seed([(2, vec![]), (3, vec![0]), (4, vec![0; 3])])
+            .await
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ValueTooLarge));
+
+        let one = map.get_or_insert(1, vec![]).await.unwrap();
+        let err = map
+            .seed([(2, vec![]), (3, vec![]), (4, vec![])])
+            .await
+            .unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::CapacityExceeded));
+
+        let snapshot = map.entries_snapshot().await;
+        assert_eq!(snapshot.keys().collect::<Vec<_>>(), vec![&1, &2]);
+        assert!(!snapshot[&2].pinned);
+
+        // the unreferenced entry makes room, but seeded ones are never evicted
+        drop(one);
+        map.seed([(1, vec![]), (3, vec![]), (4, vec![])])
+            .await
+            .unwrap_err();
+        map.seed([(3, vec![]), (4, vec![])]).await.unwrap();
+
+        let snapshot = map.entries_snapshot().await;
+        assert_eq!(snapshot.keys().collect::<Vec<_>>(), vec![&2, &3, &4]);
+    }
+
+    #[async_std::test]
+    async fn should_deep_clone_values_without_subscribers() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut one = map.get_or_insert(1, 0).await.unwrap();
+        let _two = map.get_or_insert(2, 2).await.unwrap();
+        one.publish(1).unwrap();
+
+        let fork = map.deep_clone().await;
+        let snapshot = fork.entries_snapshot().await;
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot
+            .values()
+            .all(|entry| entry.rc() == 0 && entry.pinned));
+
+        // the maps are independent of each other
+        let mut forked = fork.get_or_insert(1, 0).await.unwrap();
+        forked.publish(10).unwrap();
+        assert_eq!(one.latest(), 1);
+        assert_eq!(forked.latest(), 10);
+
+        drop(one);
+        assert_eq!(map.entries_snapshot().await.len(), 1);
+        #[cfg(feature = "stats")]
+        assert_eq!(fork.stats().total.created, 2);
+
+        fork.unpin(&2).await;
+        assert_eq!(fork.entries_snapshot().await.len(), 1);
+    }
+}
@@ -0,0 +1,50 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Keys which can be grouped by a common prefix, e.g. `"sessions/"` for `"sessions/42"`.
+///
+/// Features which are configured per group of keys, such as the churn statistics, are available
+/// for maps whose keys implement this trait.
+pub trait KeyPrefix {
+    /// Check if the key starts with the prefix
+    fn starts_with(&self, prefix: &Self) -> bool;
+}
+
+impl KeyPrefix for String {
+    fn starts_with(&self, prefix: &Self) -> bool {
+        self.as_str().starts_with(prefix.as_str())
+    }
+}
+
+impl KeyPrefix for &str {
+    fn starts_with(&self, prefix: &Self) -> bool {
+        str::starts_with(self, prefix)
+    }
+}
+
+impl KeyPrefix for Box<str> {
+    fn starts_with(&self, prefix: &Self) -> bool {
+        str::starts_with(self, &**prefix)
+    }
+}
+
+impl KeyPrefix for Rc<str> {
+    fn starts_with(&self, prefix: &Self) -> bool {
+        str::starts_with(self, &**prefix)
+    }
+}
+
+impl KeyPrefix for Arc<str> {
+    fn starts_with(&self, prefix: &Self) -> bool {
+        str::starts_with(self, &**prefix)
+    }
+}
+
+impl<T> KeyPrefix for Vec<T>
+where
+    T: PartialEq,
+{
+    fn starts_with(&self, prefix: &Self) -> bool {
+        <[T]>::starts_with(self, prefix)
+    }
+}
@@ -0,0 +1,187 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+/// The priority of a writer waiting for the lock of an entry, see
+/// [`SubscriptionMap::modify_async_with_priority`](crate::SubscriptionMap::modify_async_with_priority).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Only served if no other writer waits, e.g. for background refreshes
+    Low,
+    /// The priority of [`modify_async`](crate::SubscriptionMap::modify_async)
+    #[default]
+    Normal,
+    /// Served before all waiting writers of lower priority
+    High,
+}
+
+/// The lock serializing the async read-modify-writes of an entry.
+///
+/// It is handed off on release instead of being reacquired, the waiter with the highest
+/// priority gets it next and waiters of the same priority are served first come, first served.
+/// A high priority writer therefore waits for the current holder at most, never for the queue
+/// of lower priority writers in front of it.
+#[derive(Debug, Default)]
+pub(crate) struct EntryLock {
+    state: Mutex<LockState>,
+}
+
+#[derive(Debug, Default)]
+struct LockState {
+    /// The ticket of the current holder, a waiter it was handed off to may not have polled yet
+    holder: Option<u64>,
+    /// The waiting writers, ordered by the ticket they are served with
+    waiters: BTreeMap<(Reverse<Priority>, u64), Waker>,
+    /// The ticket of the next writer
+    next_ticket: u64,
+}
+
+impl LockState {
+    /// Hand the lock to the next waiter or unlock it if no one waits
+    fn release(&mut self) {
+        self.holder = self.waiters.pop_first().map(|((_, ticket), waker)| {
+            waker.wake();
+            ticket
+        });
+    }
+}
+
+impl EntryLock {
+    pub fn lock(self: &Arc<Self>, priority: Priority) -> Lock {
+        Lock {
+            lock: self.clone(),
+            priority,
+            ticket: None,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, LockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Waits for an [`EntryLock`], leaving the queue if it is dropped before
+pub(crate) struct Lock {
+    lock: Arc<EntryLock>,
+    priority: Priority,
+    /// Assigned on the first poll, the position in [`LockState::waiters`]
+    ticket: Option<u64>,
+}
+
+impl Future for Lock {
+    type Output = EntryGuard;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let lock = self.lock.clone();
+        let mut state = lock.state();
+
+        let ticket = match self.ticket {
+            Some(ticket) => ticket,
+            None => {
+                let ticket = state.next_ticket;
+                state.next_ticket += 1;
+
+                if state.holder.is_none() {
+                    state.holder = Some(ticket);
+                }
+
+                self.ticket = Some(ticket);
+                ticket
+            }
+        };
+
+        if state.holder == Some(ticket) {
+            state.waiters.remove(&(Reverse(self.priority), ticket));
+            drop(state);
+
+            // the guard releases the lock from now on
+            self.ticket = None;
+            return Poll::Ready(EntryGuard { lock });
+        }
+
+        state
+            .waiters
+            .insert((Reverse(self.priority), ticket), cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let ticket = match self.ticket {
+            Some(ticket) => ticket,
+            None => return,
+        };
+
+        let mut state = self.lock.state();
+        state.waiters.remove(&(Reverse(self.priority), ticket));
+
+        // the lock was handed off to this waiter, but it is no longer interested
+        if state.holder == Some(ticket) {
+            state.release();
+        }
+    }
+}
+
+/// Holds the [`EntryLock`] until it is dropped
+pub(crate) struct EntryGuard {
+    lock: Arc<EntryLock>,
+}
+
+impl Drop for EntryGuard {
+    fn drop(&mut self) {
+        self.lock.state().release();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EntryLock, Priority};
+    use futures::FutureExt;
+    use std::sync::Arc;
+
+    #[async_std::test]
+    async fn should_hand_off_to_the_highest_priority() {
+        let lock = Arc::new(EntryLock::default());
+        let holder = lock.lock(Priority::Low).await;
+
+        let mut low = lock.lock(Priority::Low);
+        let mut normal = lock.lock(Priority::Normal);
+        let mut high = lock.lock(Priority::High);
+        assert!((&mut low).now_or_never().is_none());
+        assert!((&mut normal).now_or_never().is_none());
+        assert!((&mut high).now_or_never().is_none());
+
+        drop(holder);
+        assert!((&mut low).now_or_never().is_none());
+        assert!((&mut normal).now_or_never().is_none());
+        let high = high.now_or_never().unwrap();
+
+        drop(high);
+        assert!((&mut low).now_or_never().is_none());
+        drop(normal.now_or_never().unwrap());
+        drop(low.now_or_never().unwrap());
+
+        assert!(lock.lock(Priority::Low).now_or_never().is_some());
+    }
+
+    #[async_std::test]
+    async fn should_pass_on_locks_of_dropped_waiters() {
+        let lock = Arc::new(EntryLock::default());
+        let holder = lock.lock(Priority::Normal).await;
+
+        let mut high = lock.lock(Priority::High);
+        let mut low = lock.lock(Priority::Low);
+        assert!((&mut high).now_or_never().is_none());
+        assert!((&mut low).now_or_never().is_none());
+
+        // the lock is handed to the high priority waiter which goes away before it polls
+        drop(holder);
+        drop(high);
+
+        assert!(low.now_or_never().is_some());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::gate::PublishTarget;
+use crate::{Entries, EntryMeta, SubscriptionMap};
+use async_observable::Observable;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::{Arc, Weak};
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// A handle which publishes to the key without keeping its entry alive.
+    ///
+    /// Every [`SubscriptionRef`](crate::SubscriptionRef) counts towards the liveness of its
+    /// entry, so a long-lived producer holding one keeps the entry around even if no reader
+    /// cares. Publisher refs don't count, the entry is cleaned up as soon as the last subscriber
+    /// is gone and publishes only reach the entry while someone subscribes to it.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut publisher = map.publisher(1);
+    /// assert!(!publisher.publish(1).await.unwrap());
+    ///
+    /// let subscriber = map.get_or_insert_read_only(1, 0).await.unwrap();
+    /// assert!(publisher.publish(2).await.unwrap());
+    /// assert_eq!(subscriber.latest(), 2);
+    ///
+    /// // the publisher doesn't keep the entry around
+    /// drop(subscriber);
+    /// assert_eq!(map.peek(&1).await, None);
+    /// # };
+    /// ```
+    pub fn publisher(&self, key: K) -> PublisherRef<K, V> {
+        PublisherRef {
+            key,
+            owner: self.internal(),
+            target: None,
+        }
+    }
+}
+
+/// The entry a publisher ref last published to
+struct Target<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    entries: Weak<Entries<K, V>>,
+    observable: Observable<V>,
+    /// Detached once the entry is removed and dropped together with it and its refs, a live
+    /// attached one means the entry is still around
+    meta: Weak<EntryMeta<V>>,
+}
+
+/// Publishes to a key without keeping its entry alive, see [`SubscriptionMap::publisher`].
+///
+/// Subscribers are the refs which count towards liveness, e.g. [`SubscriptionRef`]s and
+/// [`ReadOnlyRef`]s, while any number of publishers can feed the entry on demand.
+///
+/// [`SubscriptionRef`]: crate::SubscriptionRef
+/// [`ReadOnlyRef`]: crate::ReadOnlyRef
+pub struct PublisherRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    key: K,
+    owner: SubscriptionMap<K, V>,
+    target: Option<Target<K, V>>,
+}
+
+impl<K, V> PublisherRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key published to
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Store the value if the entry exists and notify all subscribers, returns if the entry
+    /// exists.
+    pub async fn publish(&mut self, value: V) -> anyhow::Result<bool> {
+        self.modify(|current| *current = value).await
+    }
+
+    /// Modify the value if the entry exists and notify all subscribers, returns if the entry
+    /// exists.
+    pub async fn modify<F>(&mut self, modify: F) -> anyhow::Result<bool>
+    where
+        F: FnOnce(&mut V),
+    {
+        let (observable, meta) = match self.target().await {
+            Some(target) => target,
+            None => return Ok(false),
+        };
+
+        let target = PublishTarget {
+            observable,
+            meta: &meta,
+            detached: false,
+        };
+        self.owner
+            .publish_gate(&self.key, target, |_| true, modify)?;
+
+        Ok(true)
+    }
+
+    /// The observable and metadata of the entry, the entries are only looked at again once the
+    /// previous entry is gone or the map [swapped](SubscriptionMap::swap_contents) its entries
+    async fn target(&mut self) -> Option<(Observable<V>, Arc<EntryMeta<V>>)> {
+        let entries = self.owner.0.entries();
+
+        if let Some(target) = &self.target {
+            if let Some(meta) = target.meta.upgrade() {
+                if !meta.is_detached() && Weak::as_ptr(&target.entries) == Arc::as_ptr(&entries) {
+                    return Some((target.observable.clone(), meta));
+                }
+            }
+        }
+
+        let map = entries.read_arc().await;
+        let entry = map.get(&self.key);
+
+        self.target = entry.map(|entry| Target {
+            entries: Arc::downgrade(&entries),
+            observable: entry.observable.clone(),
+            meta: Arc::downgrade(&entry.meta),
+        });
+
+        entry.map(|entry| (entry.observable.clone(), entry.meta.clone()))
+    }
+}
+
+impl<K, V> Debug for PublisherRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PublisherRef")
+            .field("key", &self.owner.redacted_key(&self.key))
+            .field(
+                "attached",
+                &self
+                    .target
+                    .as_ref()
+                    .is_some_and(|target| target.meta.strong_count() > 0),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_publish_only_while_subscribed() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut publisher = map.publisher(1);
+        assert!(!publisher.publish(1).await.unwrap());
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+
+        let mut subscriber = map.get_or_insert(1, 0).await.unwrap();
+        assert!(publisher.modify(|v| *v += 5).await.unwrap());
+        assert_eq!(subscriber.next().await, 5);
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 1);
+
+        drop(subscriber);
+        assert!(!publisher.publish(6).await.unwrap());
+
+        // a new entry at the same key is picked up again
+        let subscriber = map.get_or_insert(1, 0).await.unwrap();
+        assert!(publisher.publish(7).await.unwrap());
+        assert_eq!(subscriber.latest(), 7);
+    }
+}
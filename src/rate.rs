@@ -0,0 +1,204 @@
+use crate::{Clock, SubscriptionMap};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Observe how many publishes are made to the key per window, without subscribing to it.
+    ///
+    /// Only publishes which change the value are counted, whether they are made through the map
+    /// or through refs. The rate neither keeps the entry alive nor requires it to exist, so it is
+    /// suited to monitor how hot a key is or to throttle its producers adaptively. Windows are
+    /// timed by the [`Clock`] of the map and start once the rate is created.
+    ///
+    /// ```
+    /// # use async_subscription_map::{ManualClock, SubscriptionMap};
+    /// # use std::time::Duration;
+    /// # async {
+    /// let clock = ManualClock::new();
+    /// let map = SubscriptionMap::<usize, usize>::builder()
+    ///     .clock(clock.clone())
+    ///     .build();
+    /// let mut rate = map.rate(&1, Duration::from_secs(1));
+    ///
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    /// subscription.publish(1).unwrap();
+    /// subscription.publish(2).unwrap();
+    ///
+    /// clock.advance(Duration::from_secs(1));
+    /// assert_eq!(rate.next().await, 2);
+    /// # };
+    /// ```
+    pub fn rate(&self, key: &K, window: Duration) -> Rate<K, V> {
+        let counter = Arc::new(AtomicU64::new(0));
+
+        self.0
+            .rates
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .push(counter.clone());
+
+        let clock = self.clock();
+
+        Rate {
+            key: key.clone(),
+            owner: self.internal(),
+            window,
+            window_start: clock.now(),
+            clock,
+            counter,
+            latest: 0,
+        }
+    }
+
+    /// Account for a publish in the rates of the key
+    pub(crate) fn count_publish(&self, key: &K) {
+        let rates = self.0.rates.read().unwrap_or_else(|e| e.into_inner());
+
+        for counter in rates.get(key).into_iter().flatten() {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The amount of publishes to a key per window, see [`SubscriptionMap::rate`].
+pub struct Rate<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    key: K,
+    owner: SubscriptionMap<K, V>,
+    window: Duration,
+    window_start: Instant,
+    clock: Arc<dyn Clock>,
+    counter: Arc<AtomicU64>,
+    latest: u64,
+}
+
+impl<K, V> Rate<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The observed key
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// The amount of publishes in the last completed window, zero before the first one ended.
+    pub fn latest(&self) -> u64 {
+        self.latest
+    }
+
+    /// Wait until the current window ends and return the amount of publishes within it.
+    ///
+    /// A window which is read late also counts the publishes until it is read, the next window
+    /// starts at that point.
+    pub async fn next(&mut self) -> u64 {
+        let end = self.window_start + self.window;
+        self.clock.sleep_until(end).await;
+
+        let now = self.clock.now();
+        self.window_start = if now < end + self.window { end } else { now };
+        self.latest = self.counter.swap(0, Ordering::Relaxed);
+        self.latest
+    }
+}
+
+impl<K, V> Debug for Rate<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Rate")
+            .field("key", &self.owner.redacted_key(&self.key))
+            .field("window", &self.window)
+            .field("latest", &self.latest)
+            .finish()
+    }
+}
+
+impl<K, V> Drop for Rate<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn drop(&mut self) {
+        let mut rates = self
+            .owner
+            .0
+            .rates
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+
+        if let Some(counters) = rates.get_mut(&self.key) {
+            counters.retain(|counter| !Arc::ptr_eq(counter, &self.counter));
+
+            if counters.is_empty() {
+                rates.remove(&self.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_count_changing_publishes_per_window() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .build();
+        let mut rate = map.rate(&1, Duration::from_secs(1));
+        assert_eq!(rate.latest(), 0);
+
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+        subscription.publish(1).unwrap();
+        assert!(!map.publish_if_changed(&1, 1).await.unwrap());
+        map.modify_and_publish(&1, |v| *v += 1).await.unwrap();
+        map.get_or_insert(2, 0).await.unwrap().publish(1).unwrap();
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(rate.next().await, 2);
+        assert_eq!(rate.latest(), 2);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(rate.next().await, 0);
+
+        drop(rate);
+        assert!(map.0.rates.read().unwrap().is_empty());
+    }
+
+    #[async_std::test]
+    async fn should_only_count_publishes_to_current_entries() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .build();
+        let other = SubscriptionMap::<usize, usize>::new();
+        let mut rate = map.rate(&1, Duration::from_secs(1));
+
+        let mut swapped_out = map.get_or_insert(1, 0).await.unwrap();
+        map.swap_contents(&other);
+
+        // the swapped out entry belongs to the other map now
+        swapped_out.publish(1).unwrap();
+        map.get_or_insert(1, 0).await.unwrap().publish(1).unwrap();
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(rate.next().await, 1);
+    }
+}
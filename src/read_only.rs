@@ -0,0 +1,150 @@
+use crate::{Error, SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+/// A subscription which can observe but not publish.
+///
+/// It keeps the entry alive just like a [`SubscriptionRef`], which makes it suitable to share a
+/// subscription with observers without also granting them write access to the entry.
+///
+/// ```
+/// # use async_subscription_map::SubscriptionMap;
+/// # async {
+/// let map = SubscriptionMap::<usize, usize>::default();
+/// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+/// let mut observer = subscription.frozen().await.unwrap();
+///
+/// subscription.publish(1).unwrap();
+/// assert_eq!(observer.next().await, 1);
+/// # };
+/// ```
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct ReadOnlyRef<K, V>(SubscriptionRef<K, V>)
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug;
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Create a read-only subscription to the same entry.
+    ///
+    /// Fails with [`Error::Draining`] once the map is [draining](SubscriptionMap::drain).
+    pub async fn frozen(&self) -> anyhow::Result<ReadOnlyRef<K, V>> {
+        if self.owner.is_draining() {
+            return Err(Error::Draining).with_context(|| {
+                format!(
+                    "unable to subscribe to {:?}",
+                    self.owner.redacted_key(&self.key)
+                )
+            });
+        }
+
+        Ok(ReadOnlyRef(self.fork().await))
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Same as [`get_or_insert`](Self::get_or_insert) but returns a read-only subscription.
+    pub async fn get_or_insert_read_only(
+        &self,
+        key: K,
+        value: V,
+    ) -> anyhow::Result<ReadOnlyRef<K, V>> {
+        Ok(ReadOnlyRef(self.get_or_insert(key, value).await?))
+    }
+}
+
+impl<K, V> ReadOnlyRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the observed entry
+    pub fn key(&self) -> &K {
+        &self.0.key
+    }
+
+    /// A clone of the latest value, without consuming the change.
+    pub fn latest(&self) -> V {
+        self.0.latest()
+    }
+
+    /// Wait until a new version is published and return a clone of it.
+    pub async fn next(&mut self) -> V {
+        self.0.next().await
+    }
+
+    /// Skip any pending updates and return the latest value.
+    pub fn synchronize(&mut self) -> V {
+        self.0.synchronize()
+    }
+
+    /// Create another read-only subscription to the same entry, see
+    /// [`SubscriptionRef::frozen`].
+    pub async fn frozen(&self) -> anyhow::Result<ReadOnlyRef<K, V>> {
+        self.0.frozen().await
+    }
+}
+
+impl<K, V> Debug for ReadOnlyRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReadOnlyRef").field(&self.0).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+
+    #[async_std::test]
+    async fn should_observe_publishes() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+        let mut observer = subscription.frozen().await.unwrap();
+
+        subscription.publish(1).unwrap();
+        assert_eq!(observer.next().await, 1);
+        assert_eq!(observer.latest(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_keep_entry_alive() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let observer = subscription.frozen().await.unwrap();
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 2);
+
+        drop(subscription);
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 1);
+
+        let another = observer.frozen().await.unwrap();
+        drop(observer);
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 1);
+
+        drop(another);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_reject_forks_while_draining() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.drain();
+        let err = subscription.frozen().await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::Draining));
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 1);
+    }
+}
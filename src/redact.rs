@@ -0,0 +1,173 @@
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+
+/// Controls how values are printed whenever the map formats them for diagnostics.
+///
+/// This applies to the `Debug` output of the map and its refs as well as to every log message the
+/// map emits, so secrets stored in entries never end up in diagnostics output. Keys are formatted
+/// through a separate redactor, see
+/// [`key_redactor`](crate::SubscriptionMapBuilder::key_redactor). Any closure with a matching
+/// signature is a redactor.
+///
+/// ```
+/// # use async_subscription_map::{FullRedaction, SubscriptionMap};
+/// let map = SubscriptionMap::<&'static str, String>::builder()
+///     .redactor(FullRedaction)
+///     .build();
+///
+/// let lengths = SubscriptionMap::<&'static str, String>::builder()
+///     .redactor(|v: &String, f: &mut std::fmt::Formatter<'_>| write!(f, "<{} bytes>", v.len()))
+///     .build();
+/// ```
+pub trait Redactor<V>: Send + Sync {
+    /// Write a redacted representation of the value to the formatter
+    fn redact(&self, value: &V, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+impl<V, F> Redactor<V> for F
+where
+    F: Fn(&V, &mut fmt::Formatter<'_>) -> fmt::Result + Send + Sync,
+{
+    fn redact(&self, value: &V, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self(value, f)
+    }
+}
+
+/// A redactor which hides values entirely
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FullRedaction;
+
+impl<V> Redactor<V> for FullRedaction {
+    fn redact(&self, _: &V, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// A key redactor which prints a hash instead of the key.
+///
+/// Every redactor hashes with its own random key, so hashes can't be precomputed to recover keys
+/// with little entropy such as user ids. They are stable for as long as the redactor lives, log
+/// lines of the map it is configured for can still be correlated, but hashes of the same key
+/// differ between maps and restarts.
+#[derive(Clone, Debug, Default)]
+pub struct HashedKeys(RandomState);
+
+impl HashedKeys {
+    /// A redactor with a new random key
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K> Redactor<K> for HashedKeys
+where
+    K: Hash,
+{
+    fn redact(&self, key: &K, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:016x}", self.0.hash_one(key))
+    }
+}
+
+/// A key redactor which only prints the first characters of the `Debug` output of keys, e.g. to
+/// keep the namespace of a key while cutting off the user identifier behind it.
+#[derive(Clone, Copy, Debug)]
+pub struct TruncatedKeys(pub usize);
+
+impl<K> Redactor<K> for TruncatedKeys
+where
+    K: Debug,
+{
+    fn redact(&self, key: &K, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatted = format!("{:?}", key);
+
+        match formatted.char_indices().nth(self.0) {
+            Some((end, _)) => write!(f, "{}...", &formatted[..end]),
+            None => f.write_str(&formatted),
+        }
+    }
+}
+
+/// Formats a value through the optional redactor of a map
+pub(crate) struct Redacted<'a, V> {
+    pub value: &'a V,
+    pub redactor: Option<&'a dyn Redactor<V>>,
+}
+
+impl<V> Debug for Redacted<'_, V>
+where
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.redactor {
+            Some(redactor) => redactor.redact(self.value, f),
+            None => self.value.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{FullRedaction, HashedKeys, SubscriptionMap, TruncatedKeys};
+
+    #[async_std::test]
+    async fn should_redact_debug_output() {
+        let map = SubscriptionMap::<usize, &'static str>::builder()
+            .redactor(FullRedaction)
+            .build();
+        let subscription = map.get_or_insert(1, "hunter2").await.unwrap();
+
+        assert!(!format!("{:?}", map).contains("hunter2"));
+        assert!(!format!("{:?}", subscription).contains("hunter2"));
+        assert!(format!("{:?}", subscription).contains("<redacted>"));
+    }
+
+    #[async_std::test]
+    async fn should_redact_keys_in_diagnostics() {
+        let map = SubscriptionMap::<&'static str, usize>::builder()
+            .key_redactor(TruncatedKeys(6))
+            .build();
+        let subscription = map.get_or_insert("users/alice", 0).await.unwrap();
+        assert_eq!(subscription.key(), &"users/alice");
+
+        assert!(!format!("{:?}", map).contains("alice"));
+        assert!(format!("{:?}", subscription).contains("\"users..."));
+
+        map.freeze();
+        let err = map.publish_if_changed(&"users/alice", 1).await.unwrap_err();
+        assert!(!format!("{:#}", err).contains("alice"));
+
+        let hashed = SubscriptionMap::<&'static str, usize>::builder()
+            .key_redactor(HashedKeys::new())
+            .build();
+        let _subscription = hashed.get_or_insert("users/alice", 0).await.unwrap();
+        assert!(!format!("{:?}", hashed).contains("alice"));
+        assert!(format!("{:?}", hashed).contains('#'));
+    }
+
+    #[test]
+    fn should_key_hashes_per_redactor() {
+        struct Hashed<'a>(&'a HashedKeys, &'a str);
+
+        impl std::fmt::Debug for Hashed<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                crate::Redactor::redact(self.0, &self.1, f)
+            }
+        }
+
+        let (first, second) = (HashedKeys::new(), HashedKeys::new());
+        let hash = |redactor| format!("{:?}", Hashed(redactor, "users/alice"));
+
+        assert_eq!(hash(&first), hash(&first));
+        assert_ne!(hash(&first), hash(&second));
+    }
+
+    #[async_std::test]
+    async fn should_print_values_without_redactor() {
+        let map = SubscriptionMap::<usize, &'static str>::new();
+        let subscription = map.get_or_insert(1, "visible").await.unwrap();
+
+        assert!(format!("{:?}", map).contains("visible"));
+        assert!(format!("{:?}", subscription).contains("visible"));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{bulk, diag, SubscriptionEntry, SubscriptionMap, SubscriptionRef};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Bound;
+use std::sync::atomic::Ordering;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Remove the entry even if it is referenced, pinned or retained, returns if it existed.
+    ///
+    /// Current subscribers are woken and observe the removal through
+    /// [`SubscriptionRef::is_removed`], their refs stay usable but are detached from the map. A
+    /// later subscription to the key creates a new entry. This is meant for administrative tasks
+    /// such as tenant teardown.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    ///
+    /// assert!(map.force_remove(&1).await);
+    /// subscription.next().await;
+    /// assert!(subscription.is_removed());
+    /// # };
+    /// ```
+    pub async fn force_remove(&self, key: &K) -> bool {
+        let mut map = self.lock_entries(&self.0.entries()).await;
+
+        match map.remove(key) {
+            Some(entry) => {
+                self.detach(&mut map, key, entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// [Forcibly remove](Self::force_remove) all entries, returns the amount of removed entries.
+    ///
+    /// Entries are removed in order and in chunks of the
+    /// [`bulk_chunk_size`](crate::SubscriptionMapBuilder::bulk_chunk_size), the task yields
+    /// between them. Entries which are created in the meantime at keys which were already passed
+    /// are kept.
+    pub async fn clear(&self) -> usize {
+        let chunk = self.0.config.bulk_chunk_size;
+        let mut after = Bound::Unbounded;
+        let mut removed = 0;
+
+        loop {
+            let last = {
+                let mut map = self.lock_entries(&self.0.entries()).await;
+                let keys: Vec<K> = map
+                    .range((after.clone(), Bound::Unbounded))
+                    .take(chunk)
+                    .map(|(key, _)| key.clone())
+                    .collect();
+
+                for key in keys.iter() {
+                    if let Some(entry) = map.remove(key) {
+                        self.detach(&mut map, key, entry);
+                    }
+                }
+
+                removed += keys.len();
+                keys.last().cloned().filter(|_| keys.len() == chunk)
+            };
+
+            match last {
+                Some(last) => after = Bound::Excluded(last),
+                None => return removed,
+            }
+
+            bulk::yield_now().await;
+        }
+    }
+
+    /// Mark the removed entry and wake its subscribers
+    fn detach(
+        &self,
+        map: &mut BTreeMap<K, SubscriptionEntry<V>>,
+        key: &K,
+        mut entry: SubscriptionEntry<V>,
+    ) {
+        diag::debug!("forcibly removing entry {:?}", self.redacted_key(key));
+
+        entry.removed.store(true, Ordering::SeqCst);
+        entry.observable.modify(|_| {});
+        entry.invalidation.publish(());
+
+        self.record_removal(map, key, &entry);
+    }
+}
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Check if the entry was [forcibly removed](SubscriptionMap::force_remove).
+    ///
+    /// Waiting subscribers are woken with the unchanged value once that happens, publishes of
+    /// removed refs no longer reach anyone else, including the [cdc streams] and [rates] of the
+    /// key.
+    ///
+    /// [cdc streams]: SubscriptionMap::cdc_stream
+    /// [rates]: SubscriptionMap::rate
+    pub fn is_removed(&self) -> bool {
+        self.removed.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{AllKeys, SubscriptionMap};
+    use futures::{FutureExt, StreamExt};
+
+    #[async_std::test]
+    async fn should_wake_subscribers_of_removed_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 5).await.unwrap();
+        map.pin(2, 0).await.unwrap();
+        assert!(!map.force_remove(&3).await);
+
+        let waiting = async_std::task::spawn({
+            let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+            async move {
+                let value = subscription.next().await;
+                (value, subscription.is_removed())
+            }
+        });
+
+        assert!(map.force_remove(&1).await);
+        assert_eq!(waiting.await, (5, true));
+        assert!(!map.entries_snapshot().await.contains_key(&1));
+
+        // the detached ref neither affects nor is affected by a new entry
+        let fresh = map.get_or_insert(1, 0).await.unwrap();
+        subscription.publish(7).unwrap();
+        assert_eq!(fresh.latest(), 0);
+        drop(subscription);
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 1);
+
+        assert_eq!(map.clear().await, 2);
+        assert!(fresh.is_removed());
+        assert!(map.is_empty().await);
+    }
+
+    #[async_std::test]
+    async fn should_keep_publishes_of_removed_refs_from_new_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut changes = map.cdc_stream(AllKeys);
+        let mut removed = map.get_or_insert(1, 0).await.unwrap();
+        assert!(map.force_remove(&1).await);
+
+        let mut fresh = map.get_or_insert(1, 5).await.unwrap();
+        map.keep_history(&1, 2).await;
+        let initial = fresh.latest_versioned();
+
+        removed.publish(1).unwrap();
+        assert_eq!(fresh.latest_versioned(), initial);
+        assert_eq!(fresh.history(), vec![5]);
+        assert!(changes.next().now_or_never().is_none());
+
+        // buffered values would be published to the new entry
+        map.pause();
+        removed.publish(2).unwrap();
+        map.resume().await;
+        assert_eq!(removed.latest(), 2);
+        assert_eq!(fresh.latest_versioned(), initial);
+        assert!(changes.next().now_or_never().is_none());
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{history, SubscriptionMap, SubscriptionRef, Versioned};
+use std::collections::VecDeque;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+/// An item of a resumed subscription, see [`SubscriptionMap::subscribe_from_version`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resumed<V> {
+    /// A value published after the version the subscription resumed from
+    Value(Versioned<V>),
+    /// Values published after the version may have been dropped from the history, the following
+    /// values are the oldest ones still known
+    Gap,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Subscribe to an existing entry starting after the version a subscriber saw last.
+    ///
+    /// The values published since are replayed from the [history](Self::keep_history) of the
+    /// entry before the subscription goes live. If the history doesn't reach back to the
+    /// version, or none is kept, a [`Resumed::Gap`] precedes the values which are still known.
+    /// Returns `None` under the same conditions as [`get`](Self::get).
+    ///
+    /// ```
+    /// # use async_subscription_map::{Resumed, SubscriptionMap};
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let mut producer = map.get_or_insert("feed", 0).await.unwrap();
+    /// map.keep_history(&"feed", 8).await;
+    ///
+    /// producer.publish(1).unwrap();
+    /// let seen = producer.latest_versioned();
+    /// producer.publish(2).unwrap();
+    ///
+    /// let mut resumed = map.subscribe_from_version(&"feed", seen.version).await.unwrap();
+    /// assert!(matches!(resumed.next().await, Resumed::Value(v) if v.value == 2));
+    ///
+    /// producer.publish(3).unwrap();
+    /// assert!(matches!(resumed.next().await, Resumed::Value(v) if v.value == 3));
+    /// # };
+    /// ```
+    pub async fn subscribe_from_version(&self, key: &K, version: u64) -> Option<ResumedRef<K, V>> {
+        let mut subscription = self.get(key).await?;
+
+        // pending updates are part of the replay, live updates continue after them
+        subscription.synchronize();
+
+        let kept = history::lock(&subscription.meta)
+            .as_ref()
+            .map(|history| history.values.iter().cloned().collect::<Vec<_>>());
+        let values = kept.unwrap_or_else(|| vec![subscription.latest_versioned()]);
+
+        let mut replay = VecDeque::new();
+
+        // versions are shared by all keys, so values in between can only be ruled out if the
+        // history reaches back to the version itself
+        if values
+            .first()
+            .is_some_and(|oldest| oldest.version > version)
+        {
+            replay.push_back(Resumed::Gap);
+        }
+
+        let values = values.into_iter().filter(|value| value.version > version);
+        replay.extend(values.map(Resumed::Value));
+
+        let seen = replay
+            .iter()
+            .filter_map(|resumed| match resumed {
+                Resumed::Value(value) => Some(value.version),
+                Resumed::Gap => None,
+            })
+            .fold(version, u64::max);
+
+        Some(ResumedRef {
+            subscription,
+            replay,
+            seen,
+        })
+    }
+}
+
+/// A subscription which replays missed values before it goes live, see
+/// [`SubscriptionMap::subscribe_from_version`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct ResumedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscription: SubscriptionRef<K, V>,
+    /// The items which weren't received yet
+    replay: VecDeque<Resumed<V>>,
+    /// The latest version which was replayed or received, older ones are skipped
+    seen: u64,
+}
+
+impl<K, V> ResumedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// Check if all missed values were received and the subscription is live
+    pub fn is_live(&self) -> bool {
+        self.replay.is_empty()
+    }
+
+    /// Return the next replayed item, once all of them were received wait until a new version
+    /// is published.
+    ///
+    /// Every version is yielded at most once, even if a publish raced with the replay.
+    pub async fn next(&mut self) -> Resumed<V> {
+        if let Some(resumed) = self.replay.pop_front() {
+            return resumed;
+        }
+
+        loop {
+            let next = self.subscription.next_versioned().await;
+
+            if next.version > self.seen {
+                self.seen = next.version;
+                return Resumed::Value(next);
+            }
+        }
+    }
+
+    /// Drop the remaining replay and return the live subscription
+    pub fn into_inner(self) -> SubscriptionRef<K, V> {
+        self.subscription
+    }
+}
+
+impl<K, V> Debug for ResumedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResumedRef")
+            .field("subscription", &self.subscription)
+            .field("replay", &self.replay.len())
+            .field("seen", &self.seen)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Resumed;
+    use crate::{SubscriptionMap, Versioned};
+    use futures::FutureExt;
+
+    fn values<V>(replay: &[Resumed<V>]) -> Vec<Option<&V>> {
+        replay
+            .iter()
+            .map(|resumed| match resumed {
+                Resumed::Value(Versioned { value, .. }) => Some(value),
+                Resumed::Gap => None,
+            })
+            .collect()
+    }
+
+    #[async_std::test]
+    async fn should_replay_values_since_version() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        map.keep_history(&1, 4).await;
+
+        producer.publish(1).unwrap();
+        let seen = producer.latest_versioned().version;
+        producer.publish(2).unwrap();
+        producer.publish(3).unwrap();
+
+        let mut resumed = map.subscribe_from_version(&1, seen).await.unwrap();
+        let replay = [resumed.next().await, resumed.next().await];
+        assert_eq!(values(&replay), vec![Some(&2), Some(&3)]);
+        assert!(resumed.is_live());
+        assert!(resumed.next().now_or_never().is_none());
+
+        producer.publish(4).unwrap();
+        assert_eq!(values(&[resumed.next().await]), vec![Some(&4)]);
+    }
+
+    #[async_std::test]
+    async fn should_go_live_if_nothing_was_missed() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let producer = map.get_or_insert(1, 0).await.unwrap();
+        let seen = producer.latest_versioned().version;
+
+        let mut resumed = map.subscribe_from_version(&1, seen).await.unwrap();
+        assert!(resumed.is_live());
+        assert!(resumed.next().now_or_never().is_none());
+        assert!(map.subscribe_from_version(&2, seen).await.is_none());
+    }
+
+    #[async_std::test]
+    async fn should_mark_gaps_beyond_the_history() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        let seen = producer.latest_versioned().version;
+
+        producer.publish(1).unwrap();
+        producer.publish(2).unwrap();
+
+        let mut resumed = map.subscribe_from_version(&1, seen).await.unwrap();
+        let replay = [resumed.next().await, resumed.next().await];
+        assert_eq!(values(&replay), vec![None, Some(&2)]);
+
+        map.keep_history(&1, 2).await;
+        for value in 3..=5 {
+            producer.publish(value).unwrap();
+        }
+
+        let mut resumed = map.subscribe_from_version(&1, seen).await.unwrap();
+        let replay = [
+            resumed.next().await,
+            resumed.next().await,
+            resumed.next().await,
+        ];
+        assert_eq!(values(&replay), vec![None, Some(&4), Some(&5)]);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::{diag, SubscriptionMap};
+use async_observable::Observable;
+use futures::future::{self, Either};
+use std::collections::btree_map;
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// How long an unreferenced entry is kept
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Retention {
+    Until(Instant),
+    /// The end of the retention can't be represented, e.g. for a ttl of [`Duration::MAX`]
+    Forever,
+}
+
+impl Retention {
+    /// Retain for the duration starting now
+    pub fn after(now: Instant, duration: Duration) -> Self {
+        now.checked_add(duration).map_or(Self::Forever, Self::Until)
+    }
+
+    pub fn has_passed(self, now: Instant) -> bool {
+        matches!(self, Self::Until(until) if until <= now)
+    }
+
+    /// The instant the retention ends, if it ever does
+    pub fn deadline(self) -> Option<Instant> {
+        match self {
+            Self::Until(until) => Some(until),
+            Self::Forever => None,
+        }
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Ensure the entries exist and keep them for at least the ttl, even if no one subscribes.
+    ///
+    /// Anticipated subscribers, e.g. after a deploy, thereby find warm state instead of racing
+    /// the producers. Existing entries keep their values and have their retention extended.
+    /// Expired entries are removed by [`sweep`](Self::sweep) or [`maintain`](Self::maintain).
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # use std::time::Duration;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// map.warm([(1, 10), (2, 20)], Duration::from_secs(60)).await.unwrap();
+    ///
+    /// let subscription = map.get_or_insert(1, 0).await.unwrap();
+    /// assert_eq!(subscription.latest(), 10);
+    /// # };
+    /// ```
+    pub async fn warm<I>(&self, pairs: I, ttl: Duration) -> anyhow::Result<()>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut map = self.lock_entries(&self.0.entries()).await;
+        let now = self.0.config.clock.now();
+        let until = Retention::after(now, ttl);
+
+        // the maintenance future waits for the lock before it looks at the new deadlines
+        self.0.deadlines_changed.clone().publish(());
+
+        for (key, value) in pairs {
+            if !map.contains_key(&key) {
+                self.check_value_size(&key, &value)?;
+                self.make_room(&mut map, &key)?;
+            }
+
+            let entry = match map.entry(key) {
+                btree_map::Entry::Occupied(entry) => entry.into_mut(),
+                btree_map::Entry::Vacant(entry) => {
+                    let new = self.new_entry(entry.key(), value, now);
+                    entry.insert(new)
+                }
+            };
+
+            entry.retain_until = entry.retain_until.max(Some(until));
+        }
+
+        Ok(())
+    }
+
+    /// Release expired leases, publish the values of ended coalescing windows, finish releases of
+    /// refs which were dropped while the entries were locked and remove all unreferenced entries
+    /// whose retention expired, returns the amount of removed entries.
+    pub async fn sweep(&self) -> usize {
+        self.sweep_expired().await.0
+    }
+
+    /// Sweep expired entries and return the amount of removed entries and the next deadline
+    async fn sweep_expired(&self) -> (usize, Option<Instant>) {
+        // released leases lock the entries to decrement their count
+        let next_lease = self.release_expired_leases();
+        let next_flush = self.flush_coalesced().await;
+        let next_release = self.collect_deferred().await;
+
+        let mut map = self.lock_entries(&self.0.entries()).await;
+        let now = self.0.config.clock.now();
+
+        let expired: Vec<K> = map
+            .iter()
+            .filter(|(_, entry)| entry.retain_until.is_some() && entry.removable(now))
+            .map(|(key, _)| key.clone())
+            .filter(|key| !self.is_excluded_from_cleanup(key))
+            .collect();
+
+        for key in expired.iter() {
+            if let Some(entry) = map.remove(key) {
+                self.record_removal(&mut map, key, &entry);
+            }
+        }
+
+        let next = map
+            .values()
+            .filter_map(|entry| entry.retain_until)
+            .filter_map(Retention::deadline)
+            .filter(|until| *until > now)
+            .chain(next_lease)
+            .chain(next_flush)
+            .chain(next_release)
+            .min();
+
+        (expired.len(), next)
+    }
+
+    /// Sweep whenever a retention, lease or coalescing deadline passes or a dropped ref couldn't
+    /// be released right away, resolves once the map is closed.
+    ///
+    /// The map doesn't spawn tasks on its own, spawn this on the runtime of your choice if
+    /// retention is used. Timing is driven by the [`Clock`](crate::Clock) of the map. The future
+    /// doesn't keep the map open, it finishes with [`MapEvent::Closed`](crate::MapEvent).
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// async_std::task::spawn(map.maintain());
+    /// ```
+    pub fn maintain(&self) -> impl Future<Output = ()> {
+        let map = self.internal();
+        let mut changed = self.0.deadlines_changed.clone();
+
+        async move {
+            // closing notifies the deadlines, so the check is never missed
+            while !map.is_closed() {
+                map.maintain_once(&mut changed).await;
+            }
+
+            diag::debug!("subscription map closed, maintenance finished");
+        }
+    }
+
+    /// Sweep and wait for the next deadline or change of the deadlines
+    async fn maintain_once(&self, changed: &mut Observable<()>) {
+        let (removed, next) = self.sweep_expired().await;
+
+        if removed > 0 {
+            diag::debug!("swept {} expired entries", removed);
+        }
+
+        let notified = changed.next();
+        futures::pin_mut!(notified);
+
+        match next {
+            Some(deadline) => {
+                let sleep = self.0.config.clock.sleep_until(deadline);
+
+                if let Either::Left(_) = future::select(sleep, notified).await {
+                    diag::trace!("retention deadline reached");
+                }
+            }
+            None => notified.await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_retain_warm_entries_until_ttl() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .build();
+
+        map.warm([(1, 1)], Duration::from_secs(10)).await.unwrap();
+        drop(map.get_or_insert(1, 0).await.unwrap());
+        assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 0);
+        assert_eq!(map.sweep().await, 0);
+
+        clock.advance(Duration::from_secs(5));
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(subscription.latest(), 1);
+
+        // referenced entries are removed on drop once their retention expired
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(map.sweep().await, 0);
+        drop(subscription);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+
+        map.warm([(2, 2)], Duration::from_secs(1)).await.unwrap();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(map.sweep().await, 1);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_retain_warm_entries_forever_if_the_ttl_overflows() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .build();
+
+        map.warm([(1, 1)], Duration::MAX).await.unwrap();
+        map.warm([(1, 1)], Duration::from_secs(1)).await.unwrap();
+
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(map.sweep().await, 0);
+        assert!(map.entries_snapshot().await.contains_key(&1));
+    }
+
+    #[async_std::test]
+    async fn should_keep_unreferenced_entries_alive() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .keep_alive(Duration::from_secs(10))
+            .build();
+
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+        subscription.publish(1).unwrap();
+        drop(subscription);
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(map.sweep().await, 0);
+        drop(map.get_or_insert(1, 0).await.unwrap());
+
+        // the grace period starts again with every release
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(map.sweep().await, 0);
+        assert_eq!(map.get_or_insert(1, 0).await.unwrap().latest(), 1);
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(map.sweep().await, 1);
+
+        map.config_handle().set_keep_alive(None);
+        drop(map.get_or_insert(1, 0).await.unwrap());
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_keep_entries_forever_if_the_grace_period_overflows() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .keep_alive(Duration::MAX)
+            .build();
+
+        drop(map.get_or_insert(1, 0).await.unwrap());
+
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(map.sweep_expired().await, (0, None));
+        assert!(map.entries_snapshot().await.contains_key(&1));
+    }
+
+    #[async_std::test]
+    async fn should_sweep_in_maintenance_future() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .build();
+
+        async_std::task::spawn(map.maintain());
+
+        map.warm([(1, 1)], Duration::from_secs(10)).await.unwrap();
+        clock.advance(Duration::from_secs(10));
+
+        // the maintenance future runs on another thread in real time
+        for _ in 0..1000 {
+            if map.entries_snapshot().await.is_empty() {
+                return;
+            }
+
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+
+        panic!("expired entry was not swept");
+    }
+
+    #[async_std::test]
+    async fn should_finish_maintenance_future_once_closed() {
+        let map = SubscriptionMap::<usize, usize>::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let maintenance = async_std::task::spawn(map.maintain());
+
+        drop(map);
+        async_std::future::timeout(Duration::from_secs(1), maintenance)
+            .await
+            .expect("maintenance future kept running after the map was closed");
+        assert_eq!(subscription.latest(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_finish_deferred_release_in_maintenance_future() {
+        let map = SubscriptionMap::<usize, usize>::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        async_std::task::spawn(map.maintain());
+
+        let entries = map.0.entries().write_arc().await;
+        drop(subscription);
+        drop(entries);
+
+        for _ in 0..1000 {
+            let removed = match map.0.entries().try_write_arc() {
+                Some(entries) => entries.is_empty(),
+                None => false,
+            };
+
+            if removed {
+                return;
+            }
+
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+
+        panic!("deferred release was not finished");
+    }
+}
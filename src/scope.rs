@@ -0,0 +1,231 @@
+use crate::diag;
+use crate::lease::Lease;
+use crate::{Error, LeasedRef, SubscriptionMap};
+use anyhow::Context as _;
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+
+/// Subscriptions whose lifetime is bound to a task.
+///
+/// All refs obtained through a scope are released as soon as the future returned by
+/// [`bind`](Self::bind) completes, panics or is dropped, e.g. because the task was aborted. Refs
+/// which are still alive at that point were orphaned by their task, e.g. because they were moved
+/// into a leaked structure, hence a diagnostic naming their key is emitted. Released refs fail
+/// with [`Error::LeaseExpired`].
+///
+/// ```
+/// # use async_subscription_map::SubscriptionMap;
+/// # async {
+/// let map = SubscriptionMap::<usize, usize>::default();
+/// let scope = map.task_scope();
+///
+/// async_std::task::spawn(scope.clone().bind(async move {
+///     let subscription = scope.get_or_insert(1, 0).await.unwrap();
+///     // forgetting the ref would keep the entry alive forever, unless it is bound to the task
+///     std::mem::forget(subscription);
+/// }))
+/// .await;
+/// # };
+/// ```
+#[derive(Clone)]
+pub struct TaskScope<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    map: SubscriptionMap<K, V>,
+    inner: Arc<ScopeState<K, V>>,
+}
+
+struct ScopeState<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    ended: AtomicBool,
+    leases: Mutex<Vec<Weak<Lease<K, V>>>>,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Create a scope to bind subscriptions to the lifetime of a task
+    pub fn task_scope(&self) -> TaskScope<K, V> {
+        TaskScope {
+            map: self.clone(),
+            inner: Arc::new(ScopeState {
+                ended: AtomicBool::new(false),
+                leases: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+}
+
+impl<K, V> TaskScope<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Same as [`SubscriptionMap::get_or_insert`] but the ref is released once the scope ends.
+    pub async fn get_or_insert(&self, key: K, value: V) -> anyhow::Result<LeasedRef<K, V>> {
+        if self.inner.ended.load(Ordering::SeqCst) {
+            return Err(Error::LeaseExpired).with_context(|| {
+                format!(
+                    "task scope ended before subscribing to {:?}",
+                    self.map.redacted_key(&key)
+                )
+            });
+        }
+
+        let subscription = self.map.get_or_insert(key, value).await?;
+        let (lease, leased) = self.map.lease(subscription, None);
+
+        let mut leases = self.inner.leases.lock().unwrap_or_else(|e| e.into_inner());
+        leases.retain(|lease| lease.strong_count() > 0);
+        leases.push(Arc::downgrade(&lease));
+
+        Ok(leased)
+    }
+
+    /// Run the future and end the scope once it completes or is dropped
+    pub fn bind<F>(self, future: F) -> Bound<K, V, F>
+    where
+        F: Future,
+    {
+        Bound {
+            scope: self,
+            future: Box::pin(future),
+        }
+    }
+
+    /// Release all refs which are still alive
+    fn end(&self) {
+        if self.inner.ended.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let leases =
+            std::mem::take(&mut *self.inner.leases.lock().unwrap_or_else(|e| e.into_inner()));
+
+        for lease in leases.iter().filter_map(Weak::upgrade) {
+            if let Some(subscription) = lease.release() {
+                diag::warning!(
+                    "releasing ref of key {:?} orphaned by its task",
+                    self.map.redacted_key(&subscription.key)
+                );
+            }
+        }
+    }
+}
+
+impl<K, V> Debug for TaskScope<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskScope")
+            .field("ended", &self.inner.ended.load(Ordering::SeqCst))
+            .finish()
+    }
+}
+
+/// A future which ends its [`TaskScope`] once it completes or is dropped
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Bound<K, V, F>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    scope: TaskScope<K, V>,
+    future: Pin<Box<F>>,
+}
+
+impl<K, V, F> Future for Bound<K, V, F>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let output = self.future.as_mut().poll(cx);
+
+        if output.is_ready() {
+            self.scope.end();
+        }
+
+        output
+    }
+}
+
+impl<K, V, F> Drop for Bound<K, V, F>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn drop(&mut self) {
+        self.scope.end();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+    use futures::FutureExt;
+
+    #[async_std::test]
+    async fn should_release_forgotten_refs_when_task_ends() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let scope = map.task_scope();
+
+        let subscription = scope
+            .clone()
+            .bind(async {
+                let subscription = scope.get_or_insert(1, 0).await.unwrap();
+                std::mem::forget(scope.get_or_insert(1, 0).await.unwrap());
+                assert_eq!(map.entries_snapshot().await.get(&1).unwrap().rc(), 2);
+                subscription
+            })
+            .await;
+
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+
+        let err = subscription.latest().unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::LeaseExpired));
+        assert!(scope.get_or_insert(1, 0).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn should_release_refs_of_panicking_tasks() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let scope = map.task_scope();
+
+        let result = std::panic::AssertUnwindSafe(scope.clone().bind(async {
+            std::mem::forget(scope.get_or_insert(1, 0).await.unwrap());
+            panic!("task failed");
+        }))
+        .catch_unwind()
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_release_dropped_refs_immediately() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let scope = map.task_scope();
+
+        drop(scope.get_or_insert(1, 0).await.unwrap());
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::SubscriptionMap;
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::io::Read;
+
+/// The formats [`seed_from_reader`](SubscriptionMap::seed_from_reader) parses, each behind the
+/// feature of the same name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SeedFormat {
+    /// A JSON object mapping keys to values
+    #[cfg(feature = "json")]
+    Json,
+    /// A TOML document whose top level table maps keys to values
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord + DeserializeOwned,
+    V: Clone + Debug + DeserializeOwned,
+{
+    /// [Seed](Self::seed) the map with the pairs of a configuration file or environment dump.
+    ///
+    /// Operators preload feature flags and config keys this way without a custom loader per
+    /// service. The whole document is parsed before anything is pinned, so a malformed one
+    /// leaves the map untouched.
+    ///
+    /// ```
+    /// # use async_subscription_map::{SeedFormat, SubscriptionMap};
+    /// # #[cfg(feature = "json")]
+    /// # async {
+    /// let map = SubscriptionMap::<String, bool>::default();
+    /// let config = r#"{ "flags/dark-mode": true, "flags/beta": false }"#;
+    ///
+    /// map.seed_from_reader(config.as_bytes(), SeedFormat::Json)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(map.peek(&"flags/dark-mode".to_string()).await, Some(true));
+    /// # };
+    /// ```
+    pub async fn seed_from_reader<R>(&self, reader: R, format: SeedFormat) -> anyhow::Result<()>
+    where
+        R: Read,
+    {
+        let pairs = parse(reader, format)?;
+        self.seed(pairs).await
+    }
+}
+
+fn parse<K, V, R>(reader: R, format: SeedFormat) -> anyhow::Result<BTreeMap<K, V>>
+where
+    K: Ord + DeserializeOwned,
+    V: DeserializeOwned,
+    R: Read,
+{
+    match format {
+        #[cfg(feature = "json")]
+        SeedFormat::Json => serde_json::from_reader(reader).context("unable to parse json seed"),
+        #[cfg(feature = "toml")]
+        SeedFormat::Toml => {
+            let mut reader = reader;
+            let mut document = String::new();
+            reader
+                .read_to_string(&mut document)
+                .context("unable to read toml seed")?;
+
+            toml::from_str(&document).context("unable to parse toml seed")
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SeedFormat;
+    use crate::SubscriptionMap;
+
+    #[cfg(feature = "json")]
+    #[async_std::test]
+    async fn should_seed_pinned_entries_from_json() {
+        let map = SubscriptionMap::<String, usize>::new();
+        let seed = r#"{ "retries": 3, "timeout": 10 }"#;
+
+        map.seed_from_reader(seed.as_bytes(), SeedFormat::Json)
+            .await
+            .unwrap();
+        assert_eq!(map.peek(&"retries".to_string()).await, Some(3));
+
+        drop(map.get_or_insert("timeout".to_string(), 0).await.unwrap());
+        assert_eq!(map.peek(&"timeout".to_string()).await, Some(10));
+    }
+
+    #[cfg(feature = "toml")]
+    #[async_std::test]
+    async fn should_seed_pinned_entries_from_toml() {
+        let map = SubscriptionMap::<String, usize>::new();
+        let seed = "retries = 3\ntimeout = 10\n";
+
+        map.seed_from_reader(seed.as_bytes(), SeedFormat::Toml)
+            .await
+            .unwrap();
+        assert_eq!(map.peek(&"timeout".to_string()).await, Some(10));
+    }
+
+    #[cfg(feature = "json")]
+    #[async_std::test]
+    async fn should_not_seed_malformed_documents() {
+        let map = SubscriptionMap::<String, usize>::new();
+        let seed = r#"{ "retries": 3, "timeout": "ten" }"#;
+
+        assert!(map
+            .seed_from_reader(seed.as_bytes(), SeedFormat::Json)
+            .await
+            .is_err());
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+}
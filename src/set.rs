@@ -0,0 +1,136 @@
+use crate::{Mux, SubscriptionMap};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Default,
+{
+    /// Create an empty [`SubscriptionSet`] whose keys are changed at runtime.
+    pub fn subscription_set(&self) -> SubscriptionSet<K, V> {
+        SubscriptionSet {
+            owner: self.internal(),
+            mux: Mux::new(),
+        }
+    }
+}
+
+/// A combined subscription to a changing set of keys, see [`SubscriptionMap::subscription_set`].
+///
+/// Unlike [`select`](SubscriptionMap::select) keys are added and removed while the set is in
+/// use, e.g. as clients change the symbols they watch. Waiting through [`next`](Self::next) may
+/// be cancelled at any time to change the set, no publish is lost in between.
+///
+/// ```
+/// # use async_subscription_map::SubscriptionMap;
+/// # async {
+/// let map = SubscriptionMap::<&'static str, u64>::default();
+/// let mut watched = map.subscription_set();
+///
+/// watched.insert("BTC").await.unwrap();
+/// watched.insert("ETH").await.unwrap();
+/// watched.remove(&"BTC");
+///
+/// map.publish_if_changed(&"ETH", 1800).await.unwrap();
+/// assert_eq!(watched.next().await, Some(("ETH", 1800)));
+/// assert_eq!(map.peek(&"BTC").await, None);
+/// # };
+/// ```
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct SubscriptionSet<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    owner: SubscriptionMap<K, V>,
+    mux: Mux<K, K, V>,
+}
+
+impl<K, V> SubscriptionSet<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Default,
+{
+    /// Subscribe to the key, the entry is created with the default value if it doesn't exist yet.
+    ///
+    /// Returns if the key was added, keys which are already in the set keep their subscription.
+    pub async fn insert(&mut self, key: K) -> anyhow::Result<bool> {
+        if self.mux.contains(&key) {
+            return Ok(false);
+        }
+
+        let subscription = self.owner.get_or_insert(key.clone(), V::default()).await?;
+        self.mux.insert(key, subscription);
+
+        Ok(true)
+    }
+
+    /// Release the subscription to the key, returns if it was in the set.
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.mux.complete(key)
+    }
+
+    /// Check if the key is in the set
+    pub fn contains(&self, key: &K) -> bool {
+        self.mux.contains(key)
+    }
+
+    /// The amount of keys in the set
+    pub fn len(&self) -> usize {
+        self.mux.len()
+    }
+
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.mux.is_empty()
+    }
+
+    /// Wait until any key of the set is published and return it together with the new value,
+    /// `None` if the set is empty.
+    pub async fn next(&mut self) -> Option<(K, V)> {
+        self.mux.next().await
+    }
+}
+
+impl<K, V> Debug for SubscriptionSet<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubscriptionSet")
+            .field("subscriptions", &self.mux)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+    use futures::FutureExt;
+
+    #[async_std::test]
+    async fn should_change_keys_while_waiting() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut set = map.subscription_set();
+        assert_eq!(set.next().await, None);
+
+        assert!(set.insert(1).await.unwrap());
+        assert!(!set.insert(1).await.unwrap());
+        assert!(set.next().now_or_never().is_none());
+
+        // a publish while the set is changed is still yielded afterwards
+        map.publish_if_changed(&1, 1).await.unwrap();
+        set.insert(2).await.unwrap();
+        assert_eq!(set.next().await, Some((1, 1)));
+
+        assert!(set.remove(&1));
+        assert!(!set.contains(&1));
+        assert_eq!(map.keys().await, vec![2]);
+
+        map.publish_if_changed(&2, 2).await.unwrap();
+        assert_eq!(set.next().await, Some((2, 2)));
+        assert_eq!(set.len(), 1);
+    }
+}
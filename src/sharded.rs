@@ -0,0 +1,216 @@
+use crate::{ReadOnlyRef, SubscriptionMap, SubscriptionRef};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::Arc;
+
+type Router<K> = Arc<dyn Fn(&K) -> usize + Send + Sync>;
+
+/// A composite map which routes every key to one of several independent shards.
+///
+/// Each shard is a regular [`SubscriptionMap`] with its own lock and configuration, e.g. keys of
+/// different enum variants can use different clocks, redactors or retention. The router maps a
+/// key to the index of its shard and must always return the same index for the same key.
+///
+/// ```
+/// # use async_subscription_map::{FullRedaction, ShardedSubscriptionMap, SubscriptionMap};
+/// #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// enum Key {
+///     Session(u64),
+///     Config(&'static str),
+/// }
+///
+/// # async {
+/// let map = ShardedSubscriptionMap::new(
+///     |key: &Key| match key {
+///         Key::Session(_) => 0,
+///         Key::Config(_) => 1,
+///     },
+///     [
+///         SubscriptionMap::builder().redactor(FullRedaction).build(),
+///         SubscriptionMap::new(),
+///     ],
+/// );
+///
+/// let session = map.get_or_insert(Key::Session(1), 0).await.unwrap();
+/// assert!(map.shard(&Key::Session(2)).describe().await.get(&Key::Session(1)).is_some());
+/// assert!(map.shard(&Key::Config("limits")).describe().await.is_empty());
+/// # };
+/// ```
+#[derive(Clone)]
+pub struct ShardedSubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    router: Router<K>,
+    shards: Arc<[SubscriptionMap<K, V>]>,
+}
+
+impl<K, V> ShardedSubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Compose the shards into one map, keys are routed to the shard at the returned index.
+    ///
+    /// Panics if there are no shards.
+    pub fn new<R, I>(router: R, shards: I) -> Self
+    where
+        R: Fn(&K) -> usize + Send + Sync + 'static,
+        I: IntoIterator<Item = SubscriptionMap<K, V>>,
+    {
+        let shards: Arc<[SubscriptionMap<K, V>]> = shards.into_iter().collect();
+        assert!(!shards.is_empty(), "a sharded map needs at least one shard");
+
+        Self {
+            router: Arc::new(router),
+            shards,
+        }
+    }
+
+    /// The shard responsible for the key.
+    ///
+    /// Panics if the router returns an index out of bounds.
+    pub fn shard(&self, key: &K) -> &SubscriptionMap<K, V> {
+        let index = (self.router)(key);
+
+        self.shards.get(index).unwrap_or_else(|| {
+            panic!(
+                "key {:?} routed to shard {} but only {} shards exist",
+                key,
+                index,
+                self.shards.len()
+            )
+        })
+    }
+
+    /// All shards in routing order
+    pub fn shards(&self) -> &[SubscriptionMap<K, V>] {
+        &self.shards
+    }
+
+    /// Same as [`SubscriptionMap::get_or_insert`] on the shard of the key.
+    pub async fn get_or_insert(&self, key: K, value: V) -> anyhow::Result<SubscriptionRef<K, V>> {
+        self.shard(&key).get_or_insert(key, value).await
+    }
+
+    /// Same as [`SubscriptionMap::get_or_insert_read_only`] on the shard of the key.
+    pub async fn get_or_insert_read_only(
+        &self,
+        key: K,
+        value: V,
+    ) -> anyhow::Result<ReadOnlyRef<K, V>> {
+        self.shard(&key).get_or_insert_read_only(key, value).await
+    }
+
+    /// Same as [`SubscriptionMap::try_modify_and_publish`] on the shard of the key.
+    pub async fn try_modify_and_publish<F, R, E>(&self, key: &K, modify: F) -> anyhow::Result<R>
+    where
+        F: FnOnce(&mut V) -> Result<R, E>,
+        E: Into<anyhow::Error>,
+    {
+        self.shard(key).try_modify_and_publish(key, modify).await
+    }
+
+    /// Same as [`SubscriptionMap::publish`] on the shard of the key.
+    pub async fn publish(&self, key: &K, value: V) -> anyhow::Result<()> {
+        self.shard(key).publish(key, value).await
+    }
+}
+
+impl<K, V> ShardedSubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Eq,
+{
+    /// Same as [`SubscriptionMap::publish_if_changed`] on the shard of the key.
+    pub async fn publish_if_changed(&self, key: &K, value: V) -> anyhow::Result<bool> {
+        self.shard(key).publish_if_changed(key, value).await
+    }
+
+    /// Same as [`SubscriptionMap::modify_and_publish`] on the shard of the key.
+    pub async fn modify_and_publish<F, R>(&self, key: &K, modify: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut V) -> R,
+    {
+        self.shard(key).modify_and_publish(key, modify).await
+    }
+}
+
+impl<K, V> Debug for ShardedSubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ShardedSubscriptionMap")
+            .field(&self.shards)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ShardedSubscriptionMap;
+    use crate::SubscriptionMap;
+
+    fn by_parity() -> ShardedSubscriptionMap<usize, usize> {
+        ShardedSubscriptionMap::new(
+            |key: &usize| key % 2,
+            [SubscriptionMap::new(), SubscriptionMap::new()],
+        )
+    }
+
+    #[async_std::test]
+    async fn should_route_keys_to_shards() {
+        let map = by_parity();
+        let mut even = map.get_or_insert(2, 0).await.unwrap();
+        let _odd = map.get_or_insert(3, 0).await.unwrap();
+
+        assert_eq!(map.shards()[0].entries_snapshot().await.len(), 1);
+        assert!(map.shards()[1].entries_snapshot().await.contains_key(&3));
+
+        assert!(map.publish_if_changed(&2, 1).await.unwrap());
+        assert_eq!(even.next().await, 1);
+        assert!(map.publish_if_changed(&4, 1).await.is_err());
+
+        drop(even);
+        assert_eq!(map.shards()[0].entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_isolate_shard_state() {
+        let map = by_parity();
+        let _even = map.get_or_insert(2, 0).await.unwrap();
+
+        map.shard(&1).drain();
+        assert!(map.get_or_insert(1, 0).await.is_err());
+        assert!(map.get_or_insert(4, 0).await.is_ok());
+    }
+
+    #[async_std::test]
+    async fn should_publish_values_without_eq() {
+        let map: ShardedSubscriptionMap<usize, f64> = ShardedSubscriptionMap::new(
+            |key: &usize| key % 2,
+            [SubscriptionMap::new(), SubscriptionMap::new()],
+        );
+        let mut odd = map.get_or_insert(1, 0.0).await.unwrap();
+
+        map.publish(&1, 0.5).await.unwrap();
+        assert_eq!(odd.next().await, 0.5);
+        assert!(map
+            .try_modify_and_publish(&1, |_| Err::<(), _>(std::fmt::Error))
+            .await
+            .is_err());
+        assert!(map.publish(&2, 0.5).await.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "routed to shard 2")]
+    fn should_panic_on_invalid_route() {
+        let map: ShardedSubscriptionMap<usize, usize> =
+            ShardedSubscriptionMap::new(|_: &usize| 2, [SubscriptionMap::new()]);
+
+        map.shard(&1);
+    }
+}
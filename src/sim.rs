@@ -0,0 +1,282 @@
+//! Deterministic single threaded simulation of concurrent map usage.
+//!
+//! The [`Simulation`] executor polls its tasks in an order decided by a seeded random number
+//! generator, the same seed always yields the same interleaving. This makes it possible to
+//! reproduce and regression test race dependent behavior such as a subscription racing the drop of
+//! the last ref to the same key.
+//!
+//! ```
+//! # use async_subscription_map::{sim::{self, Simulation}, SubscriptionMap};
+//! for seed in 0..100 {
+//!     let map = SubscriptionMap::<usize, usize>::default();
+//!     let mut simulation = Simulation::new(seed);
+//!
+//!     let first = map.clone();
+//!     simulation.spawn(async move {
+//!         let subscription = first.get_or_insert(1, 0).await.unwrap();
+//!         sim::yield_now().await;
+//!         drop(subscription);
+//!     });
+//!
+//!     let second = map.clone();
+//!     simulation.spawn(async move {
+//!         let subscription = second.get_or_insert(1, 0).await.unwrap();
+//!         sim::yield_now().await;
+//!         drop(subscription);
+//!     });
+//!
+//!     assert_eq!(simulation.run(), 0);
+//! }
+//! ```
+use crate::{Clock, ManualClock};
+use futures::future::LocalBoxFuture;
+use futures::task::{waker, ArcWake};
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// A deterministic executor for simulating concurrent tasks on a single thread.
+///
+/// If no task is runnable anymore but tasks are sleeping on the [`ManualClock`] of the simulation,
+/// the clock is advanced to the next deadline. Hence maps used inside of a simulation should be
+/// configured to use [`Simulation::clock`].
+pub struct Simulation {
+    rng: u64,
+    clock: ManualClock,
+    tasks: Vec<Option<LocalBoxFuture<'static, ()>>>,
+    ready: Arc<Mutex<BTreeSet<usize>>>,
+    trace: Vec<usize>,
+}
+
+struct TaskWaker {
+    id: usize,
+    ready: Arc<Mutex<BTreeSet<usize>>>,
+}
+
+impl ArcWake for TaskWaker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        lock(&arc_self.ready).insert(arc_self.id);
+    }
+}
+
+impl Simulation {
+    /// Create a simulation whose scheduling order is derived from the seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: seed_state(seed),
+            clock: ManualClock::new(),
+            tasks: Vec::new(),
+            ready: Arc::new(Mutex::new(BTreeSet::new())),
+            trace: Vec::new(),
+        }
+    }
+
+    /// The virtual clock of this simulation
+    pub fn clock(&self) -> ManualClock {
+        self.clock.clone()
+    }
+
+    /// Add a task to the simulation, tasks only make progress inside of [`Simulation::run`].
+    pub fn spawn<F>(&mut self, task: F)
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        let id = self.tasks.len();
+        self.tasks.push(Some(Box::pin(task)));
+        lock(&self.ready).insert(id);
+    }
+
+    /// Poll the tasks in seeded random order until all of them completed or none of them can make
+    /// progress anymore.
+    ///
+    /// Returns the number of tasks which did not complete, which is a deadlock if non zero.
+    pub fn run(&mut self) -> usize {
+        loop {
+            let ready = lock(&self.ready).iter().copied().collect::<Vec<_>>();
+
+            if ready.is_empty() {
+                match self.clock.next_deadline() {
+                    Some(deadline) => {
+                        let now = self.clock.now();
+                        self.clock.advance(deadline.saturating_duration_since(now));
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            let id = ready[(self.next_random() % ready.len() as u64) as usize];
+            lock(&self.ready).remove(&id);
+            self.poll(id);
+        }
+
+        self.tasks.iter().filter(|task| task.is_some()).count()
+    }
+
+    /// The ids of the polled tasks in order, tasks are numbered in the order they were spawned.
+    ///
+    /// Two simulations with the same seed and tasks produce the same trace.
+    pub fn trace(&self) -> &[usize] {
+        &self.trace
+    }
+
+    fn poll(&mut self, id: usize) {
+        let task = match self.tasks[id].as_mut() {
+            Some(task) => task,
+            None => return,
+        };
+
+        self.trace.push(id);
+
+        let waker = waker(Arc::new(TaskWaker {
+            id,
+            ready: self.ready.clone(),
+        }));
+
+        if task
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_ready()
+        {
+            self.tasks[id] = None;
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+}
+
+/// Spread the seed over the state of the xorshift generator with splitmix64, so neighbouring
+/// seeds schedule differently from the first step on
+fn seed_state(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+    // xorshift must not be seeded with zero, which splitmix64 maps a single seed to
+    match z ^ (z >> 31) {
+        0 => 0x9e37_79b9_7f4a_7c15,
+        state => state,
+    }
+}
+
+/// Give the simulation a chance to schedule another task at this point.
+pub async fn yield_now() {
+    YieldNow(false).await
+}
+
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(e) => e.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{seed_state, yield_now, Simulation};
+    use crate::{Clock, SubscriptionMap};
+    use std::time::Duration;
+
+    fn scenario(seed: u64) -> (SubscriptionMap<usize, usize>, Simulation) {
+        let map = SubscriptionMap::<usize, usize>::new();
+        let mut simulation = Simulation::new(seed);
+
+        for id in 0..4 {
+            let map = map.clone();
+
+            simulation.spawn(async move {
+                let mut subscription = map.get_or_insert(id % 2, 0).await.unwrap();
+                yield_now().await;
+                subscription.publish(id).unwrap();
+                yield_now().await;
+                drop(subscription);
+            });
+        }
+
+        (map, simulation)
+    }
+
+    #[async_std::test]
+    async fn should_reproduce_interleavings() {
+        for seed in 0..32 {
+            let (_, mut first) = scenario(seed);
+            let (_, mut second) = scenario(seed);
+
+            assert_eq!(first.run(), 0);
+            assert_eq!(second.run(), 0);
+            assert_eq!(first.trace(), second.trace());
+        }
+    }
+
+    #[async_std::test]
+    async fn should_vary_interleavings_by_seed() {
+        let traces = (0..32)
+            .map(|seed| {
+                let (_, mut simulation) = scenario(seed);
+                simulation.run();
+                simulation.trace().to_vec()
+            })
+            .collect::<std::collections::BTreeSet<_>>();
+
+        assert!(traces.len() > 1);
+    }
+
+    #[test]
+    fn should_never_seed_a_zero_state() {
+        for seed in [0, 1, u64::MAX, 0x9e37_79b9_7f4a_7c15] {
+            assert_ne!(seed_state(seed), 0);
+        }
+
+        let mut simulation = Simulation::new(0x9e37_79b9_7f4a_7c15);
+        assert_ne!(simulation.next_random(), simulation.next_random());
+    }
+
+    #[async_std::test]
+    async fn should_clean_up_in_every_interleaving() {
+        for seed in 0..256 {
+            let (map, mut simulation) = scenario(seed);
+            assert_eq!(simulation.run(), 0);
+            assert_eq!(map.entries_snapshot().await.len(), 0);
+        }
+    }
+
+    #[async_std::test]
+    async fn should_advance_virtual_time_when_stalled() {
+        let mut simulation = Simulation::new(0);
+        let clock = simulation.clock();
+        let start = clock.now();
+
+        let sleeper = clock.clone();
+        simulation.spawn(async move {
+            sleeper
+                .sleep_until(sleeper.now() + Duration::from_secs(3600))
+                .await;
+        });
+
+        assert_eq!(simulation.run(), 0);
+        assert_eq!(clock.now(), start + Duration::from_secs(3600));
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{Error, SubscriptionMap};
+use anyhow::Context;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Fail with [`Error::ValueTooLarge`] if the value exceeds the configured maximum size, see
+    /// [`max_value_size`](crate::SubscriptionMapBuilder::max_value_size).
+    pub(crate) fn check_value_size(&self, key: &K, value: &V) -> anyhow::Result<()> {
+        if let Some((limit, size)) = &self.0.config.max_value_size {
+            let size = size(value);
+
+            if size > *limit {
+                return Err(Error::ValueTooLarge).with_context(|| {
+                    format!(
+                        "value of {:?} has size {}, limit is {}",
+                        self.redacted_key(key),
+                        size,
+                        limit
+                    )
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, SubscriptionMap};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn bounded() -> SubscriptionMap<usize, Vec<u8>> {
+        SubscriptionMap::builder()
+            .max_value_size(4, |blob: &Vec<u8>| blob.len())
+            .build()
+    }
+
+    #[async_std::test]
+    async fn should_reject_oversized_publishes() {
+        let map = bounded();
+        let mut subscription = map.get_or_insert(1, vec![0]).await.unwrap();
+
+        let err = subscription.publish(vec![0; 5]).unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ValueTooLarge));
+
+        let err = subscription.modify(|blob| blob.extend([1; 4])).unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ValueTooLarge));
+        assert_eq!(subscription.latest(), vec![0]);
+
+        subscription.modify(|blob| blob.extend([1; 3])).unwrap();
+        assert_eq!(subscription.latest(), vec![0, 1, 1, 1]);
+    }
+
+    #[async_std::test]
+    async fn should_check_replacements_without_cloning() {
+        #[derive(Debug)]
+        struct Counted(Arc<AtomicUsize>, usize);
+
+        impl Clone for Counted {
+            fn clone(&self) -> Self {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Self(self.0.clone(), self.1)
+            }
+        }
+
+        let clones = Arc::new(AtomicUsize::new(0));
+        let map = SubscriptionMap::<usize, Counted>::builder()
+            .max_value_size(4, |value: &Counted| value.1)
+            .build();
+        let mut subscription = map
+            .get_or_insert(1, Counted(clones.clone(), 0))
+            .await
+            .unwrap();
+        clones.store(0, Ordering::SeqCst);
+
+        subscription.publish(Counted(clones.clone(), 4)).unwrap();
+        assert!(subscription.publish(Counted(clones.clone(), 5)).is_err());
+        assert_eq!(clones.load(Ordering::SeqCst), 0);
+
+        subscription.modify(|value| value.1 = 3).unwrap();
+        assert_eq!(clones.load(Ordering::SeqCst), 1);
+    }
+
+    #[async_std::test]
+    async fn should_reject_oversized_insertions() {
+        let map = bounded();
+
+        let err = map.get_or_insert(1, vec![0; 5]).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ValueTooLarge));
+
+        let err = map.pin(2, vec![0; 5]).await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Error>(), Some(&Error::ValueTooLarge));
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+}
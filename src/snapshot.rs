@@ -0,0 +1,616 @@
+use crate::redact::{Redacted, Redactor};
+use crate::SubscriptionMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// The state of a single entry at the time a snapshot was taken.
+///
+/// Snapshots taken by a map remember how it formats the key and the value of the entry, so their
+/// `Debug` output and diffs respect its [redactors](crate::SubscriptionMapBuilder::redactor).
+/// Equality only considers the state of the entry.
+#[derive(Clone)]
+pub struct EntrySnapshot<V> {
+    /// The latest value of the entry
+    pub value: V,
+    /// Amount of refs subscribed to the entry
+    pub subscribers: usize,
+    /// If the entry is kept without subscribers
+    pub pinned: bool,
+    /// The tags of all tagged subscribers in ascending order
+    pub tags: Vec<&'static str>,
+    redaction: Redaction<V>,
+}
+
+/// How the map which took a snapshot formats an entry in diagnostics
+struct Redaction<V> {
+    /// The key formatted through the key redactor
+    key: Option<Arc<str>>,
+    value: Option<Arc<dyn Redactor<V>>>,
+}
+
+impl<V> Redaction<V> {
+    /// Fill in the formatting of the other entry where this one has none
+    fn or(&self, other: &Self) -> Self {
+        Self {
+            key: self.key.clone().or_else(|| other.key.clone()),
+            value: self.value.clone().or_else(|| other.value.clone()),
+        }
+    }
+
+    fn key<'a, K>(&'a self, key: &'a K) -> RedactedKey<'a, K> {
+        RedactedKey {
+            key,
+            redacted: self.key.as_deref(),
+        }
+    }
+}
+
+impl<V> Default for Redaction<V> {
+    fn default() -> Self {
+        Self {
+            key: None,
+            value: None,
+        }
+    }
+}
+
+impl<V> Clone for Redaction<V> {
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            value: self.value.clone(),
+        }
+    }
+}
+
+/// Formats a key of a snapshot as the map which took it does
+struct RedactedKey<'a, K> {
+    key: &'a K,
+    redacted: Option<&'a str>,
+}
+
+impl<K> Debug for RedactedKey<'_, K>
+where
+    K: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.redacted {
+            Some(redacted) => f.write_str(redacted),
+            None => self.key.fmt(f),
+        }
+    }
+}
+
+/// Formats an entry of a snapshot through the provided redaction
+struct RedactedEntry<'a, V> {
+    entry: &'a EntrySnapshot<V>,
+    redaction: &'a Redaction<V>,
+}
+
+impl<V> Debug for RedactedEntry<'_, V>
+where
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = Redacted {
+            value: &self.entry.value,
+            redactor: self.redaction.value.as_deref(),
+        };
+
+        f.debug_struct("EntrySnapshot")
+            .field("value", &value)
+            .field("subscribers", &self.entry.subscribers)
+            .field("pinned", &self.entry.pinned)
+            .field("tags", &self.entry.tags)
+            .finish()
+    }
+}
+
+impl<V> EntrySnapshot<V> {
+    /// An unpinned entry with the value and amount of subscribers
+    pub fn new(value: V, subscribers: usize) -> Self {
+        Self {
+            value,
+            subscribers,
+            pinned: false,
+            tags: Vec::new(),
+            redaction: Redaction::default(),
+        }
+    }
+
+    /// Mark the entry as pinned
+    pub fn pinned(mut self) -> Self {
+        self.pinned = true;
+        self
+    }
+
+    /// Add the tag of a subscriber
+    pub fn tagged(mut self, tag: &'static str) -> Self {
+        self.tags.push(tag);
+        self.tags.sort_unstable();
+        self
+    }
+
+    fn redacted<'a>(&'a self, redaction: &'a Redaction<V>) -> RedactedEntry<'a, V> {
+        RedactedEntry {
+            entry: self,
+            redaction,
+        }
+    }
+}
+
+impl<V> PartialEq for EntrySnapshot<V>
+where
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+            && self.subscribers == other.subscribers
+            && self.pinned == other.pinned
+            && self.tags == other.tags
+    }
+}
+
+impl<V> Eq for EntrySnapshot<V> where V: Eq {}
+
+impl<V> Debug for EntrySnapshot<V>
+where
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.redacted(&self.redaction).fmt(f)
+    }
+}
+
+/// A point in time copy of all entries of a map.
+///
+/// Snapshots can be compared to each other, which makes them handy to assert the state of a map
+/// in integration tests, see [`assert_snapshot_eq`](crate::assert_snapshot_eq).
+#[derive(Clone, PartialEq, Eq)]
+pub struct MapSnapshot<K, V> {
+    entries: BTreeMap<K, EntrySnapshot<V>>,
+}
+
+impl<K, V> MapSnapshot<K, V>
+where
+    K: Ord,
+{
+    /// The state of the entry at this key
+    pub fn get(&self, key: &K) -> Option<&EntrySnapshot<V>> {
+        self.entries.get(key)
+    }
+
+    /// All entries ordered by key
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &EntrySnapshot<V>)> {
+        self.entries.iter()
+    }
+
+    /// Amount of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The changes which turn this snapshot into the other one
+    pub fn diff<'a>(&'a self, other: &'a Self) -> SnapshotDiff<'a, K, V>
+    where
+        V: PartialEq,
+    {
+        let keys: BTreeSet<&K> = self.entries.keys().chain(other.entries.keys()).collect();
+
+        let changes = keys
+            .into_iter()
+            .filter_map(
+                |key| match (self.entries.get(key), other.entries.get(key)) {
+                    (Some(before), Some(after)) if before == after => None,
+                    (Some(before), Some(after)) => {
+                        Some(SnapshotChange::Changed { key, before, after })
+                    }
+                    (Some(entry), None) => Some(SnapshotChange::Removed { key, entry }),
+                    (None, Some(entry)) => Some(SnapshotChange::Added { key, entry }),
+                    (None, None) => None,
+                },
+            )
+            .collect();
+
+        SnapshotDiff { changes }
+    }
+}
+
+impl<K, V> Debug for MapSnapshot<K, V>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Entries<'a, K, V>(&'a BTreeMap<K, EntrySnapshot<V>>);
+
+        impl<K: Debug, V: Debug> Debug for Entries<'_, K, V> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_map()
+                    .entries(
+                        self.0
+                            .iter()
+                            .map(|(key, entry)| (entry.redaction.key(key), entry)),
+                    )
+                    .finish()
+            }
+        }
+
+        f.debug_struct("MapSnapshot")
+            .field("entries", &Entries(&self.entries))
+            .finish()
+    }
+}
+
+impl<K, V> Default for MapSnapshot<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, EntrySnapshot<V>)> for MapSnapshot<K, V>
+where
+    K: Ord,
+{
+    fn from_iter<T: IntoIterator<Item = (K, EntrySnapshot<V>)>>(iter: T) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// A single difference between two snapshots, formats like the snapshots it was taken from
+#[derive(PartialEq, Eq)]
+pub enum SnapshotChange<'a, K, V> {
+    /// The entry is only present in the other snapshot
+    Added {
+        key: &'a K,
+        entry: &'a EntrySnapshot<V>,
+    },
+    /// The entry is only present in this snapshot
+    Removed {
+        key: &'a K,
+        entry: &'a EntrySnapshot<V>,
+    },
+    /// The entry differs between both snapshots
+    Changed {
+        key: &'a K,
+        before: &'a EntrySnapshot<V>,
+        after: &'a EntrySnapshot<V>,
+    },
+}
+
+/// All differences between two snapshots ordered by key.
+///
+/// Formats as a line based diff, with `-` marking the state of this and `+` the state of the
+/// other snapshot. Entries are formatted through the redactors of the map which took either of
+/// the snapshots.
+#[derive(PartialEq, Eq)]
+pub struct SnapshotDiff<'a, K, V> {
+    changes: Vec<SnapshotChange<'a, K, V>>,
+}
+
+impl<'a, K, V> SnapshotDiff<'a, K, V> {
+    /// Check if both snapshots are equal
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The individual changes
+    pub fn changes(&self) -> &[SnapshotChange<'a, K, V>] {
+        &self.changes
+    }
+}
+
+/// One side of a change, absent if the entry only exists in the other snapshot
+type Side<'a, V> = Option<&'a EntrySnapshot<V>>;
+
+impl<K, V> SnapshotChange<'_, K, V> {
+    /// The key and the entries in this and in the other snapshot
+    fn sides(&self) -> (&K, Side<'_, V>, Side<'_, V>) {
+        match *self {
+            SnapshotChange::Added { key, entry } => (key, None, Some(entry)),
+            SnapshotChange::Removed { key, entry } => (key, Some(entry), None),
+            SnapshotChange::Changed { key, before, after } => (key, Some(before), Some(after)),
+        }
+    }
+
+    /// The formatting of the maps which took the snapshots of either side
+    fn redaction(&self) -> Redaction<V> {
+        match *self {
+            SnapshotChange::Added { entry, .. } | SnapshotChange::Removed { entry, .. } => {
+                entry.redaction.clone()
+            }
+            SnapshotChange::Changed { before, after, .. } => after.redaction.or(&before.redaction),
+        }
+    }
+}
+
+impl<K, V> Debug for SnapshotChange<'_, K, V>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (key, before, after) = self.sides();
+        let redaction = self.redaction();
+        let key = redaction.key(key);
+
+        match (before, after) {
+            (None, Some(entry)) => f
+                .debug_struct("Added")
+                .field("key", &key)
+                .field("entry", &entry.redacted(&redaction))
+                .finish(),
+            (Some(entry), None) => f
+                .debug_struct("Removed")
+                .field("key", &key)
+                .field("entry", &entry.redacted(&redaction))
+                .finish(),
+            (before, after) => f
+                .debug_struct("Changed")
+                .field("key", &key)
+                .field("before", &before.map(|entry| entry.redacted(&redaction)))
+                .field("after", &after.map(|entry| entry.redacted(&redaction)))
+                .finish(),
+        }
+    }
+}
+
+impl<K, V> Debug for SnapshotDiff<'_, K, V>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SnapshotDiff")
+            .field("changes", &self.changes)
+            .finish()
+    }
+}
+
+impl<K, V> fmt::Display for SnapshotDiff<'_, K, V>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.changes.iter() {
+            let (key, before, after) = change.sides();
+            let redaction = change.redaction();
+            let key = redaction.key(key);
+
+            if let Some(before) = before {
+                writeln!(f, "- {:?}: {:?}", key, before.redacted(&redaction))?;
+            }
+
+            if let Some(after) = after {
+                writeln!(f, "+ {:?}: {:?}", key, after.redacted(&redaction))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Assert that two [`MapSnapshot`]s are equal, printing a diff of their entries otherwise.
+///
+/// ```
+/// # use async_subscription_map::{assert_snapshot_eq, EntrySnapshot, MapSnapshot, SubscriptionMap};
+/// # async {
+/// let map = SubscriptionMap::<usize, usize>::default();
+/// let _subscription = map.get_or_insert(1, 0).await.unwrap();
+///
+/// let expected: MapSnapshot<_, _> = [(1, EntrySnapshot::new(0, 1))].into_iter().collect();
+/// assert_snapshot_eq!(map.describe().await, expected);
+/// # };
+/// ```
+#[macro_export]
+macro_rules! assert_snapshot_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert_snapshot_eq!($actual, $expected, "snapshots differ")
+    };
+    ($actual:expr, $expected:expr, $($arg:tt)+) => {
+        match (&$actual, &$expected) {
+            (actual, expected) => {
+                let diff = $crate::MapSnapshot::diff(expected, actual);
+
+                if !diff.is_empty() {
+                    panic!(
+                        "{}\n(- expected, + actual)\n{}",
+                        format_args!($($arg)+),
+                        diff
+                    );
+                }
+            }
+        }
+    };
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// A clone of the latest value of every entry, including pinned and retained ones.
+    ///
+    /// Only the shared lock of the entries is held while the values are cloned, so this is safe
+    /// to call while the map is in use, e.g. to render an admin dashboard.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, usize>::default();
+    /// let _workers = map.get_or_insert("workers", 4).await.unwrap();
+    /// map.pin("queue", 12).await.unwrap();
+    ///
+    /// let values = map.snapshot().await;
+    /// assert_eq!(values.into_iter().collect::<Vec<_>>(), [("queue", 12), ("workers", 4)]);
+    /// # };
+    /// ```
+    pub async fn snapshot(&self) -> BTreeMap<K, V> {
+        let map = self.read_entries().await;
+
+        map.iter()
+            .map(|(key, entry)| (key.clone(), entry.observable.latest()))
+            .collect()
+    }
+
+    /// Take a snapshot of all entries including their subscription counts.
+    ///
+    /// The snapshot is formatted through the redactors of the map, see [`EntrySnapshot`].
+    pub async fn describe(&self) -> MapSnapshot<K, V> {
+        let map = self.read_entries().await;
+        let key_redactor = self.0.config.key_redactor.as_ref();
+
+        map.iter()
+            .map(|(key, entry)| {
+                let mut tags = entry.tags.clone();
+                tags.sort_unstable();
+
+                let snapshot = EntrySnapshot {
+                    value: entry.observable.latest(),
+                    subscribers: entry.rc(),
+                    pinned: entry.pinned,
+                    tags,
+                    redaction: Redaction {
+                        key: key_redactor.map(|_| format!("{:?}", self.redacted_key(key)).into()),
+                        value: self.0.config.redactor.clone(),
+                    },
+                };
+
+                (key.clone(), snapshot)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EntrySnapshot, MapSnapshot, SnapshotChange};
+    use crate::{FullRedaction, SubscriptionMap, TruncatedKeys};
+
+    #[async_std::test]
+    async fn should_snapshot_latest_values() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+        subscription.publish(5).unwrap();
+        map.pin(2, 2).await.unwrap();
+
+        let values = map.snapshot().await;
+        assert_eq!(values, [(1, 5), (2, 2)].into_iter().collect());
+
+        drop(subscription);
+        assert_eq!(map.snapshot().await.len(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_describe_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let _one = map.get_or_insert(1, 1).await.unwrap();
+        let _two = map.get_or_insert(1, 1).await.unwrap();
+        map.pin(2, 2).await.unwrap();
+
+        let expected: MapSnapshot<_, _> = [
+            (1, EntrySnapshot::new(1, 2)),
+            (2, EntrySnapshot::new(2, 0).pinned()),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_snapshot_eq!(map.describe().await, expected);
+    }
+
+    #[async_std::test]
+    async fn should_redact_snapshots() {
+        let map = SubscriptionMap::<&'static str, &'static str>::builder()
+            .redactor(FullRedaction)
+            .key_redactor(TruncatedKeys(6))
+            .build();
+        let mut subscription = map.get_or_insert("users/alice", "hunter2").await.unwrap();
+        let before = map.describe().await;
+
+        subscription.publish("hunter3").unwrap();
+        let after = map.describe().await;
+        let diff = before.diff(&after);
+        let expected = MapSnapshot::default();
+        let removed = expected.diff(&after);
+
+        let entry = format!("{:?}", after.get(&"users/alice"));
+        assert!(!entry.contains("hunter"));
+
+        for output in [
+            format!("{:?}", after),
+            format!("{}", diff),
+            format!("{:?}", diff),
+            format!("{:?}", removed.changes()),
+        ] {
+            assert!(!output.contains("alice"), "{}", output);
+            assert!(!output.contains("hunter"), "{}", output);
+            assert!(output.contains("\"users..."), "{}", output);
+        }
+
+        assert_eq!(before.get(&"users/alice").unwrap().value, "hunter2");
+        assert_eq!(
+            after,
+            [("users/alice", EntrySnapshot::new("hunter3", 1))]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn should_diff_snapshots() {
+        let before: MapSnapshot<usize, usize> =
+            [(1, EntrySnapshot::new(1, 1)), (2, EntrySnapshot::new(2, 1))]
+                .into_iter()
+                .collect();
+        let after: MapSnapshot<usize, usize> =
+            [(2, EntrySnapshot::new(3, 1)), (3, EntrySnapshot::new(3, 1))]
+                .into_iter()
+                .collect();
+
+        let diff = before.diff(&after);
+        assert!(before.diff(&before).is_empty());
+        assert_eq!(
+            diff.changes(),
+            &[
+                SnapshotChange::Removed {
+                    key: &1,
+                    entry: &EntrySnapshot::new(1, 1)
+                },
+                SnapshotChange::Changed {
+                    key: &2,
+                    before: &EntrySnapshot::new(2, 1),
+                    after: &EntrySnapshot::new(3, 1)
+                },
+                SnapshotChange::Added {
+                    key: &3,
+                    entry: &EntrySnapshot::new(3, 1)
+                },
+            ]
+        );
+
+        let output = diff.to_string();
+        assert!(output.starts_with("- 1: "));
+        assert_eq!(output.lines().count(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "+ 1: EntrySnapshot { value: 2")]
+    fn should_panic_with_diff() {
+        let actual: MapSnapshot<usize, usize> =
+            [(1, EntrySnapshot::new(2, 0))].into_iter().collect();
+
+        assert_snapshot_eq!(actual, MapSnapshot::default());
+    }
+}
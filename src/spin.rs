@@ -0,0 +1,87 @@
+use crate::SubscriptionRef;
+use futures::task::noop_waker_ref;
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::Hash;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Same as [`next`](Self::next) but busy waits for up to the spin duration before the task
+    /// is parked.
+    ///
+    /// A producer and consumer running on different cores thereby skip the roundtrip through
+    /// the waker and the executor, which shaves wakeup latency in microsecond sensitive paths at
+    /// the cost of a fully busy core while spinning. The duration is measured in real time,
+    /// independent of the [`Clock`](crate::Clock) of the map, and should stay in the range of
+    /// microseconds.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # use std::time::Duration;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    ///
+    /// map.publish_if_changed(&1, 1).await.unwrap();
+    /// assert_eq!(subscription.next_spinning(Duration::from_micros(50)).await, 1);
+    /// # };
+    /// ```
+    pub async fn next_spinning(&mut self, spin: Duration) -> V {
+        let value = {
+            let next = self.observable.next();
+            futures::pin_mut!(next);
+
+            let mut cx = Context::from_waker(noop_waker_ref());
+            let started = Instant::now();
+
+            loop {
+                if let Poll::Ready(value) = next.as_mut().poll(&mut cx) {
+                    break value;
+                }
+
+                if started.elapsed() >= spin {
+                    // registers the waker of the task, nothing is lost between the polls
+                    break next.await;
+                }
+
+                std::hint::spin_loop();
+            }
+        };
+
+        #[cfg(feature = "latency")]
+        self.owner.record_wakeup(&self.key);
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_park_once_spin_is_exceeded() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        let producer = async_std::task::spawn({
+            let map = map.clone();
+            async move {
+                async_std::task::sleep(Duration::from_millis(20)).await;
+                map.publish_if_changed(&1, 2).await.unwrap();
+            }
+        });
+
+        let value = subscription.next_spinning(Duration::from_micros(10)).await;
+        assert_eq!(value, 2);
+        producer.await;
+
+        map.publish_if_changed(&1, 3).await.unwrap();
+        assert_eq!(subscription.next_spinning(Duration::ZERO).await, 3);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::SubscriptionRef;
+use futures::channel::oneshot;
+use futures::{Stream, StreamExt};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Send + 'static,
+{
+    /// Split the subscription into a stream of published values and a guard which keeps the
+    /// entry alive.
+    ///
+    /// The stream can be moved into a pipeline of combinators which may drop it early, while the
+    /// owning task decides how long the entry lives through the guard. Dropping the stream has no
+    /// effect on the entry, dropping the guard releases it and ends the stream.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # use futures::StreamExt;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let (values, guard) = map.get_or_insert(1, 0).await.unwrap().split();
+    /// let mut doubled = values.map(|value| value * 2);
+    ///
+    /// map.publish_if_changed(&1, 2).await.unwrap();
+    /// assert_eq!(doubled.next().await, Some(4));
+    ///
+    /// drop(doubled);
+    /// assert_eq!(map.peek(&1).await, Some(2));
+    ///
+    /// drop(guard);
+    /// assert_eq!(map.peek(&1).await, None);
+    /// # };
+    /// ```
+    pub fn split(self) -> (ReadHalf<V>, KeepAlive<K, V>) {
+        let (released, stopped) = oneshot::channel::<()>();
+
+        let values = futures::stream::unfold(self.observable.clone(), |mut observable| async {
+            let value = observable.next().await;
+            Some((value, observable))
+        })
+        .take_until(stopped);
+
+        let read = ReadHalf {
+            values: Box::pin(values),
+        };
+
+        let guard = KeepAlive {
+            subscription: self,
+            _released: released,
+        };
+
+        (read, guard)
+    }
+}
+
+/// The values of a [split](SubscriptionRef::split) subscription, doesn't keep the entry alive.
+#[must_use = "streams do nothing unless polled"]
+pub struct ReadHalf<V> {
+    values: Pin<Box<dyn Stream<Item = V> + Send>>,
+}
+
+impl<V> Stream for ReadHalf<V> {
+    type Item = V;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.values.poll_next_unpin(cx)
+    }
+}
+
+impl<V> Debug for ReadHalf<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadHalf").finish_non_exhaustive()
+    }
+}
+
+/// Keeps the entry of a [split](SubscriptionRef::split) subscription alive, its
+/// [`ReadHalf`] ends once this is dropped.
+#[must_use = "the entry is released as soon as the guard is dropped"]
+pub struct KeepAlive<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscription: SubscriptionRef<K, V>,
+    /// Dropped together with the guard, which ends the read half
+    _released: oneshot::Sender<()>,
+}
+
+impl<K, V> KeepAlive<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the kept entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// A clone of the latest value of the entry
+    pub fn latest(&self) -> V {
+        self.subscription.latest()
+    }
+}
+
+impl<K, V> Debug for KeepAlive<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeepAlive")
+            .field("subscription", &self.subscription)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+    use futures::StreamExt;
+
+    #[async_std::test]
+    async fn should_end_read_half_with_guard() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let (mut values, guard) = map.get_or_insert(1, 0).await.unwrap().split();
+        assert_eq!(guard.key(), &1);
+
+        map.publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(values.next().await, Some(1));
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 1);
+
+        drop(guard);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+        assert_eq!(values.next().await, None);
+    }
+}
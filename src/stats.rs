@@ -0,0 +1,315 @@
+use crate::SubscriptionMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+const BUCKETS: usize = 32;
+
+/// A distribution of entry lifetimes in power of two millisecond buckets.
+///
+/// Bucket `i` counts lifetimes below `2^i` milliseconds, the last bucket also contains everything
+/// exceeding its bound.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LifetimeHistogram {
+    buckets: [u64; BUCKETS],
+}
+
+impl LifetimeHistogram {
+    fn record(&mut self, lifetime: Duration) {
+        let millis = lifetime.as_millis();
+        let bucket = (u128::BITS - millis.leading_zeros()) as usize;
+        self.buckets[bucket.min(BUCKETS - 1)] += 1;
+    }
+
+    /// The total amount of recorded lifetimes
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// The upper bounds of all buckets together with their counts
+    pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (Duration::from_millis(1 << i), *count))
+    }
+
+    /// The upper bound of the bucket containing the quantile `q` (between 0 and 1) of all
+    /// recorded lifetimes.
+    pub fn quantile(&self, q: f64) -> Option<Duration> {
+        let count = self.count();
+
+        if count == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+
+        self.buckets().find_map(|(bound, bucket)| {
+            seen += bucket;
+            (seen >= rank).then_some(bound)
+        })
+    }
+}
+
+/// Creation and removal statistics of a group of entries.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChurnStats {
+    /// Amount of created entries
+    pub created: u64,
+    /// Amount of removed entries
+    pub removed: u64,
+    /// Lifetimes of the removed entries
+    pub lifetimes: LifetimeHistogram,
+    /// The time span the statistics were collected in
+    pub elapsed: Duration,
+}
+
+impl ChurnStats {
+    /// Amount of entries which are currently present
+    pub fn live(&self) -> u64 {
+        self.created.saturating_sub(self.removed)
+    }
+
+    /// Created entries per second
+    pub fn creation_rate(&self) -> f64 {
+        self.created as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Removed entries per second
+    pub fn removal_rate(&self) -> f64 {
+        self.removed as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Churn statistics of a map, in total and per configured key prefix.
+///
+/// ```
+/// # use async_subscription_map::SubscriptionMap;
+/// # async {
+/// let map = SubscriptionMap::<&'static str, usize>::builder()
+///     .stats_prefixes(["sessions/", "config/"])
+///     .build();
+///
+/// drop(map.get_or_insert("sessions/1", 0).await.unwrap());
+///
+/// let stats = map.stats();
+/// assert_eq!(stats.total.removed, 1);
+/// assert_eq!(stats.prefixes[0], ("sessions/", stats.total.clone()));
+/// # };
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapStats<K> {
+    /// Statistics over all keys
+    pub total: ChurnStats,
+    /// Statistics over the keys of every prefix configured via
+    /// [`stats_prefixes`](crate::SubscriptionMapBuilder::stats_prefixes)
+    pub prefixes: Vec<(K, ChurnStats)>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Counters {
+    created: u64,
+    removed: u64,
+    lifetimes: LifetimeHistogram,
+}
+
+impl Counters {
+    fn snapshot(&self, elapsed: Duration) -> ChurnStats {
+        ChurnStats {
+            created: self.created,
+            removed: self.removed,
+            lifetimes: self.lifetimes.clone(),
+            elapsed,
+        }
+    }
+}
+
+/// Records the churn of a map
+#[derive(Debug)]
+pub(crate) struct Stats {
+    started: Instant,
+    total: Counters,
+    prefixes: Vec<Counters>,
+}
+
+impl Stats {
+    pub fn new(started: Instant, prefixes: usize) -> Self {
+        Self {
+            started,
+            total: Counters::default(),
+            prefixes: vec![Counters::default(); prefixes],
+        }
+    }
+
+    pub fn created(&mut self, prefix: Option<usize>) {
+        self.total.created += 1;
+
+        if let Some(prefix) = prefix {
+            self.prefixes[prefix].created += 1;
+        }
+    }
+
+    pub fn removed(&mut self, prefix: Option<usize>, lifetime: Duration) {
+        self.total.removed += 1;
+        self.total.lifetimes.record(lifetime);
+
+        if let Some(prefix) = prefix {
+            self.prefixes[prefix].removed += 1;
+            self.prefixes[prefix].lifetimes.record(lifetime);
+        }
+    }
+}
+
+/// Locks the statistics of a map, they stay consistent if a holder panics
+pub(crate) fn lock(stats: &Mutex<Stats>) -> MutexGuard<'_, Stats> {
+    match stats.lock() {
+        Ok(guard) => guard,
+        Err(e) => e.into_inner(),
+    }
+}
+
+/// The statistics an entry was created in, its removal is accounted to them even after the entry
+/// was [swapped](SubscriptionMap::swap_contents) into another map
+#[derive(Clone, Debug)]
+pub(crate) struct ChurnOrigin {
+    stats: Arc<Mutex<Stats>>,
+    prefix: Option<usize>,
+}
+
+impl ChurnOrigin {
+    /// Account a created entry to the statistics
+    pub fn created(stats: &Arc<Mutex<Stats>>, prefix: Option<usize>) -> Self {
+        lock(stats).created(prefix);
+
+        Self {
+            stats: stats.clone(),
+            prefix,
+        }
+    }
+
+    pub fn removed(&self, lifetime: Duration) {
+        lock(&self.stats).removed(self.prefix, lifetime);
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The churn statistics of this map since it was created.
+    pub fn stats(&self) -> MapStats<K> {
+        let stats = self.0.stats();
+        let elapsed = self
+            .0
+            .config
+            .clock
+            .now()
+            .saturating_duration_since(stats.started);
+
+        MapStats {
+            total: stats.total.snapshot(elapsed),
+            prefixes: self
+                .0
+                .config
+                .stats_prefixes
+                .iter()
+                .cloned()
+                .zip(stats.prefixes.iter().map(|c| c.snapshot(elapsed)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LifetimeHistogram;
+    use crate::{ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    #[test]
+    fn should_bucket_lifetimes() {
+        let mut histogram = LifetimeHistogram::default();
+        histogram.record(Duration::from_millis(0));
+        histogram.record(Duration::from_millis(3));
+        histogram.record(Duration::from_millis(3));
+        histogram.record(Duration::from_secs(1));
+
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.quantile(0.0), Some(Duration::from_millis(1)));
+        assert_eq!(histogram.quantile(0.5), Some(Duration::from_millis(4)));
+        assert_eq!(histogram.quantile(1.0), Some(Duration::from_millis(1024)));
+        assert_eq!(LifetimeHistogram::default().quantile(0.5), None);
+    }
+
+    #[async_std::test]
+    async fn should_track_churn_per_prefix() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<&'static str, usize>::builder()
+            .clock(clock.clone())
+            .stats_prefixes(["a/", "b/"])
+            .build();
+
+        let a = map.get_or_insert("a/1", 0).await.unwrap();
+        let _b = map.get_or_insert("b/1", 0).await.unwrap();
+        drop(map.get_or_insert("c/1", 0).await.unwrap());
+
+        clock.advance(Duration::from_millis(100));
+        drop(a);
+
+        let stats = map.stats();
+        assert_eq!(stats.total.created, 3);
+        assert_eq!(stats.total.removed, 2);
+        assert_eq!(stats.total.live(), 1);
+        assert_eq!(stats.total.elapsed, Duration::from_millis(100));
+        assert_eq!(stats.total.creation_rate(), 30.0);
+
+        let (prefix, a) = &stats.prefixes[0];
+        assert_eq!(*prefix, "a/");
+        assert_eq!((a.created, a.removed), (1, 1));
+        assert_eq!(a.lifetimes.quantile(1.0), Some(Duration::from_millis(128)));
+
+        let (prefix, b) = &stats.prefixes[1];
+        assert_eq!(*prefix, "b/");
+        assert_eq!((b.created, b.removed), (1, 0));
+    }
+
+    #[async_std::test]
+    async fn should_not_underflow_live_entries_of_swapped_maps() {
+        let a = SubscriptionMap::<usize, usize>::new();
+        let b = SubscriptionMap::<usize, usize>::new();
+
+        let first = a.get_or_insert(1, 1).await.unwrap();
+        a.swap_contents(&b);
+        let second = b.get_or_insert(1, 0).await.unwrap();
+
+        drop((first, second));
+        b.sweep().await;
+
+        assert_eq!(a.stats().total.live(), 0);
+        assert_eq!(b.stats().total.live(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_account_removals_to_the_creating_map() {
+        let a = SubscriptionMap::<&'static str, usize>::builder()
+            .stats_prefixes(["a/"])
+            .build();
+        let b = SubscriptionMap::<&'static str, usize>::new();
+
+        a.pin("a/1", 0).await.unwrap();
+        a.swap_contents(&b);
+        b.unpin(&"a/1").await;
+
+        let stats = a.stats();
+        assert_eq!((stats.total.created, stats.total.removed), (1, 1));
+        assert_eq!(
+            (stats.prefixes[0].1.created, stats.prefixes[0].1.removed),
+            (1, 1)
+        );
+        assert_eq!((b.stats().total.created, b.stats().total.removed), (0, 0));
+    }
+}
@@ -0,0 +1,79 @@
+use crate::SubscriptionRef;
+use futures::{Stream, StreamExt};
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Turn the subscription into a stream of published values which ends once the shutdown
+    /// future completes.
+    ///
+    /// A value which is pending when the shutdown fires is not yielded anymore. The subscription
+    /// is released together with the stream.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # use futures::StreamExt;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let (shutdown, signal) = futures::channel::oneshot::channel::<()>();
+    ///
+    /// let subscription = map.get_or_insert(1, 0).await.unwrap();
+    /// let mut values = subscription.stream_while(async move {
+    ///     signal.await.ok();
+    /// });
+    ///
+    /// map.publish_if_changed(&1, 1).await.unwrap();
+    /// assert_eq!(values.next().await, Some(1));
+    ///
+    /// shutdown.send(()).unwrap();
+    /// while let Some(value) = values.next().await {
+    ///     println!("{}", value);
+    /// }
+    /// # };
+    /// ```
+    pub fn stream_while<F>(self, shutdown: F) -> impl Stream<Item = V> + Unpin
+    where
+        F: Future<Output = ()>,
+    {
+        Box::pin(
+            futures::stream::unfold(self, |mut subscription| async move {
+                let value = subscription.next().await;
+                Some((value, subscription))
+            })
+            .take_until(shutdown),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+    use futures::channel::oneshot;
+    use futures::StreamExt;
+
+    #[async_std::test]
+    async fn should_end_stream_on_shutdown() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let (shutdown, signal) = oneshot::channel::<()>();
+
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let mut values = subscription.stream_while(async move {
+            signal.await.ok();
+        });
+
+        map.publish_if_changed(&1, 1).await.unwrap();
+        assert_eq!(values.next().await, Some(1));
+
+        shutdown.send(()).unwrap();
+        map.publish_if_changed(&1, 2).await.unwrap();
+        assert_eq!(values.next().await, None);
+
+        drop(values);
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::SubscriptionMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Atomically exchange the entries of this map with the ones of the other map.
+    ///
+    /// All lookups and map level publishes which start after this returns operate on the swapped
+    /// entries. Existing refs stay attached to the entries they were created in, they keep
+    /// working and drain the old entries as they are dropped. Configuration and publish state
+    /// remain with their map, so publishes through existing refs bypass the [rates](Self::rate),
+    /// [cdc streams](Self::cdc_stream) and buffering of their map. The churn
+    /// [statistics](Self::stats) follow the entries instead: removals are accounted to the map
+    /// which created the entry, so both maps keep consistent live counts.
+    ///
+    /// This enables blue/green state rebuilds: a fresh map is populated in the background and
+    /// swapped in once it is complete.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let live = SubscriptionMap::<usize, usize>::default();
+    /// let old = live.get_or_insert(1, 0).await.unwrap();
+    ///
+    /// let rebuilt = SubscriptionMap::<usize, usize>::default();
+    /// rebuilt.pin(1, 42).await.unwrap();
+    ///
+    /// live.swap_contents(&rebuilt);
+    /// assert_eq!(live.get_or_insert(1, 0).await.unwrap().latest(), 42);
+    /// assert_eq!(old.latest(), 0);
+    /// # };
+    /// ```
+    pub fn swap_contents(&self, other: &Self) {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return;
+        }
+
+        // lock both maps in a consistent order so concurrent swaps in opposite directions
+        // can't deadlock
+        let (first, second) = if Arc::as_ptr(&self.0) < Arc::as_ptr(&other.0) {
+            (&self.0, &other.0)
+        } else {
+            (&other.0, &self.0)
+        };
+
+        let mut first = first.entries.write().unwrap_or_else(|e| e.into_inner());
+        let mut second = second.entries.write().unwrap_or_else(|e| e.into_inner());
+
+        std::mem::swap(&mut *first, &mut *second);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_swap_entries_and_drain_old_refs() {
+        let blue: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let green: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+
+        let mut old = blue.get_or_insert(1, 0).await.unwrap();
+        let new = green.get_or_insert(1, 10).await.unwrap();
+
+        blue.swap_contents(&green);
+
+        let mut current = blue.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(current.latest(), 10);
+        assert_eq!(blue.entries_snapshot().await.get(&1).unwrap().rc(), 2);
+
+        // map level publishes reach the swapped in entries only
+        assert!(blue.publish_if_changed(&1, 11).await.unwrap());
+        assert_eq!(current.next().await, 11);
+        assert_eq!(old.latest(), 0);
+
+        // old refs keep working on the entries they were created in
+        old.publish(1).unwrap();
+        assert_eq!(green.get_or_insert(1, 0).await.unwrap().latest(), 1);
+
+        drop(old);
+        assert_eq!(green.entries_snapshot().await.len(), 0);
+
+        drop(new);
+        drop(current);
+        assert_eq!(blue.entries_snapshot().await.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_ignore_swap_with_itself() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let _subscription = map.get_or_insert(1, 0).await.unwrap();
+
+        map.swap_contents(&map.clone());
+        assert_eq!(map.entries_snapshot().await.len(), 1);
+    }
+}
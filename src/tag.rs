@@ -0,0 +1,122 @@
+use crate::{Error, SubscriptionEntry, SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+
+impl<V> SubscriptionEntry<V>
+where
+    V: Clone + Debug,
+{
+    /// Forget a single occurrence of the tag
+    pub(crate) fn untag(&mut self, tag: &'static str) {
+        if let Some(index) = self.tags.iter().position(|t| *t == tag) {
+            self.tags.swap_remove(index);
+        }
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Same as [`get_or_insert`](Self::get_or_insert) but labels the ref for diagnostics.
+    ///
+    /// The tags of all live refs show up in [`describe`](Self::describe) and the debug output of
+    /// the map, so operators can see who keeps an entry alive and not just how many refs do.
+    /// Refs created from a tagged one, e.g. through [`frozen`](SubscriptionRef::frozen), carry the
+    /// same tag.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let _subscription = map.get_or_insert_tagged(1, 0, "ws-session-123").await.unwrap();
+    ///
+    /// let snapshot = map.describe().await;
+    /// assert_eq!(snapshot.get(&1).unwrap().tags, vec!["ws-session-123"]);
+    /// # };
+    /// ```
+    pub async fn get_or_insert_tagged(
+        &self,
+        key: K,
+        value: V,
+        tag: &'static str,
+    ) -> anyhow::Result<SubscriptionRef<K, V>> {
+        let entries = self.0.entries();
+        let mut map = self.lock_entries(&entries).await;
+
+        if self.0.draining.load(Ordering::SeqCst) {
+            return Err(Error::Draining).with_context(|| {
+                format!(
+                    "unable to subscribe to {:?} as {}",
+                    self.redacted_key(&key),
+                    tag
+                )
+            });
+        }
+
+        let mut subscription = self.attach(&entries, &mut map, key, value)?;
+        subscription.tag = Some(tag);
+
+        if let Some(entry) = map.get_mut(&subscription.key) {
+            entry.tags.push(tag);
+        }
+
+        Ok(subscription)
+    }
+}
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The diagnostic label of this ref, see [`SubscriptionMap::get_or_insert_tagged`]
+    pub fn tag(&self) -> Option<&'static str> {
+        self.tag
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_track_tags_of_live_refs() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let session = map.get_or_insert_tagged(1, 0, "session").await.unwrap();
+        let worker = map.get_or_insert_tagged(1, 0, "worker").await.unwrap();
+        let _untagged = map.get_or_insert(1, 0).await.unwrap();
+        let observer = session.frozen().await.unwrap();
+
+        assert_eq!(session.tag(), Some("session"));
+        assert_eq!(map.entries_snapshot().await[&1].tags.len(), 3);
+
+        drop(session);
+        drop(worker);
+        assert_eq!(map.entries_snapshot().await[&1].tags, vec!["session"]);
+
+        drop(observer);
+        assert!(map.entries_snapshot().await[&1].tags.is_empty());
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 1);
+    }
+
+    #[async_std::test]
+    async fn should_fork_untagged_refs_of_removed_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let tagged = map.get_or_insert_tagged(1, 0, "session").await.unwrap();
+
+        map.force_remove(&1).await;
+        let _fresh = map.get_or_insert(1, 0).await.unwrap();
+
+        let observer = tagged.frozen().await.unwrap();
+        assert_eq!(observer.latest(), 0);
+        assert!(map.entries_snapshot().await[&1].tags.is_empty());
+
+        drop(observer);
+        drop(tagged);
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 1);
+    }
+}
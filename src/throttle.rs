@@ -0,0 +1,253 @@
+use crate::{Clock, SubscriptionRef};
+use futures::future::{self, Either};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Deliver the latest value at most once per window.
+    ///
+    /// The first publish is delivered right away, publishes within the window after a delivery
+    /// are conflated and the latest of them is delivered once the window ends. Slow consumers of
+    /// high frequency publishers thereby receive a steady rate of fresh values. Windows are
+    /// measured by the [`Clock`] of the map.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # use std::time::Duration;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, f64>::default();
+    /// let mut producer = map.get_or_insert("btc", 0.0).await.unwrap();
+    /// let subscription = map.get_or_insert("btc", 0.0).await.unwrap();
+    /// let mut ticks = subscription.throttle(Duration::from_millis(10));
+    ///
+    /// producer.publish(1.0).unwrap();
+    /// assert_eq!(ticks.next().await, 1.0);
+    ///
+    /// producer.publish(2.0).unwrap();
+    /// producer.publish(3.0).unwrap();
+    /// assert_eq!(ticks.next().await, 3.0);
+    /// # };
+    /// ```
+    pub fn throttle(self, window: Duration) -> ThrottledRef<K, V> {
+        ThrottledRef {
+            clock: self.owner.clock(),
+            subscription: self,
+            window,
+            delivered: None,
+        }
+    }
+
+    /// Deliver the latest value once no publish happened for the window.
+    ///
+    /// Bursts of publishes are conflated into their last value, which is delivered after the
+    /// burst settled. A publisher which never pauses for the window is never delivered. Windows
+    /// are measured by the [`Clock`] of the map.
+    pub fn debounce(self, window: Duration) -> DebouncedRef<K, V> {
+        DebouncedRef {
+            clock: self.owner.clock(),
+            subscription: self,
+            window,
+        }
+    }
+}
+
+/// A subscription which delivers at most one value per window, see
+/// [`SubscriptionRef::throttle`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct ThrottledRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscription: SubscriptionRef<K, V>,
+    clock: Arc<dyn Clock>,
+    window: Duration,
+    /// When the previous value was delivered
+    delivered: Option<Instant>,
+}
+
+impl<K, V> ThrottledRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// A clone of the latest value, without consuming the change.
+    pub fn latest(&self) -> V {
+        self.subscription.latest()
+    }
+
+    /// Wait until a new version is published and the window since the previous delivery ended,
+    /// then return a clone of the latest value.
+    pub async fn next(&mut self) -> V {
+        let mut value = self.subscription.next().await;
+
+        if let Some(delivered) = self.delivered {
+            let end = delivered + self.window;
+
+            if self.clock.now() < end {
+                self.clock.sleep_until(end).await;
+                value = self.subscription.synchronize();
+            }
+        }
+
+        self.delivered = Some(self.clock.now());
+        value
+    }
+
+    /// The wrapped subscription, which delivers every published version again
+    pub fn into_inner(self) -> SubscriptionRef<K, V> {
+        self.subscription
+    }
+}
+
+impl<K, V> Debug for ThrottledRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThrottledRef")
+            .field("subscription", &self.subscription)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+/// A subscription which delivers values once publishes settled, see
+/// [`SubscriptionRef::debounce`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct DebouncedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscription: SubscriptionRef<K, V>,
+    clock: Arc<dyn Clock>,
+    window: Duration,
+}
+
+impl<K, V> DebouncedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// A clone of the latest value, without consuming the change.
+    pub fn latest(&self) -> V {
+        self.subscription.latest()
+    }
+
+    /// Wait until a new version is published and no further publish happened for the window,
+    /// then return a clone of the latest value.
+    pub async fn next(&mut self) -> V {
+        let mut value = self.subscription.next().await;
+
+        loop {
+            let quiet = self.clock.sleep_until(self.clock.now() + self.window);
+            let next = self.subscription.next();
+            futures::pin_mut!(next);
+
+            match future::select(next, quiet).await {
+                Either::Left((newer, _)) => value = newer,
+                Either::Right(_) => return value,
+            }
+        }
+    }
+
+    /// The wrapped subscription, which delivers every published version again
+    pub fn into_inner(self) -> SubscriptionRef<K, V> {
+        self.subscription
+    }
+}
+
+impl<K, V> Debug for DebouncedRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebouncedRef")
+            .field("subscription", &self.subscription)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ManualClock, SubscriptionMap};
+    use futures::FutureExt;
+    use std::time::Duration;
+
+    fn map(clock: &ManualClock) -> SubscriptionMap<usize, usize> {
+        SubscriptionMap::builder().clock(clock.clone()).build()
+    }
+
+    #[async_std::test]
+    async fn should_throttle_to_one_value_per_window() {
+        let clock = ManualClock::new();
+        let map = map(&clock);
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let mut throttled = subscription.throttle(Duration::from_secs(1));
+
+        producer.publish(1).unwrap();
+        assert_eq!(throttled.next().await, 1);
+
+        producer.publish(2).unwrap();
+        {
+            let next = throttled.next();
+            futures::pin_mut!(next);
+            assert!(next.as_mut().now_or_never().is_none());
+
+            producer.publish(3).unwrap();
+            clock.advance(Duration::from_secs(1));
+            assert_eq!(next.await, 3);
+        }
+
+        // the window passed without a delivery, so the next publish is delivered right away
+        clock.advance(Duration::from_secs(1));
+        producer.publish(4).unwrap();
+        assert_eq!(throttled.next().await, 4);
+    }
+
+    #[async_std::test]
+    async fn should_debounce_bursts() {
+        let clock = ManualClock::new();
+        let map = map(&clock);
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let mut debounced = subscription.debounce(Duration::from_secs(1));
+
+        producer.publish(1).unwrap();
+        let next = debounced.next();
+        futures::pin_mut!(next);
+        assert!(next.as_mut().now_or_never().is_none());
+
+        clock.advance(Duration::from_millis(500));
+        producer.publish(2).unwrap();
+        assert!(next.as_mut().now_or_never().is_none());
+
+        // the quiet period restarts with every publish
+        clock.advance(Duration::from_millis(500));
+        assert!(next.as_mut().now_or_never().is_none());
+
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(next.await, 2);
+    }
+}
@@ -0,0 +1,147 @@
+/// A zero-sized key type declared through [`topics!`](crate::topics).
+pub trait Topic {
+    /// The raw key of the topic
+    const NAME: &'static str;
+
+    /// The raw key of the topic
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+}
+
+#[doc(hidden)]
+pub const fn unique_topics(names: &[&str]) -> bool {
+    let mut i = 0;
+
+    while i < names.len() {
+        let mut j = i + 1;
+
+        while j < names.len() {
+            if str_eq(names[i], names[j]) {
+                return false;
+            }
+
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    true
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+
+        i += 1;
+    }
+
+    true
+}
+
+/// Declare typed topic constants instead of scattering raw string keys across a codebase.
+///
+/// Every topic becomes a zero-sized type which implements [`Topic`], `Display` and `Ord` and
+/// converts into `&'static str` and `String` keys. Declaring the same raw key twice within one
+/// invocation fails to compile.
+///
+/// ```
+/// # use async_subscription_map::{topics, SubscriptionMap};
+/// topics! {
+///     /// The measured temperature in degree celsius
+///     pub Temperature = "sensors/temperature";
+///     pub Humidity = "sensors/humidity";
+/// }
+///
+/// # async {
+/// let map = SubscriptionMap::<&'static str, f32>::default();
+/// let subscription = map.get_or_insert(Temperature.into(), 21.5).await.unwrap();
+/// assert_eq!(Temperature.to_string(), "sensors/temperature");
+/// # };
+/// ```
+///
+/// ```compile_fail
+/// # use async_subscription_map::topics;
+/// topics! {
+///     Temperature = "sensors/temperature";
+///     Celsius = "sensors/temperature";
+/// }
+/// ```
+#[macro_export]
+macro_rules! topics {
+    ($($(#[$meta:meta])* $vis:vis $name:ident = $topic:literal;)*) => {
+        $(
+            $(#[$meta])*
+            #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            $vis struct $name;
+
+            impl $crate::Topic for $name {
+                const NAME: &'static str = $topic;
+            }
+
+            impl ::std::fmt::Display for $name {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.write_str($topic)
+                }
+            }
+
+            impl ::std::convert::From<$name> for &'static str {
+                fn from(_: $name) -> Self {
+                    $topic
+                }
+            }
+
+            impl ::std::convert::From<$name> for ::std::string::String {
+                fn from(_: $name) -> Self {
+                    ::std::string::String::from($topic)
+                }
+            }
+        )*
+
+        const _: () = assert!(
+            $crate::topic::unique_topics(&[$($topic),*]),
+            "topics must have unique keys"
+        );
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::{unique_topics, Topic};
+    use crate::SubscriptionMap;
+
+    topics! {
+        Temperature = "sensors/temperature";
+        Humidity = "sensors/humidity";
+    }
+
+    #[test]
+    fn should_detect_duplicate_topics() {
+        assert!(unique_topics(&["a", "b", "ab"]));
+        assert!(!unique_topics(&["a", "b", "a"]));
+    }
+
+    #[async_std::test]
+    async fn should_use_topics_as_keys() {
+        let map: SubscriptionMap<String, usize> = SubscriptionMap::new();
+        let _subscription = map.get_or_insert(Temperature.into(), 0).await.unwrap();
+
+        assert_eq!(Humidity.name(), "sensors/humidity");
+        assert_eq!(Temperature.to_string(), Temperature::NAME);
+        assert!(map
+            .publish_if_changed(&Temperature.into(), 1)
+            .await
+            .unwrap());
+    }
+}
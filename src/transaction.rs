@@ -0,0 +1,72 @@
+use crate::SubscriptionMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Read the current values of several keys as one mutually consistent view.
+    ///
+    /// All publishes, including the ones through refs, are held back while the values are
+    /// read, so no publish can land between the reads of two keys. The values are passed in the
+    /// order of the keys, `None` for keys which are not present. The closure runs after the map
+    /// is released again and is free to publish.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<&'static str, i64>::default();
+    /// let _debit = map.get_or_insert("debit", -10).await.unwrap();
+    /// let _credit = map.get_or_insert("credit", 10).await.unwrap();
+    ///
+    /// let balance = map
+    ///     .read_transaction(&["debit", "credit"], |values| {
+    ///         values.iter().flatten().sum::<i64>()
+    ///     })
+    ///     .await;
+    /// assert_eq!(balance, 0);
+    /// # };
+    /// ```
+    pub async fn read_transaction<F, R>(&self, keys: &[K], read: F) -> R
+    where
+        F: FnOnce(&[Option<V>]) -> R,
+    {
+        let values: Vec<Option<V>> = {
+            let map = self.read_entries().await;
+
+            // every publish passes the gate, which can't be entered while its state is locked
+            let _publishes = self.publish_state_mut();
+
+            keys.iter()
+                .map(|key| map.get(key).map(|entry| entry.observable.latest()))
+                .collect()
+        };
+
+        read(&values)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_read_values_in_key_order() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut one = map.get_or_insert(1, 1).await.unwrap();
+        let _two = map.get_or_insert(2, 2).await.unwrap();
+
+        let values = map
+            .read_transaction(&[2, 3, 1], |values| {
+                // publishing inside of the transaction doesn't block
+                one.publish(10).unwrap();
+                values.to_vec()
+            })
+            .await;
+
+        assert_eq!(values, vec![Some(2), None, Some(1)]);
+        assert_eq!(map.peek(&1).await, Some(10));
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{EntryMeta, SubscriptionMap, SubscriptionRef};
+use std::cell::Cell;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::MutexGuard;
+use std::time::Instant;
+
+/// A value together with the metadata of the publish which produced it, see
+/// [`SubscriptionRef::updates`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Update<V> {
+    /// The published value
+    pub value: V,
+    /// The version of the value, see [`Versioned::version`](crate::Versioned::version)
+    pub version: u64,
+    /// When the value reached the subscribers, according to the [`Clock`](crate::Clock) of the
+    /// map
+    pub timestamp: Instant,
+    /// The tag of the ref which published, see
+    /// [`get_or_insert_tagged`](SubscriptionMap::get_or_insert_tagged)
+    pub producer: Option<&'static str>,
+}
+
+/// The metadata of the latest publish of an entry
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct UpdateMeta {
+    pub version: u64,
+    pub timestamp: Instant,
+    pub producer: Option<&'static str>,
+}
+
+impl UpdateMeta {
+    /// The metadata of the initial value of an entry
+    pub fn new(version: u64, timestamp: Instant) -> Self {
+        Self {
+            version,
+            timestamp,
+            producer: None,
+        }
+    }
+}
+
+fn lock<V>(meta: &EntryMeta<V>) -> MutexGuard<'_, UpdateMeta> {
+    meta.update.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl<V> EntryMeta<V> {
+    /// The version of the latest value, only consistent with it under the lock of the observable
+    pub(crate) fn version(&self) -> u64 {
+        lock(self).version
+    }
+
+    /// When the latest value was published, or the entry was created if it never was
+    pub(crate) fn updated_at(&self) -> Instant {
+        lock(self).timestamp
+    }
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Move the entry to the next version of the map for a value which reaches its subscribers.
+    ///
+    /// This runs under the lock of the observable, so readers never see a value with the
+    /// metadata of another one.
+    pub(crate) fn record_update(
+        &self,
+        meta: &EntryMeta<V>,
+        producer: Option<&'static str>,
+    ) -> UpdateMeta {
+        let update = UpdateMeta {
+            version: self.next_sequence(),
+            timestamp: self.0.config.clock.now(),
+            producer,
+        };
+
+        *lock(meta) = update;
+        update
+    }
+}
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Receive values together with the metadata of their publish instead of bare values.
+    ///
+    /// The metadata serves auditing, ordering and replication alike: versions increase with every
+    /// publish which changes the value, whether it was made through the map or a ref, and are
+    /// the same ones [`latest_versioned`](Self::latest_versioned) and
+    /// [`cdc_stream`](SubscriptionMap::cdc_stream) report.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut updates = map.get_or_insert(1, 0).await.unwrap().updates();
+    /// let initial = updates.latest();
+    ///
+    /// let mut producer = map.get_or_insert_tagged(1, 0, "importer").await.unwrap();
+    /// producer.publish(5).unwrap();
+    ///
+    /// let update = updates.next().await;
+    /// assert_eq!(update.value, 5);
+    /// assert!(update.version > initial.version);
+    /// assert_eq!(update.producer, Some("importer"));
+    /// # };
+    /// ```
+    pub fn updates(self) -> UpdateRef<K, V> {
+        UpdateRef { subscription: self }
+    }
+
+    /// The latest value with the metadata of its publish
+    pub(crate) fn latest_update(&self) -> Update<V> {
+        let latest = Cell::new(None);
+
+        // the metadata is recorded under the lock of the observable, reading both under it as
+        // well keeps them consistent
+        self.observable.clone().modify_conditional(
+            |value| {
+                let meta = *lock(&self.meta);
+
+                latest.set(Some(Update {
+                    value: value.clone(),
+                    version: meta.version,
+                    timestamp: meta.timestamp,
+                    producer: meta.producer,
+                }));
+                false
+            },
+            |_| {},
+        );
+
+        latest
+            .into_inner()
+            .expect("condition is evaluated under the lock")
+    }
+}
+
+/// A subscription which yields [`Update`]s, see [`SubscriptionRef::updates`].
+#[must_use = "entries are removed as soon as no one subscribes to them"]
+pub struct UpdateRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    subscription: SubscriptionRef<K, V>,
+}
+
+impl<K, V> UpdateRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the subscribed entry
+    pub fn key(&self) -> &K {
+        self.subscription.key()
+    }
+
+    /// The latest value with its metadata, without consuming the change.
+    pub fn latest(&self) -> Update<V> {
+        self.subscription.latest_update()
+    }
+
+    /// Wait until a new version is published and return the latest value with its metadata.
+    ///
+    /// A publish which happens right after the wakeup is returned right away and yielded once
+    /// more with the same version by the following call.
+    pub async fn next(&mut self) -> Update<V> {
+        self.subscription.next().await;
+        self.latest()
+    }
+
+    /// Skip any pending updates and return the latest value with its metadata.
+    pub fn synchronize(&mut self) -> Update<V> {
+        self.subscription.synchronize();
+        self.latest()
+    }
+}
+
+impl<K, V> Debug for UpdateRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UpdateRef")
+            .field("subscription", &self.subscription)
+            .field("version", &lock(&self.subscription.meta).version)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{ManualClock, SubscriptionMap};
+    use std::time::Duration;
+
+    #[async_std::test]
+    async fn should_attach_metadata_to_updates() {
+        let clock = ManualClock::new();
+        let map = SubscriptionMap::<usize, usize>::builder()
+            .clock(clock.clone())
+            .build();
+        let mut updates = map.get_or_insert(1, 0).await.unwrap().updates();
+        let initial = updates.latest();
+
+        clock.advance(Duration::from_secs(1));
+        assert!(!map.publish_if_changed(&1, 0).await.unwrap());
+        map.publish_if_changed(&1, 1).await.unwrap();
+
+        let update = updates.next().await;
+        assert_eq!((update.value, update.producer), (1, None));
+        assert!(update.version > initial.version);
+        assert_eq!(update.timestamp, initial.timestamp + Duration::from_secs(1));
+
+        let second = map.get_or_insert(1, 0).await.unwrap().updates();
+        assert_eq!(second.latest(), update);
+    }
+
+    #[async_std::test]
+    async fn should_share_versions_with_versioned_values() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        let updates = map.get_or_insert(1, 0).await.unwrap().updates();
+
+        // paused publishes are recorded once they reach the subscribers
+        map.pause();
+        producer.publish(1).unwrap();
+        assert_eq!(updates.latest().value, 0);
+        map.resume().await;
+
+        let update = updates.latest();
+        let versioned = producer.latest_versioned();
+        assert_eq!(
+            (update.value, update.version),
+            (versioned.value, versioned.version)
+        );
+    }
+}
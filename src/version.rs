@@ -0,0 +1,125 @@
+use crate::{SubscriptionMap, SubscriptionRef};
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::atomic::Ordering;
+
+/// A value together with the sequence number of the publish which produced it, see
+/// [`SubscriptionRef::latest_versioned`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Versioned<V> {
+    /// The published value
+    pub value: V,
+    /// Increases with every publish and insertion across all keys of the map, so a gap between
+    /// consecutive versions of a key doesn't mean updates were missed, but a repeated version
+    /// means the value is unchanged
+    pub version: u64,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The next version of the map, new entries start out with one as well
+    pub(crate) fn next_sequence(&self) -> u64 {
+        self.0.sequence.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The latest value with its version, without consuming the change.
+    ///
+    /// Versions increase with every publish, consumers compare them to detect updates which were
+    /// conflated while they lagged behind or to make downstream writes idempotent.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let mut subscription = map.get_or_insert(1, 0).await.unwrap();
+    /// let initial = subscription.latest_versioned();
+    ///
+    /// map.publish_if_changed(&1, 1).await.unwrap();
+    /// let next = subscription.next_versioned().await;
+    /// assert_eq!(next.value, 1);
+    /// assert!(next.version > initial.version);
+    /// # };
+    /// ```
+    pub fn latest_versioned(&self) -> Versioned<V> {
+        let update = self.latest_update();
+
+        Versioned {
+            value: update.value,
+            version: update.version,
+        }
+    }
+
+    /// Wait until a new version is published and return the latest value with its version.
+    ///
+    /// A publish which happens right after the wakeup is returned right away and yielded once
+    /// more with the same version by the following call.
+    pub async fn next_versioned(&mut self) -> Versioned<V> {
+        self.next().await;
+        self.latest_versioned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_advance_versions_with_publishes() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let mut producer = map.get_or_insert(1, 0).await.unwrap();
+        let other = map.get_or_insert(2, 0).await.unwrap();
+        let initial = producer.latest_versioned();
+
+        producer.publish(1).unwrap();
+        let first = producer.next_versioned().await;
+        assert_eq!(first.value, 1);
+        assert!(first.version > initial.version);
+        assert!(first.version > other.latest_versioned().version);
+
+        // unchanged values don't advance the version
+        assert!(!producer.publish_if_changed(1).unwrap());
+        assert_eq!(producer.latest_versioned(), first);
+
+        map.pause();
+        producer.publish(2).unwrap();
+        assert_eq!(producer.latest_versioned(), first);
+        map.resume().await;
+        assert!(producer.next_versioned().await.version > first.version);
+    }
+
+    #[async_std::test]
+    async fn should_keep_versions_increasing_across_recreation() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let before = map.get_or_insert(1, 0).await.unwrap().latest_versioned();
+        let after = map.get_or_insert(1, 0).await.unwrap().latest_versioned();
+        assert!(after.version > before.version);
+    }
+
+    #[async_std::test]
+    async fn should_keep_versions_of_swapped_entries() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let other: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let swapped_out = map.get_or_insert(1, 0).await.unwrap();
+        let mut swapped_in = other.get_or_insert(1, 0).await.unwrap();
+        swapped_in.publish(1).unwrap();
+
+        map.swap_contents(&other);
+        // removing the entry which moved to the other map leaves the one of this map alone
+        drop(swapped_out);
+
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        assert_eq!(
+            subscription.latest_versioned(),
+            swapped_in.latest_versioned()
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{Error, ReadOnlyRef, SubscriptionMap, SubscriptionRef};
+use anyhow::Context;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::Arc;
+
+/// The kind of access a [`RestrictedView`] asks its policy for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Access {
+    /// Obtaining a subscription to the key
+    Subscribe,
+    /// Publishing or modifying the value of the key
+    Publish,
+}
+
+type Policy<K> = Arc<dyn Fn(&K, Access) -> bool + Send + Sync>;
+
+/// A handle to a subscription map which only permits operations that are accepted by a policy.
+///
+/// This is useful for sandboxing plugins loaded into the same process, they can be handed a view
+/// which only sees their own part of the key space instead of the whole map.
+///
+/// A [`SubscriptionRef`] grants write access to its observable, hence obtaining one through a view
+/// requires both [`Access::Subscribe`] and [`Access::Publish`] for the key. Keys which may only be
+/// observed can be subscribed to through [`RestrictedView::subscribe`].
+///
+/// ```
+/// # use async_subscription_map::SubscriptionMap;
+/// # async {
+/// let map = SubscriptionMap::<&'static str, usize>::default();
+/// let view = map.restricted_view(|key, _| key.starts_with("plugin/"));
+///
+/// assert!(view.get_or_insert("plugin/state", 0).await.is_ok());
+/// assert!(view.get_or_insert("core/state", 0).await.is_err());
+/// # };
+/// ```
+#[derive(Clone)]
+pub struct RestrictedView<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    map: SubscriptionMap<K, V>,
+    policy: Policy<K>,
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Create a handle to this map which filters every operation through the provided policy.
+    pub fn restricted_view<P>(&self, policy: P) -> RestrictedView<K, V>
+    where
+        P: Fn(&K, Access) -> bool + Send + Sync + 'static,
+    {
+        RestrictedView {
+            map: self.clone(),
+            policy: Arc::new(policy),
+        }
+    }
+}
+
+impl<K, V> RestrictedView<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn check(&self, key: &K, access: Access) -> anyhow::Result<()> {
+        if (self.policy)(key, access) {
+            return Ok(());
+        }
+
+        Err(Error::AccessDenied(access))
+            .with_context(|| format!("restricted key {:?}", self.map.redacted_key(key)))
+    }
+
+    /// Same as [`SubscriptionMap::get_or_insert`] if the policy permits subscribing and publishing.
+    pub async fn get_or_insert(&self, key: K, value: V) -> anyhow::Result<SubscriptionRef<K, V>> {
+        self.check(&key, Access::Subscribe)?;
+        self.check(&key, Access::Publish)?;
+
+        self.map.get_or_insert(key, value).await
+    }
+
+    /// Same as [`SubscriptionMap::get_or_insert_read_only`] if the policy permits subscribing.
+    pub async fn subscribe(&self, key: K, value: V) -> anyhow::Result<ReadOnlyRef<K, V>> {
+        self.check(&key, Access::Subscribe)?;
+
+        self.map.get_or_insert_read_only(key, value).await
+    }
+
+    /// Same as [`SubscriptionMap::try_modify_and_publish`] if the policy permits publishing.
+    pub async fn try_modify_and_publish<F, R, E>(&self, key: &K, modify: F) -> anyhow::Result<R>
+    where
+        F: FnOnce(&mut V) -> Result<R, E>,
+        E: Into<anyhow::Error>,
+    {
+        self.check(key, Access::Publish)?;
+        self.map.try_modify_and_publish(key, modify).await
+    }
+
+    /// Same as [`SubscriptionMap::publish`] if the policy permits publishing.
+    pub async fn publish(&self, key: &K, value: V) -> anyhow::Result<()> {
+        self.check(key, Access::Publish)?;
+        self.map.publish(key, value).await
+    }
+}
+
+impl<K, V> RestrictedView<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug + Eq,
+{
+    /// Same as [`SubscriptionMap::publish_if_changed`] if the policy permits publishing.
+    pub async fn publish_if_changed(&self, key: &K, value: V) -> anyhow::Result<bool> {
+        self.check(key, Access::Publish)?;
+        self.map.publish_if_changed(key, value).await
+    }
+
+    /// Same as [`SubscriptionMap::modify_and_publish`] if the policy permits publishing.
+    pub async fn modify_and_publish<F, R>(&self, key: &K, modify: F) -> anyhow::Result<()>
+    where
+        F: FnOnce(&mut V) -> R,
+    {
+        self.check(key, Access::Publish)?;
+        self.map.modify_and_publish(key, modify).await
+    }
+}
+
+impl<K, V> Debug for RestrictedView<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    // the entries of the map are left out, they may not be visible through the view
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestrictedView").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Access, Error, SubscriptionMap};
+
+    #[async_std::test]
+    async fn should_reject_keys_outside_of_policy() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let view = map.restricted_view(|key, _| *key < 10);
+
+        let err = view.get_or_insert(10, 0).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::AccessDenied(Access::Subscribe))
+        );
+
+        let subscription = map.get_or_insert(10, 0).await.unwrap();
+        assert!(view.publish_if_changed(&10, 1).await.is_err());
+        assert_eq!(subscription.latest(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_distinguish_subscribe_and_publish() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let view = map.restricted_view(|_, access| access == Access::Subscribe);
+
+        let err = view.get_or_insert(1, 0).await.unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::AccessDenied(Access::Publish))
+        );
+        assert!(view.subscribe(1, 0).await.is_ok());
+
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        assert!(view.modify_and_publish(&1, |v| *v = 1).await.is_err());
+        assert_eq!(subscription.latest(), 0);
+    }
+
+    #[async_std::test]
+    async fn should_forward_permitted_operations() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let view = map.restricted_view(|_, _| true);
+
+        let mut subscription = view.get_or_insert(1, 0).await.unwrap();
+        assert!(view.publish_if_changed(&1, 1).await.unwrap());
+        assert_eq!(subscription.next().await, 1);
+    }
+
+    #[async_std::test]
+    async fn should_publish_values_without_eq() {
+        let map: SubscriptionMap<usize, f64> = SubscriptionMap::new();
+        let view = map.restricted_view(|key, _| *key < 10);
+        let mut subscription = view.get_or_insert(1, 0.0).await.unwrap();
+
+        view.publish(&1, 0.5).await.unwrap();
+        assert_eq!(subscription.next().await, 0.5);
+        assert!(view.publish(&10, 0.5).await.is_err());
+
+        let doubled = view
+            .try_modify_and_publish(&1, |v| {
+                *v *= 2.0;
+                Ok::<_, std::fmt::Error>(*v)
+            })
+            .await
+            .unwrap();
+        assert_eq!(doubled, 1.0);
+    }
+
+    #[async_std::test]
+    async fn should_not_debug_print_the_map() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        map.pin(4242, 0).await.unwrap();
+
+        let view = map.restricted_view(|_, _| false);
+        assert_eq!(format!("{:?}", view), "RestrictedView { .. }");
+    }
+}
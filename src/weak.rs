@@ -0,0 +1,162 @@
+use crate::{Entries, SubscriptionMap, SubscriptionRef};
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, Weak};
+
+impl<K, V> SubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// Create a handle to the entry which doesn't keep it alive.
+    ///
+    /// Monitoring and debugging code can hold on to it and observe the entry opportunistically
+    /// through [`upgrade`](WeakSubscriptionRef::upgrade), without preventing the cleanup once
+    /// everyone else is done with the entry.
+    ///
+    /// ```
+    /// # use async_subscription_map::SubscriptionMap;
+    /// # async {
+    /// let map = SubscriptionMap::<usize, usize>::default();
+    /// let subscription = map.get_or_insert(1, 5).await.unwrap();
+    /// let weak = subscription.downgrade();
+    ///
+    /// assert_eq!(weak.upgrade().await.unwrap().latest(), 5);
+    ///
+    /// drop(subscription);
+    /// assert!(weak.upgrade().await.is_none());
+    /// # };
+    /// ```
+    pub fn downgrade(&self) -> WeakSubscriptionRef<K, V> {
+        WeakSubscriptionRef {
+            key: self.key.clone(),
+            owner: self.owner.internal(),
+            entries: Arc::downgrade(&self.entries),
+            rc: Arc::downgrade(&self.rc),
+        }
+    }
+}
+
+/// A handle to an entry which doesn't keep it alive, see [`SubscriptionRef::downgrade`].
+pub struct WeakSubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    key: K,
+    owner: SubscriptionMap<K, V>,
+    entries: Weak<Entries<K, V>>,
+    /// Dropped together with the entry and its refs, identifies the entry across re-insertions
+    rc: Weak<AtomicUsize>,
+}
+
+impl<K, V> WeakSubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    /// The key of the entry
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Check if the entry may still exist, an upgrade fails for sure once this returns `false`
+    pub fn is_alive(&self) -> bool {
+        self.rc.strong_count() > 0
+    }
+
+    /// Subscribe to the entry again if it still exists.
+    ///
+    /// This only succeeds for the very entry the handle was created from, an entry which was
+    /// inserted at the same key after the cleanup is a different one. Like
+    /// [`SubscriptionMap::get`] it returns `None` once the map is
+    /// [draining](SubscriptionMap::drain).
+    pub async fn upgrade(&self) -> Option<SubscriptionRef<K, V>> {
+        let entries = self.entries.upgrade()?;
+        let rc = self.rc.upgrade()?;
+        let mut map = self.owner.lock_entries(&entries).await;
+
+        if self.owner.is_draining() {
+            return None;
+        }
+
+        let entry = map
+            .get_mut(&self.key)
+            .filter(|entry| Arc::ptr_eq(&entry.rc, &rc))?;
+        entry.last_used = self.owner.0.config.clock.now();
+
+        Some(SubscriptionRef::new(
+            self.key.clone(),
+            self.owner.internal(),
+            entries.clone(),
+            entry,
+        ))
+    }
+}
+
+impl<K, V> Clone for WeakSubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            owner: self.owner.internal(),
+            entries: self.entries.clone(),
+            rc: self.rc.clone(),
+        }
+    }
+}
+
+impl<K, V> Debug for WeakSubscriptionRef<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WeakSubscriptionRef")
+            .field("key", &self.owner.redacted_key(&self.key))
+            .field("alive", &self.is_alive())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+
+    #[async_std::test]
+    async fn should_not_keep_entry_alive() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let weak = subscription.downgrade();
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 1);
+
+        let upgraded = weak.upgrade().await.unwrap();
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 2);
+
+        drop((subscription, upgraded));
+        assert_eq!(map.entries_snapshot().await.len(), 0);
+        assert!(!weak.is_alive());
+
+        // a new entry at the same key isn't the one the handle was created from
+        let _new = map.get_or_insert(1, 0).await.unwrap();
+        assert!(weak.upgrade().await.is_none());
+    }
+
+    #[async_std::test]
+    async fn should_not_upgrade_while_draining() {
+        let map: SubscriptionMap<usize, usize> = SubscriptionMap::new();
+        let subscription = map.get_or_insert(1, 0).await.unwrap();
+        let weak = subscription.downgrade();
+
+        map.drain();
+        assert!(weak.upgrade().await.is_none());
+        assert_eq!(map.entries_snapshot().await[&1].rc(), 1);
+
+        map.undrain();
+        assert!(weak.upgrade().await.is_some());
+    }
+}
@@ -0,0 +1,414 @@
+//! Forwarding of map events to an HTTP endpoint, so low rate external systems such as alerting or
+//! audit logs can observe a map without embedding a subscriber task.
+//!
+//! A [`Webhook`] POSTs the insertions, publishes and removals of the keys under its prefixes in
+//! batches, one event per line of a `text/plain` body:
+//!
+//! ```text
+//! inserted <key>
+//! published <key> <version> <value>
+//! removed <key>
+//! ```
+//!
+//! Keys and values are formatted through the [redactors](crate::Redactor) of the map. Failed
+//! deliveries are retried with a growing backoff and dropped once the attempts are exhausted.
+//!
+//! ```
+//! # use async_subscription_map::SubscriptionMap;
+//! # use std::time::Duration;
+//! let map = SubscriptionMap::<&'static str, usize>::default();
+//! let webhook = map
+//!     .webhook(([127, 0, 0, 1], 8080), "/audit")
+//!     .prefix("orders/")
+//!     .retries(5, Duration::from_secs(1));
+//!
+//! async_std::task::spawn(webhook.run());
+//! ```
+use crate::{diag, KeyMatcher, KeyPrefix, MapEvent, SubscriptionMap, Update};
+use anyhow::{bail, Context};
+use async_io::Async;
+use futures::stream::{self, StreamExt};
+use futures::{AsyncReadExt, AsyncWriteExt};
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::hash::Hash;
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// The longest response head the webhook reads to find the status
+const MAX_RESPONSE_HEAD: usize = 8 * 1024;
+
+/// Selects the keys under any of the prefixes, or all keys without prefixes
+struct Prefixes<K>(Vec<K>);
+
+impl<K> KeyMatcher<K> for Prefixes<K>
+where
+    K: KeyPrefix,
+{
+    fn matches(&self, key: &K) -> bool {
+        under_any(&self.0, key)
+    }
+}
+
+fn under_any<K>(prefixes: &[K], key: &K) -> bool
+where
+    K: KeyPrefix,
+{
+    prefixes.is_empty() || prefixes.iter().any(|prefix| key.starts_with(prefix))
+}
+
+/// An event the webhook received from the map
+enum Event<K, V> {
+    Lifecycle(MapEvent<K>),
+    Published(K, Update<V>),
+}
+
+impl<K, V> SubscriptionMap<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord + KeyPrefix + Send + Sync + 'static,
+    V: Clone + Debug,
+{
+    /// Forward the events of the map to the path of an HTTP endpoint, see the [module
+    /// documentation](crate::webhook).
+    ///
+    /// All keys are forwarded unless [prefixes](Webhook::prefix) are configured. Nothing is sent
+    /// until the webhook [runs](Webhook::run).
+    pub fn webhook<A>(&self, endpoint: A, path: &str) -> Webhook<K, V>
+    where
+        A: Into<SocketAddr>,
+    {
+        Webhook {
+            owner: self.internal(),
+            endpoint: endpoint.into(),
+            path: path.to_string(),
+            prefixes: Vec::new(),
+            max_batch: 64,
+            retries: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Posts the events of a map to an HTTP endpoint, see [`SubscriptionMap::webhook`].
+#[must_use = "the webhook does nothing unless it runs"]
+pub struct Webhook<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    owner: SubscriptionMap<K, V>,
+    endpoint: SocketAddr,
+    path: String,
+    prefixes: Vec<K>,
+    max_batch: usize,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl<K, V> Webhook<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord + KeyPrefix + Send + Sync + 'static,
+    V: Clone + Debug,
+{
+    /// Forward the events of keys under the prefix, may be called multiple times
+    pub fn prefix(mut self, prefix: K) -> Self {
+        self.prefixes.push(prefix);
+        self
+    }
+
+    /// The largest amount of events in a single request, defaults to 64
+    pub fn max_batch(mut self, events: usize) -> Self {
+        self.max_batch = events.max(1);
+        self
+    }
+
+    /// Retry failed deliveries, the backoff doubles with every attempt. Defaults to three
+    /// retries starting at 100ms.
+    pub fn retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Forward events until the map is closed.
+    ///
+    /// Events are captured from this call on, even before the returned future is polled. Every
+    /// request contains all events which are ready, up to the [batch size](Self::max_batch).
+    /// Like [`maintain`](SubscriptionMap::maintain) this doesn't spawn tasks and doesn't keep the
+    /// map open. Publishes are captured like a [`cdc_stream`](SubscriptionMap::cdc_stream) does,
+    /// so none of them is conflated.
+    pub fn run(self) -> impl Future<Output = ()> {
+        let lifecycle = self.owner.events().map(Event::Lifecycle);
+        let published = self
+            .owner
+            .cdc_stream(Prefixes(self.prefixes.clone()))
+            .map(|(key, update)| Event::Published(key, update));
+
+        let mut batches = stream::select(lifecycle, published).ready_chunks(self.max_batch);
+
+        async move {
+            while let Some(batch) = batches.next().await {
+                let mut closed = false;
+                let mut body = String::new();
+
+                for event in batch {
+                    match event {
+                        Event::Lifecycle(MapEvent::Closed) => closed = true,
+                        event => self.write_event(&mut body, event),
+                    }
+                }
+
+                if !body.is_empty() {
+                    self.deliver(&body).await;
+                }
+
+                if closed {
+                    break;
+                }
+            }
+
+            diag::debug!("subscription map closed, webhook finished");
+        }
+    }
+
+    fn write_event(&self, body: &mut String, event: Event<K, V>) {
+        use std::fmt::Write;
+
+        let owner = &self.owner;
+
+        // writing to a string never fails
+        let _ = match event {
+            Event::Lifecycle(MapEvent::Inserted(key)) if under_any(&self.prefixes, &key) => {
+                writeln!(body, "inserted {:?}", owner.redacted_key(&key))
+            }
+            Event::Lifecycle(MapEvent::Removed(key)) if under_any(&self.prefixes, &key) => {
+                writeln!(body, "removed {:?}", owner.redacted_key(&key))
+            }
+            Event::Lifecycle(_) => Ok(()),
+            Event::Published(key, update) => writeln!(
+                body,
+                "published {:?} {} {:?}",
+                owner.redacted_key(&key),
+                update.version,
+                owner.redacted(&update.value)
+            ),
+        };
+    }
+
+    /// Post the body, retrying with backoff until it is accepted or the attempts are exhausted
+    async fn deliver(&self, body: &str) {
+        let clock = self.owner.clock();
+        let mut backoff = self.backoff;
+
+        for attempt in 0..=self.retries {
+            let e = match self.post(body).await {
+                Ok(()) => return,
+                Err(e) => e,
+            };
+
+            if attempt == self.retries {
+                diag::warning!(
+                    "dropping webhook events after {} attempts: {:#}",
+                    attempt + 1,
+                    e
+                );
+                return;
+            }
+
+            diag::debug!("retrying failed webhook delivery: {:#}", e);
+
+            // backoffs which can't be represented retry right away
+            if let Some(deadline) = clock.now().checked_add(backoff) {
+                clock.sleep_until(deadline).await;
+            }
+
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+
+    async fn post(&self, body: &str) -> anyhow::Result<()> {
+        let mut stream = Async::<TcpStream>::connect(self.endpoint)
+            .await
+            .with_context(|| format!("unable to connect to {}", self.endpoint))?;
+
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.endpoint,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut head = Vec::new();
+        let mut chunk = [0; 512];
+
+        while !head.windows(2).any(|window| window == b"\r\n") {
+            if head.len() > MAX_RESPONSE_HEAD {
+                bail!("response head exceeds {} bytes", MAX_RESPONSE_HEAD);
+            }
+
+            match stream.read(&mut chunk).await? {
+                0 => bail!("connection closed before the response status"),
+                read => head.extend_from_slice(&chunk[..read]),
+            }
+        }
+
+        let status = String::from_utf8_lossy(&head)
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok())
+            .context("malformed response status line")?;
+
+        if !(200..300).contains(&status) {
+            bail!("endpoint responded with status {}", status);
+        }
+
+        Ok(())
+    }
+}
+
+impl<K, V> Debug for Webhook<K, V>
+where
+    K: Clone + Debug + Eq + Hash + Ord,
+    V: Clone + Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Webhook")
+            .field("endpoint", &self.endpoint)
+            .field("path", &self.path)
+            .field("prefixes", &self.prefixes.len())
+            .field("max_batch", &self.max_batch)
+            .field("retries", &self.retries)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::SubscriptionMap;
+    use async_io::Async;
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// An endpoint which records the request bodies and fails the first `failures` requests
+    fn endpoint(failures: usize) -> (SocketAddr, Arc<Mutex<Vec<String>>>) {
+        let listener = Async::<TcpListener>::bind(([127, 0, 0, 1], 0)).unwrap();
+        let addr = listener.get_ref().local_addr().unwrap();
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+
+        async_std::task::spawn({
+            let bodies = bodies.clone();
+
+            async move {
+                for served in 0.. {
+                    let (mut stream, _) = listener.accept().await.unwrap();
+                    let mut request = String::new();
+                    let mut chunk = [0; 512];
+
+                    let body = loop {
+                        let read = stream.read(&mut chunk).await.unwrap();
+                        request.push_str(std::str::from_utf8(&chunk[..read]).unwrap());
+
+                        if let Some((head, body)) = request.split_once("\r\n\r\n") {
+                            let length = head
+                                .lines()
+                                .find_map(|line| line.strip_prefix("Content-Length: "))
+                                .unwrap();
+
+                            if body.len() == length.parse::<usize>().unwrap() {
+                                break body;
+                            }
+                        }
+                    };
+                    bodies.lock().unwrap().push(body.to_string());
+
+                    let status = match served < failures {
+                        true => "500 Internal Server Error",
+                        false => "200 OK",
+                    };
+                    let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status);
+                    stream.write_all(response.as_bytes()).await.ok();
+                }
+            }
+        });
+
+        (addr, bodies)
+    }
+
+    /// Wait until the endpoint received the amount of requests
+    async fn received(bodies: &Mutex<Vec<String>>, requests: usize) -> Vec<String> {
+        // the endpoint runs on another task in real time
+        for _ in 0..1000 {
+            let received = bodies.lock().unwrap().clone();
+
+            if received.len() >= requests {
+                return received;
+            }
+
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+
+        panic!("endpoint did not receive {} requests", requests);
+    }
+
+    /// Wait until the endpoint received the amount of events, regardless of their batching
+    async fn events(bodies: &Mutex<Vec<String>>, events: usize) -> Vec<String> {
+        for _ in 0..1000 {
+            let body = bodies.lock().unwrap().concat();
+
+            if body.lines().count() >= events {
+                return body.lines().map(str::to_string).collect();
+            }
+
+            async_std::task::sleep(Duration::from_millis(1)).await;
+        }
+
+        panic!("endpoint did not receive {} events", events);
+    }
+
+    #[async_std::test]
+    async fn should_post_events_under_prefixes() {
+        let (addr, bodies) = endpoint(0);
+        let map = SubscriptionMap::<&'static str, usize>::new();
+        let webhook = async_std::task::spawn(map.webhook(addr, "/events").prefix("a/").run());
+
+        let mut other = map.get_or_insert("b/1", 0).await.unwrap();
+        other.publish(1).unwrap();
+
+        let mut subscription = map.get_or_insert("a/1", 0).await.unwrap();
+        assert_eq!(events(&bodies, 1).await, vec!["inserted \"a/1\""]);
+
+        subscription.publish(1).unwrap();
+        let published = events(&bodies, 2).await.remove(1);
+        assert!(published.starts_with("published \"a/1\" ") && published.ends_with(" 1"));
+
+        drop(subscription);
+        assert_eq!(events(&bodies, 3).await[2], "removed \"a/1\"");
+        assert_eq!(events(&bodies, 3).await.len(), 3);
+
+        drop(map);
+        async_std::future::timeout(Duration::from_secs(1), webhook)
+            .await
+            .expect("webhook kept running after the map was closed");
+    }
+
+    #[async_std::test]
+    async fn should_retry_failed_deliveries() {
+        let (addr, bodies) = endpoint(1);
+        let map = SubscriptionMap::<&'static str, usize>::new();
+        let webhook = map
+            .webhook(addr, "/events")
+            .retries(1, Duration::from_millis(1));
+        async_std::task::spawn(webhook.run());
+
+        map.pin("a/1", 0).await.unwrap();
+
+        let received = received(&bodies, 2).await;
+        assert_eq!(received[0], "inserted \"a/1\"\n");
+        assert_eq!(received[0], received[1]);
+    }
+}